@@ -6,6 +6,8 @@ declare_id!("Em3rP8zzX1FXFKCs1AfyxBjQbKwP3CG6MEhzY9XczPja");
 pub mod programs_policyvault {
     use super::*;
 
+    /// Stateless: takes no accounts and writes nothing, so a deployment has no
+    /// state to migrate. See this workspace's README for closing it.
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         msg!("Greetings from: {:?}", ctx.program_id);
         Ok(())
@@ -402,6 +402,14 @@ export default function DemoApp() {
         allowedRecipientOption = pk
       }
 
+      // Keep the policy's day offset; the demo has no control for it.
+      const policyAcct = (await (
+        program as unknown as {
+          account: { policy: { fetch: (pk: PublicKey) => Promise<unknown> } }
+        }
+      ).account.policy.fetch(policy)) as { dayOffsetSeconds?: number; day_offset_seconds?: number }
+      const dayOffsetSeconds = policyAcct.dayOffsetSeconds ?? policyAcct.day_offset_seconds ?? 0
+
       const sig = await program.methods
         .setPolicyAdvanced(
           new BN(lamports(dailyBudgetSol)),
@@ -411,6 +419,7 @@ export default function DemoApp() {
           allowlistEnabled,
           allowedRecipientOption,
           new BN(lamports(perRecipientCapSol)),
+          dayOffsetSeconds,
         )
         .accounts({ policy, vault, authority: owner })
         .rpc()
@@ -17,6 +17,36 @@ export const REASON_CODES = {
   PAUSED: 5,
   RECIPIENT_NOT_ALLOWED: 6,
   RECIPIENT_CAP_EXCEEDED: 7,
+  RECIPIENT_COUNT_EXCEEDED: 8,
+  MAINTENANCE: 9,
+  HOOK_REJECTED: 10,
+  SLOT_ALREADY_SPENT: 11,
+  AMOUNT_NOT_ALLOWED: 12,
+  RECIPIENT_NOT_REGISTERED: 13,
+  MONTHLY_BUDGET_EXCEEDED: 14,
+  ALLOWANCE_FROZEN: 15,
+  AGENT_RESERVE_VIOLATION: 16,
+  AGENT_WEEKLY_BUDGET: 17,
+  RELEASE_SCHEDULE: 18,
+  OWNER_GONE: 19,
+  RECIPIENT_COOLDOWN: 20,
+  BAD_RECIPIENT_UNIT: 21,
+  WEEKLY_EXCEEDED: 22,
+  RECIPIENT_IS_PROGRAM: 23,
+  RESERVE_PROTECTED: 24,
+  AGENT_BUDGET_EXCEEDED: 25,
+  BACKOFF: 26,
+  REGION_CAP: 27,
+  OPS_BUDGET_EXCEEDED: 28,
+  INVALID_RECIPIENT: 29,
+  RECIPIENT_BLOCKED: 30,
+  COUNT_LIMIT: 31,
+  APPROVAL_REQUIRED: 32,
+  OUTSIDE_WINDOW: 33,
+  STALE_PRICE: 34,
+  RECIPIENT_LIFETIME_CAP: 35,
+  TIMELOCK_CANCELLED: 36,
+  APPROVAL_UNAVAILABLE: 37,
 } as const
 
 export const REASON_LABELS: Record<number, string> = {
@@ -27,6 +57,36 @@ export const REASON_LABELS: Record<number, string> = {
   5: 'PAUSED',
   6: 'RECIPIENT_NOT_ALLOWED',
   7: 'RECIPIENT_CAP_EXCEEDED',
+  8: 'RECIPIENT_COUNT_EXCEEDED',
+  9: 'MAINTENANCE',
+  10: 'HOOK_REJECTED',
+  11: 'SLOT_ALREADY_SPENT',
+  12: 'AMOUNT_NOT_ALLOWED',
+  13: 'RECIPIENT_NOT_REGISTERED',
+  14: 'MONTHLY_BUDGET_EXCEEDED',
+  15: 'ALLOWANCE_FROZEN',
+  16: 'AGENT_RESERVE_VIOLATION',
+  17: 'AGENT_WEEKLY_BUDGET',
+  18: 'RELEASE_SCHEDULE',
+  19: 'OWNER_GONE',
+  20: 'RECIPIENT_COOLDOWN',
+  21: 'BAD_RECIPIENT_UNIT',
+  22: 'WEEKLY_EXCEEDED',
+  23: 'RECIPIENT_IS_PROGRAM',
+  24: 'RESERVE_PROTECTED',
+  25: 'AGENT_BUDGET_EXCEEDED',
+  26: 'BACKOFF',
+  27: 'REGION_CAP',
+  28: 'OPS_BUDGET_EXCEEDED',
+  29: 'INVALID_RECIPIENT',
+  30: 'RECIPIENT_BLOCKED',
+  31: 'COUNT_LIMIT',
+  32: 'APPROVAL_REQUIRED',
+  33: 'OUTSIDE_WINDOW',
+  34: 'STALE_PRICE',
+  35: 'RECIPIENT_LIFETIME_CAP',
+  36: 'TIMELOCK_CANCELLED',
+  37: 'APPROVAL_UNAVAILABLE',
 }
 
 /* ------------------------------------------------------------------ */
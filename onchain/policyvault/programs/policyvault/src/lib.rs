@@ -1,17 +1,199 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{get_return_data, invoke};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, TransferChecked};
+use solana_sha256_hasher::hashv;
 
 declare_id!("DiWRnGf1JpqZrL8n9dUA9bUaJ4ruBVvmmKBcrdp7tJLD");
 
-// ── reason codes ──
+// ── reason codes (also in the IDL's constants; see `reason_code_str`) ──
+#[constant]
 pub const REASON_OK: u16 = 1;
+#[constant]
 pub const REASON_BUDGET_EXCEEDED: u16 = 2;
+#[constant]
 pub const REASON_COOLDOWN: u16 = 3;
+#[constant]
 pub const REASON_INVALID_AMOUNT: u16 = 4;
+#[constant]
 pub const REASON_PAUSED: u16 = 5;
+#[constant]
 pub const REASON_RECIPIENT_NOT_ALLOWED: u16 = 6;
+#[constant]
 pub const REASON_RECIPIENT_CAP_EXCEEDED: u16 = 7;
+#[constant]
+pub const REASON_RECIPIENT_COUNT_EXCEEDED: u16 = 8;
+#[constant]
+pub const REASON_MAINTENANCE: u16 = 9;
+#[constant]
+pub const REASON_HOOK_REJECTED: u16 = 10;
+#[constant]
+pub const REASON_SLOT_ALREADY_SPENT: u16 = 11;
+#[constant]
+pub const REASON_AMOUNT_NOT_ALLOWED: u16 = 12;
+#[constant]
+pub const REASON_RECIPIENT_NOT_REGISTERED: u16 = 13;
+#[constant]
+pub const REASON_MONTHLY_BUDGET_EXCEEDED: u16 = 14;
+#[constant]
+pub const REASON_ALLOWANCE_FROZEN: u16 = 15;
+#[constant]
+pub const REASON_AGENT_RESERVE_VIOLATION: u16 = 16;
+#[constant]
+pub const REASON_AGENT_WEEKLY_BUDGET: u16 = 17;
+#[constant]
+pub const REASON_RELEASE_SCHEDULE: u16 = 18;
+#[constant]
+pub const REASON_OWNER_GONE: u16 = 19;
+#[constant]
+pub const REASON_RECIPIENT_COOLDOWN: u16 = 20;
+#[constant]
+pub const REASON_BAD_RECIPIENT_UNIT: u16 = 21;
+#[constant]
+pub const REASON_WEEKLY_EXCEEDED: u16 = 22;
+#[constant]
+pub const REASON_RECIPIENT_IS_PROGRAM: u16 = 23;
+#[constant]
+pub const REASON_RESERVE_PROTECTED: u16 = 24;
+#[constant]
+pub const REASON_AGENT_BUDGET_EXCEEDED: u16 = 25;
+#[constant]
+pub const REASON_BACKOFF: u16 = 26;
+#[constant]
+pub const REASON_REGION_CAP: u16 = 27;
+#[constant]
+pub const REASON_OPS_BUDGET_EXCEEDED: u16 = 28;
+#[constant]
+pub const REASON_INVALID_RECIPIENT: u16 = 29;
+#[constant]
+pub const REASON_RECIPIENT_BLOCKED: u16 = 30;
+#[constant]
+pub const REASON_COUNT_LIMIT: u16 = 31;
+#[constant]
+pub const REASON_APPROVAL_REQUIRED: u16 = 32;
+#[constant]
+pub const REASON_OUTSIDE_WINDOW: u16 = 33;
+#[constant]
+pub const REASON_STALE_PRICE: u16 = 34;
+#[constant]
+pub const REASON_RECIPIENT_LIFETIME_CAP: u16 = 35;
+/// Not a denial: the AuditEvent `cancel_timelock` writes for a cancelled claim.
+#[constant]
+pub const REASON_TIMELOCK_CANCELLED: u16 = 36;
+/// The spend needs the authority's approval, but its path cannot queue a
+/// `PendingSpend` (see `spend_intent_spl` and `spend_intent_metered`).
+#[constant]
+pub const REASON_APPROVAL_UNAVAILABLE: u16 = 37;
+
+/// Name of a reason code as used in the README and client tooling
+/// (`REASON_BUDGET_EXCEEDED` → `"BUDGET_EXCEEDED"`); `"UNKNOWN"` otherwise.
+pub fn reason_code_str(code: u16) -> &'static str {
+    match code {
+        REASON_OK => "OK",
+        REASON_BUDGET_EXCEEDED => "BUDGET_EXCEEDED",
+        REASON_COOLDOWN => "COOLDOWN",
+        REASON_INVALID_AMOUNT => "INVALID_AMOUNT",
+        REASON_PAUSED => "PAUSED",
+        REASON_RECIPIENT_NOT_ALLOWED => "RECIPIENT_NOT_ALLOWED",
+        REASON_RECIPIENT_CAP_EXCEEDED => "RECIPIENT_CAP_EXCEEDED",
+        REASON_RECIPIENT_COUNT_EXCEEDED => "RECIPIENT_COUNT_EXCEEDED",
+        REASON_MAINTENANCE => "MAINTENANCE",
+        REASON_HOOK_REJECTED => "HOOK_REJECTED",
+        REASON_SLOT_ALREADY_SPENT => "SLOT_ALREADY_SPENT",
+        REASON_AMOUNT_NOT_ALLOWED => "AMOUNT_NOT_ALLOWED",
+        REASON_RECIPIENT_NOT_REGISTERED => "RECIPIENT_NOT_REGISTERED",
+        REASON_MONTHLY_BUDGET_EXCEEDED => "MONTHLY_BUDGET_EXCEEDED",
+        REASON_ALLOWANCE_FROZEN => "ALLOWANCE_FROZEN",
+        REASON_AGENT_RESERVE_VIOLATION => "AGENT_RESERVE_VIOLATION",
+        REASON_AGENT_WEEKLY_BUDGET => "AGENT_WEEKLY_BUDGET",
+        REASON_RELEASE_SCHEDULE => "RELEASE_SCHEDULE",
+        REASON_OWNER_GONE => "OWNER_GONE",
+        REASON_RECIPIENT_COOLDOWN => "RECIPIENT_COOLDOWN",
+        REASON_BAD_RECIPIENT_UNIT => "BAD_RECIPIENT_UNIT",
+        REASON_WEEKLY_EXCEEDED => "WEEKLY_EXCEEDED",
+        REASON_RECIPIENT_IS_PROGRAM => "RECIPIENT_IS_PROGRAM",
+        REASON_RESERVE_PROTECTED => "RESERVE_PROTECTED",
+        REASON_AGENT_BUDGET_EXCEEDED => "AGENT_BUDGET_EXCEEDED",
+        REASON_BACKOFF => "BACKOFF",
+        REASON_REGION_CAP => "REGION_CAP",
+        REASON_OPS_BUDGET_EXCEEDED => "OPS_BUDGET_EXCEEDED",
+        REASON_INVALID_RECIPIENT => "INVALID_RECIPIENT",
+        REASON_RECIPIENT_BLOCKED => "RECIPIENT_BLOCKED",
+        REASON_COUNT_LIMIT => "COUNT_LIMIT",
+        REASON_APPROVAL_REQUIRED => "APPROVAL_REQUIRED",
+        REASON_OUTSIDE_WINDOW => "OUTSIDE_WINDOW",
+        REASON_STALE_PRICE => "STALE_PRICE",
+        REASON_RECIPIENT_LIFETIME_CAP => "RECIPIENT_LIFETIME_CAP",
+        REASON_TIMELOCK_CANCELLED => "TIMELOCK_CANCELLED",
+        REASON_APPROVAL_UNAVAILABLE => "APPROVAL_UNAVAILABLE",
+        _ => "UNKNOWN",
+    }
+}
+
+// ── policy flag bits (see `get_policy_flags`) ──
+pub const FLAG_PAUSED: u32 = 1 << 0;
+pub const FLAG_ALLOWLIST_ENABLED: u32 = 1 << 1;
+pub const FLAG_REQUIRE_FIRST_SPEND_APPROVAL: u32 = 1 << 2;
+pub const FLAG_HAS_SPENT_ONCE: u32 = 1 << 3;
+pub const FLAG_ONE_SPEND_PER_SLOT: u32 = 1 << 4;
+pub const FLAG_ENFORCE_FIXED_AMOUNTS: u32 = 1 << 5;
+pub const FLAG_AGENT_REQUIRES_REGISTERED_RECIPIENT: u32 = 1 << 6;
+pub const FLAG_REQUIRE_RECIPIENT_SIGNATURE: u32 = 1 << 7;
+pub const FLAG_SEQUENCE_ON_ALLOWED_ONLY: u32 = 1 << 8;
+pub const FLAG_LINEAR_RELEASE: u32 = 1 << 9;
+pub const FLAG_REQUIRE_OWNER_EXISTS: u32 = 1 << 10;
+pub const FLAG_AUTO_CLOSE_EMPTY_TRACKER: u32 = 1 << 11;
+pub const FLAG_RECIPIENT_THROTTLE_ATTEMPTS: u32 = 1 << 12;
+pub const FLAG_LOCKED: u32 = 1 << 13;
+pub const FLAG_BLOCK_PROGRAM_RECIPIENTS: u32 = 1 << 14;
+pub const FLAG_RECIPIENT_CAP_EXCLUSIVE: u32 = 1 << 15;
 
 const SECONDS_PER_DAY: i64 = 86_400;
+/// Deprecated, v2 and upgradeable BPF loaders; accounts they own cannot use
+/// lamports sent to them.
+const BPF_LOADERS: [Pubkey; 3] = [
+    pubkey!("BPFLoader1111111111111111111111111111111111"),
+    pubkey!("BPFLoader2111111111111111111111111111111111"),
+    bpf_loader_upgradeable::ID,
+];
+const SECONDS_PER_WEEK: i64 = 7 * SECONDS_PER_DAY;
+/// Largest doubling applied by the denial backoff (`base * 2^10` at most).
+const DENIAL_BACKOFF_MAX_EXPONENT: u32 = 10;
+
+// ── time units (Policy.time_unit) ──
+pub const TIME_UNIT_SECONDS: u8 = 0;
+pub const TIME_UNIT_SLOTS: u8 = 1;
+/// Daily window length in slot mode (nominal 400 ms slots).
+pub const SLOTS_PER_DAY: i64 = 216_000;
+
+// ── cooldown scopes (Policy.cooldown_scope) ──
+pub const COOLDOWN_SCOPE_GLOBAL: u8 = 0;
+pub const COOLDOWN_SCOPE_RECIPIENT: u8 = 1;
+
+// ── budget modes (Policy.budget_mode) ──
+pub const BUDGET_MODE_CALENDAR: u8 = 0;
+pub const BUDGET_MODE_ROLLING: u8 = 1;
+/// Entries in `Policy::rolling_spends`.
+pub const ROLLING_SPEND_SLOTS: usize = 16;
+
+/// Upper bound on policies per `get_policies_summary` call; keeps the packed
+/// result (4 + 19 * 50 bytes) under the 1024-byte return-data limit.
+pub const MAX_POLICIES_PER_SUMMARY: usize = 50;
+
+/// Maximum number of entries in a policy's `FixedAmounts` whitelist.
+pub const MAX_FIXED_AMOUNTS: usize = 16;
+
+/// Maximum number of payments in one `spend_batch`; with two accounts per
+/// payment this keeps the transaction under the packet size and compute limit.
+pub const MAX_BATCH_RECIPIENTS: usize = 10;
+
+/// Maximum number of AuditEvents one `close_audit_events_batch` closes; keeps
+/// the transaction under the account limit without a lookup table.
+pub const MAX_AUDIT_CLOSE_BATCH: usize = 30;
+
+/// Largest `Policy::budget_scale`; `10^19` is the last power of ten in a `u64`.
+pub const MAX_BUDGET_SCALE: u8 = 19;
 
 #[program]
 pub mod policyvault {
@@ -25,6 +207,67 @@ pub mod policyvault {
         Ok(())
     }
 
+    /// A.1) Fund the vault PDA from `funder` (any signer) and emit `Deposited`
+    /// with the new balance: the canonical alternative to a raw transfer.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        deposit_into_vault(
+            &ctx.accounts.system_program,
+            &ctx.accounts.funder,
+            &ctx.accounts.vault,
+            amount,
+        )
+    }
+
+    /// A.2) Owner pulls lamports out of the vault PDA to any `destination`.
+    /// Vault owner only.
+    ///
+    /// Not a spend: no budget, cooldown or audit sequence applies and
+    /// `Withdrawn` is emitted instead of `SpendRecorded`. The vault must keep
+    /// its rent-exempt minimum and, while it has a policy, the policy's
+    /// `min_reserve_lamports` on top of the lamports committed to timelocked
+    /// claims and pending spends (`timelocked_lamports`, `pending_lamports`).
+    /// Allowances are not held back: see `check_solvency`.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidWithdrawAmount);
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let policy_info = ctx.accounts.policy.to_account_info();
+        let (min_reserve, committed) = if *policy_info.owner == crate::ID {
+            let policy = Policy::try_deserialize(&mut &policy_info.try_borrow_data()?[..])?;
+            (
+                policy.min_reserve_lamports,
+                policy
+                    .timelocked_lamports
+                    .saturating_add(policy.pending_lamports),
+            )
+        } else {
+            (0, 0)
+        };
+        let floor = Rent::get()?
+            .minimum_balance(Vault::SIZE)
+            .max(min_reserve)
+            .saturating_add(committed);
+        require!(
+            vault_info.lamports().saturating_sub(amount) >= floor,
+            VaultError::WithdrawBreachesReserve
+        );
+
+        pay_from_vault(
+            &vault_info,
+            &ctx.accounts.destination.to_account_info(),
+            amount,
+        )?;
+        emit!(Withdrawn {
+            vault: ctx.accounts.vault.key(),
+            owner: ctx.accounts.owner.key(),
+            destination: ctx.accounts.destination.key(),
+            amount,
+            new_balance: vault_info.lamports(),
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
     /// B) Create the Policy PDA linked to a vault.
     ///
     /// `agent` — optional pubkey that may also call `spend_intent`.
@@ -35,23 +278,72 @@ pub mod policyvault {
         cooldown_seconds: u32,
         agent: Option<Pubkey>,
     ) -> Result<()> {
+        ctx.accounts.policy.init(
+            ctx.accounts.vault.key(),
+            ctx.accounts.owner.key(),
+            agent,
+            daily_budget_lamports,
+            cooldown_seconds,
+            ctx.bumps.policy,
+        )
+    }
+
+    /// B.2) Create the Policy PDA with the allowlist on and `recipient` as its
+    /// only allowed payee, registering it (`RecipientAllow`) in the same call.
+    ///
+    /// The recipient may not be the vault or its owner.
+    pub fn initialize_policy_with_recipient(
+        ctx: Context<InitializePolicyWithRecipient>,
+        daily_budget_lamports: u64,
+        cooldown_seconds: u32,
+        agent: Option<Pubkey>,
+    ) -> Result<()> {
+        let recipient_key = ctx.accounts.recipient.key();
+        let vault_key = ctx.accounts.vault.key();
+        let owner_key = ctx.accounts.owner.key();
+        require!(
+            recipient_key != vault_key && recipient_key != owner_key,
+            VaultError::InvalidRecipient
+        );
+
         let policy = &mut ctx.accounts.policy;
-        policy.vault = ctx.accounts.vault.key();
-        policy.authority = ctx.accounts.owner.key();
-        policy.agent = agent;
-        policy.daily_budget_lamports = daily_budget_lamports;
-        policy.spent_today_lamports = 0;
-        policy.day_index = Clock::get()?.unix_timestamp / SECONDS_PER_DAY;
-        policy.cooldown_seconds = cooldown_seconds;
-        policy.last_spend_ts = 0;
-        policy.next_sequence = 0;
-        // advanced defaults
-        policy.paused = false;
-        policy.allowlist_enabled = false;
-        policy.allowed_recipient = None;
-        policy.per_recipient_daily_cap_lamports = 0;
-        policy.policy_version = 1;
-        policy.bump = ctx.bumps.policy;
+        policy.init(
+            vault_key,
+            owner_key,
+            agent,
+            daily_budget_lamports,
+            cooldown_seconds,
+            ctx.bumps.policy,
+        )?;
+        policy.allowlist_enabled = true;
+        policy.allowed_recipient = Some(recipient_key);
+
+        let recipient_allow = &mut ctx.accounts.recipient_allow;
+        recipient_allow.policy = policy.key();
+        recipient_allow.recipient = recipient_key;
+        recipient_allow.registered_by = owner_key;
+        recipient_allow.bump = ctx.bumps.recipient_allow;
+        Ok(())
+    }
+
+    /// B.3) Create the Policy PDA for `vault` as a copy of `source_policy`'s
+    /// configuration, under the same authority, so standard policies can be
+    /// stamped out across agent vaults.
+    ///
+    /// `authority` must be the source's authority and pays; the vault owner
+    /// co-signs. Counters, windows and sequences start fresh, the copy starts
+    /// unlocked at version 1 with no pending authority transfer. Per-policy
+    /// PDAs (allowlist entries, fixed amounts, agents, ...) are not copied.
+    pub fn clone_policy(ctx: Context<ClonePolicy>) -> Result<()> {
+        let mut config = ctx.accounts.source_policy.clone().into_inner();
+        config.clear_runtime_state();
+        config.roll_windows(&Clock::get()?);
+        config.vault = ctx.accounts.vault.key();
+        config.bump = ctx.bumps.policy;
+        config.locked = false;
+        config.pending_authority = None;
+        config.policy_version = 1;
+        ctx.accounts.policy.set_inner(config);
         Ok(())
     }
 
@@ -72,12 +364,32 @@ pub mod policyvault {
         policy.cooldown_seconds = cooldown_seconds;
         policy.agent = agent;
         policy.policy_version = policy.policy_version.saturating_add(1);
+        emit!(PolicyUpdated {
+            vault: policy.vault,
+            policy: policy.key(),
+            policy_version: policy.policy_version,
+            daily_budget_lamports: policy.daily_budget_lamports,
+            cooldown_seconds: policy.cooldown_seconds,
+            agent: policy.agent,
+            paused: policy.paused,
+            allowlist_enabled: policy.allowlist_enabled,
+            allowed_recipient: policy.allowed_recipient,
+            per_recipient_daily_cap_lamports: policy.per_recipient_daily_cap_lamports,
+            ts: Clock::get()?.unix_timestamp,
+        });
         Ok(())
     }
 
     /// C.2) Authority updates advanced policy parameters.
     ///
     /// This is an additive API (keeps `set_policy` as the simple MVP surface).
+    /// Emits `PolicyPaused` / `PolicyUnpaused` when `paused` actually changes.
+    ///
+    /// `day_offset_seconds` moves the day, week and month boundaries off UTC
+    /// midnight: the day index is `(unix_timestamp + day_offset_seconds) /
+    /// 86400`, rounded down (US Eastern, UTC-5: -18000). It must lie within
+    /// one day either way.
+    #[allow(clippy::too_many_arguments)]
     pub fn set_policy_advanced(
         ctx: Context<SetPolicy>,
         daily_budget_lamports: u64,
@@ -87,6 +399,7 @@ pub mod policyvault {
         allowlist_enabled: bool,
         allowed_recipient: Option<Pubkey>,
         per_recipient_daily_cap_lamports: u64,
+        day_offset_seconds: i32,
     ) -> Result<()> {
         let policy = &mut ctx.accounts.policy;
         require_keys_eq!(
@@ -94,521 +407,8359 @@ pub mod policyvault {
             policy.authority,
             VaultError::Unauthorized
         );
+        require!(
+            (day_offset_seconds.unsigned_abs() as i64) < SECONDS_PER_DAY,
+            VaultError::InvalidDayOffset
+        );
 
         policy.daily_budget_lamports = daily_budget_lamports;
         policy.cooldown_seconds = cooldown_seconds;
         policy.agent = agent;
 
+        let was_paused = policy.paused;
         policy.paused = paused;
         policy.allowlist_enabled = allowlist_enabled;
         policy.allowed_recipient = allowed_recipient;
         policy.per_recipient_daily_cap_lamports = per_recipient_daily_cap_lamports;
+        policy.day_offset_seconds = day_offset_seconds;
 
         policy.policy_version = policy.policy_version.saturating_add(1);
+        emit!(PolicyUpdated {
+            vault: policy.vault,
+            policy: policy.key(),
+            policy_version: policy.policy_version,
+            daily_budget_lamports: policy.daily_budget_lamports,
+            cooldown_seconds: policy.cooldown_seconds,
+            agent: policy.agent,
+            paused: policy.paused,
+            allowlist_enabled: policy.allowlist_enabled,
+            allowed_recipient: policy.allowed_recipient,
+            per_recipient_daily_cap_lamports: policy.per_recipient_daily_cap_lamports,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        if paused != was_paused {
+            let by = ctx.accounts.authority.key();
+            let ts = Clock::get()?.unix_timestamp;
+            if paused {
+                emit!(PolicyPaused {
+                    policy: policy.key(),
+                    by,
+                    ts
+                });
+            } else {
+                emit!(PolicyUnpaused {
+                    policy: policy.key(),
+                    by,
+                    ts
+                });
+            }
+        }
         Ok(())
     }
 
-    /// D) Record a spend intent; enforce policy, optionally execute SOL transfer.
+    /// C.3) Authority sets how many times a single recipient may be paid per day.
     ///
-    /// Authorization: caller must be either `policy.authority` or `policy.agent` (if set).
-    /// When allowed, lamports are transferred from the vault PDA to the recipient.
-    /// When denied, no transfer occurs but the audit event is still recorded.
-    pub fn spend_intent(ctx: Context<SpendIntent>, amount: u64) -> Result<()> {
+    /// Complements the per-recipient amount cap by limiting payment frequency,
+    /// which catches an agent splitting one payment into many small ones.
+    /// `0` = unlimited.
+    pub fn set_recipient_count_limit(
+        ctx: Context<SetPolicy>,
+        per_recipient_max_count: u16,
+    ) -> Result<()> {
         let policy = &mut ctx.accounts.policy;
-        let caller_key = ctx.accounts.caller.key();
-
-        // ── Authorization: caller must be authority or agent ──
-        let is_authority = caller_key == policy.authority;
-        let is_agent = policy.agent.map_or(false, |a| a == caller_key);
-        require!(is_authority || is_agent, VaultError::Unauthorized);
-
-        let clock = Clock::get()?;
-        let current_day = clock.unix_timestamp / SECONDS_PER_DAY;
-
-        // Reset daily window if the day rolled over.
-        if current_day != policy.day_index {
-            policy.spent_today_lamports = 0;
-            policy.day_index = current_day;
-        }
-
-        // Determine if the intent is allowed.
-        let (allowed, reason_code) = if amount == 0 {
-            (false, REASON_INVALID_AMOUNT)
-        } else if policy
-            .spent_today_lamports
-            .checked_add(amount)
-            .unwrap_or(u64::MAX)
-            > policy.daily_budget_lamports
-        {
-            (false, REASON_BUDGET_EXCEEDED)
-        } else if policy.last_spend_ts > 0
-            && clock.unix_timestamp - policy.last_spend_ts < policy.cooldown_seconds as i64
-        {
-            (false, REASON_COOLDOWN)
-        } else {
-            (true, REASON_OK)
-        };
-
-        // Write AuditEvent PDA.
-        let audit = &mut ctx.accounts.audit_event;
-        audit.policy = policy.key();
-        audit.sequence = policy.next_sequence;
-        audit.ts = clock.unix_timestamp;
-        audit.recipient = ctx.accounts.recipient.key();
-        audit.amount = amount;
-        audit.allowed = allowed;
-        audit.reason_code = reason_code;
-        audit.policy_version = policy.policy_version;
-
-        // Advance sequence counter.
-        policy.next_sequence = policy.next_sequence.checked_add(1).unwrap();
-
-        // Execute transfer + update counters only when allowed.
-        if allowed {
-            policy.spent_today_lamports = policy.spent_today_lamports.checked_add(amount).unwrap();
-            policy.last_spend_ts = clock.unix_timestamp;
-
-            // Transfer SOL from vault PDA → recipient via direct lamport manipulation.
-            // (SystemProgram::transfer cannot be used because the vault PDA carries account data.)
-            let vault_info = ctx.accounts.vault.to_account_info();
-            let recipient_info = ctx.accounts.recipient.to_account_info();
-            **vault_info.try_borrow_mut_lamports()? = vault_info
-                .lamports()
-                .checked_sub(amount)
-                .ok_or(ProgramError::InsufficientFunds)?;
-            **recipient_info.try_borrow_mut_lamports()? =
-                recipient_info.lamports().checked_add(amount).unwrap();
-        }
-
-        // Emit Anchor event for off-chain indexers.
-        emit!(SpendRecorded {
-            vault: ctx.accounts.vault.key(),
-            policy: policy.key(),
-            policy_version: policy.policy_version,
-            sequence: audit.sequence,
-            recipient: ctx.accounts.recipient.key(),
-            amount,
-            allowed,
-            reason_code,
-            ts: clock.unix_timestamp,
-        });
-
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.per_recipient_max_count = per_recipient_max_count;
+        policy.policy_version = policy.policy_version.saturating_add(1);
         Ok(())
     }
 
-    /// D.2) Spend intent with per-recipient tracking.
+    /// C.4) Authority selects the time unit for cooldown and daily window.
     ///
-    /// Adds enforceable switches:
-    /// - `paused` (kill switch)
-    /// - `allowlist_enabled` + `allowed_recipient` (simple allowlist)
-    /// - `per_recipient_daily_cap_lamports` enforced via `RecipientSpend` PDA
-    pub fn spend_intent_v2(ctx: Context<SpendIntentV2>, amount: u64) -> Result<()> {
+    /// `TIME_UNIT_SECONDS` (default) uses `unix_timestamp`; `TIME_UNIT_SLOTS`
+    /// uses `Clock::slot`, interpreting `cooldown_seconds` as slots and the
+    /// daily window as `SLOTS_PER_DAY` slots. Switching units changes the
+    /// window index, so the next spend starts a fresh window.
+    pub fn set_time_unit(ctx: Context<SetPolicy>, time_unit: u8) -> Result<()> {
         let policy = &mut ctx.accounts.policy;
-        let caller_key = ctx.accounts.caller.key();
-
-        // ── Authorization: caller must be authority or agent ──
-        let is_authority = caller_key == policy.authority;
-        let is_agent = policy.agent.map_or(false, |a| a == caller_key);
-        require!(is_authority || is_agent, VaultError::Unauthorized);
-
-        let clock = Clock::get()?;
-        let current_day = clock.unix_timestamp / SECONDS_PER_DAY;
-
-        // Reset daily window if the day rolled over.
-        if current_day != policy.day_index {
-            policy.spent_today_lamports = 0;
-            policy.day_index = current_day;
-        }
-
-        // Keep per-recipient tracker on same day window.
-        let recipient_spend = &mut ctx.accounts.recipient_spend;
-        if recipient_spend.policy == Pubkey::default() {
-            // init_if_needed created the account; fill fixed fields.
-            recipient_spend.policy = policy.key();
-            recipient_spend.recipient = ctx.accounts.recipient.key();
-            recipient_spend.spent_today_lamports = 0;
-            recipient_spend.day_index = current_day;
-            recipient_spend.bump = ctx.bumps.recipient_spend;
-        } else if recipient_spend.day_index != current_day {
-            recipient_spend.spent_today_lamports = 0;
-            recipient_spend.day_index = current_day;
-        }
-
-        // Determine if the intent is allowed.
-        let (allowed, reason_code) = if amount == 0 {
-            (false, REASON_INVALID_AMOUNT)
-        } else if policy.paused {
-            (false, REASON_PAUSED)
-        } else if policy.allowlist_enabled {
-            match policy.allowed_recipient {
-                Some(allowed_pk) if allowed_pk == ctx.accounts.recipient.key() => (true, REASON_OK),
-                _ => (false, REASON_RECIPIENT_NOT_ALLOWED),
-            }
-        } else {
-            (true, REASON_OK)
-        };
-
-        // Apply caps / cooldown / daily budget only if we haven't denied already.
-        let (allowed, reason_code) = if !allowed {
-            (allowed, reason_code)
-        } else if policy
-            .spent_today_lamports
-            .checked_add(amount)
-            .unwrap_or(u64::MAX)
-            > policy.daily_budget_lamports
-        {
-            (false, REASON_BUDGET_EXCEEDED)
-        } else if policy.last_spend_ts > 0
-            && clock.unix_timestamp - policy.last_spend_ts < policy.cooldown_seconds as i64
-        {
-            (false, REASON_COOLDOWN)
-        } else if policy.per_recipient_daily_cap_lamports > 0
-            && recipient_spend
-                .spent_today_lamports
-                .checked_add(amount)
-                .unwrap_or(u64::MAX)
-                > policy.per_recipient_daily_cap_lamports
-        {
-            (false, REASON_RECIPIENT_CAP_EXCEEDED)
-        } else {
-            (true, REASON_OK)
-        };
-
-        // Write AuditEvent PDA.
-        let audit = &mut ctx.accounts.audit_event;
-        audit.policy = policy.key();
-        audit.sequence = policy.next_sequence;
-        audit.ts = clock.unix_timestamp;
-        audit.recipient = ctx.accounts.recipient.key();
-        audit.amount = amount;
-        audit.allowed = allowed;
-        audit.reason_code = reason_code;
-        audit.policy_version = policy.policy_version;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            time_unit == TIME_UNIT_SECONDS || time_unit == TIME_UNIT_SLOTS,
+            VaultError::InvalidTimeUnit
+        );
+        require!(
+            time_unit == TIME_UNIT_SECONDS || policy.cooldown_scope == COOLDOWN_SCOPE_GLOBAL,
+            VaultError::InvalidCooldownScope
+        );
+        policy.time_unit = time_unit;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
 
-        // Advance sequence counter.
-        policy.next_sequence = policy.next_sequence.checked_add(1).unwrap();
+    /// C.5) Authority schedules a maintenance window during which all spends are denied.
+    ///
+    /// The window is `[start_ts, end_ts)` in unix seconds and clears itself once
+    /// `end_ts` passes — unlike `paused`, no follow-up call is needed.
+    /// Pass `0, 0` to remove the window.
+    pub fn set_maintenance_window(
+        ctx: Context<SetPolicy>,
+        maintenance_start_ts: i64,
+        maintenance_end_ts: i64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            maintenance_start_ts <= maintenance_end_ts,
+            VaultError::InvalidMaintenanceWindow
+        );
+        policy.maintenance_start_ts = maintenance_start_ts;
+        policy.maintenance_end_ts = maintenance_end_ts;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
 
-        // Execute transfer + update counters only when allowed.
-        if allowed {
-            policy.spent_today_lamports = policy.spent_today_lamports.checked_add(amount).unwrap();
-            policy.last_spend_ts = clock.unix_timestamp;
+    /// C.6) Authority sets (or clears with `None`) the spend hook program.
+    ///
+    /// See `spend_hook_approves` for the interface a hook must implement.
+    pub fn set_spend_hook(ctx: Context<SetPolicy>, hook_program: Option<Pubkey>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            hook_program != Some(crate::ID),
+            VaultError::InvalidHookProgram
+        );
+        policy.hook_program = hook_program;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
 
-            recipient_spend.spent_today_lamports = recipient_spend
-                .spent_today_lamports
-                .checked_add(amount)
-                .unwrap();
+    /// C.7) Authority configures the external counter used by `spend_intent_metered`.
+    ///
+    /// `meter_account` holds a little-endian `u64` at byte `meter_offset`;
+    /// pass `None` to disable metered spending.
+    pub fn set_meter(
+        ctx: Context<SetPolicy>,
+        meter_account: Option<Pubkey>,
+        meter_offset: u32,
+        meter_lamports_per_unit: u64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.meter_account = meter_account;
+        policy.meter_offset = meter_offset;
+        policy.meter_lamports_per_unit = meter_lamports_per_unit;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
 
-            // Transfer SOL from vault PDA → recipient via direct lamport manipulation.
-            let vault_info = ctx.accounts.vault.to_account_info();
-            let recipient_info = ctx.accounts.recipient.to_account_info();
-            **vault_info.try_borrow_mut_lamports()? = vault_info
-                .lamports()
-                .checked_sub(amount)
-                .ok_or(ProgramError::InsufficientFunds)?;
-            **recipient_info.try_borrow_mut_lamports()? =
-                recipient_info.lamports().checked_add(amount).unwrap();
+    /// C.8) Authority toggles the first-spend checkpoint.
+    ///
+    /// While set and no spend has gone through yet, an agent's spend must be
+    /// co-signed by the authority (`approver`). Enabling re-arms the checkpoint,
+    /// e.g. after handing the policy to a new agent.
+    pub fn set_first_spend_approval(ctx: Context<SetPolicy>, required: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.require_first_spend_approval = required;
+        if required {
+            policy.has_spent_once = false;
         }
-
-        emit!(SpendRecorded {
-            vault: ctx.accounts.vault.key(),
-            policy: policy.key(),
-            policy_version: policy.policy_version,
-            sequence: audit.sequence,
-            recipient: ctx.accounts.recipient.key(),
-            amount,
-            allowed,
-            reason_code,
-            ts: clock.unix_timestamp,
-        });
-
+        policy.policy_version = policy.policy_version.saturating_add(1);
         Ok(())
     }
 
-    /// E) Reclaim rent from an old AuditEvent account. Authority only.
-    pub fn close_audit_event(ctx: Context<CloseAuditEvent>) -> Result<()> {
+    /// C.9) Authority toggles at most one allowed spend per slot for this policy.
+    pub fn set_one_spend_per_slot(ctx: Context<SetPolicy>, enabled: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
         require_keys_eq!(
             ctx.accounts.authority.key(),
-            ctx.accounts.policy.authority,
+            policy.authority,
             VaultError::Unauthorized
         );
-        // The `close` constraint in the Accounts struct handles lamport transfer.
+        policy.one_spend_per_slot = enabled;
+        policy.policy_version = policy.policy_version.saturating_add(1);
         Ok(())
     }
 
-    /// E.3) Reclaim rent from a per-recipient spend tracker. Authority only.
-    pub fn close_recipient_spend(ctx: Context<CloseRecipientSpend>) -> Result<()> {
+    /// C.10) Authority sets the environment tag stamped on this policy's audit
+    /// events (e.g. 0 = prod, 1 = dev), so indexers can memcmp-filter by environment.
+    pub fn set_environment_tag(ctx: Context<SetPolicy>, environment_tag: u8) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
         require_keys_eq!(
             ctx.accounts.authority.key(),
-            ctx.accounts.policy.authority,
+            policy.authority,
             VaultError::Unauthorized
         );
-        // The `close` constraint in the Accounts struct handles lamport transfer.
+        policy.environment_tag = environment_tag;
+        policy.policy_version = policy.policy_version.saturating_add(1);
         Ok(())
     }
-}
 
-// ──────────────── Accounts ────────────────
+    /// C.11) Authority sets the fixed amounts a spend must match exactly.
+    ///
+    /// Creates the `FixedAmounts` PDA on first use (authority pays rent). A
+    /// non-empty list turns on `enforce_fixed_amounts`; an empty list turns it off.
+    /// While enforced, spends must pass the `fixed_amounts` account.
+    pub fn set_fixed_amounts(ctx: Context<SetFixedAmounts>, amounts: Vec<u64>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            amounts.len() <= MAX_FIXED_AMOUNTS,
+            VaultError::TooManyFixedAmounts
+        );
 
-#[account]
-pub struct Vault {
-    pub owner: Pubkey, // 32
-    pub bump: u8,      // 1
-}
+        let fixed_amounts = &mut ctx.accounts.fixed_amounts;
+        fixed_amounts.policy = policy.key();
+        fixed_amounts.bump = ctx.bumps.fixed_amounts;
+        policy.enforce_fixed_amounts = !amounts.is_empty();
+        fixed_amounts.amounts = amounts;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
 
-// 8 discriminator + 32 + 1 = 41
-impl Vault {
-    pub const SIZE: usize = 8 + 32 + 1;
-}
+    /// C.12) Authority changes the daily budget and records why.
+    ///
+    /// `reason` is a free-form 32-byte note (short tag or hash of an off-chain
+    /// justification) emitted in `BudgetChanged` for the audit trail.
+    pub fn set_daily_budget_with_reason(
+        ctx: Context<SetPolicy>,
+        daily_budget_lamports: u64,
+        reason: [u8; 32],
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        let old = policy.daily_budget_lamports;
+        policy.daily_budget_lamports = daily_budget_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
 
+        emit!(BudgetChanged {
+            policy: policy.key(),
+            old,
+            new: daily_budget_lamports,
+            reason,
+            by: ctx.accounts.authority.key(),
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// C.13) Authority caps each recipient at a share of the daily budget.
+    ///
+    /// `per_recipient_cap_bps` of 2000 = 20%; non-zero overrides
+    /// `per_recipient_daily_cap_lamports`, 0 falls back to it.
+    pub fn set_per_recipient_cap_bps(
+        ctx: Context<SetPolicy>,
+        per_recipient_cap_bps: u16,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(per_recipient_cap_bps <= 10_000, VaultError::InvalidBps);
+        policy.per_recipient_cap_bps = per_recipient_cap_bps;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.14) Authority grants (or updates) a streaming allowance for `recipient`.
+    ///
+    /// The recipient accrues `rate_per_second` policy units up to `ceiling` and
+    /// pulls them with `accrue_and_claim`. On update, the balance accrued so
+    /// far is settled at the old rate first and carried over into the current
+    /// `budget_scale`. Creates the `Allowance` PDA on first use.
+    pub fn set_allowance(
+        ctx: Context<SetAllowance>,
+        rate_per_second: u64,
+        ceiling: u64,
+    ) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.policy.authority,
+            VaultError::Unauthorized
+        );
+        let now = Clock::get()?.unix_timestamp;
+        let policy = &ctx.accounts.policy;
+
+        let allowance = &mut ctx.accounts.allowance;
+        if allowance.policy == Pubkey::default() {
+            // init_if_needed created the account; fill fixed fields.
+            allowance.policy = policy.key();
+            allowance.recipient = ctx.accounts.recipient.key();
+            allowance.accrued = 0;
+            allowance.last_accrual_ts = now;
+            allowance.bump = ctx.bumps.allowance;
+            allowance.budget_scale = policy.budget_scale;
+        } else {
+            allowance.accrue(now);
+        }
+        if allowance.budget_scale != policy.budget_scale {
+            // Carry the settled balance over into the current unit.
+            let lamports = units_to_lamports(allowance.accrued, allowance.budget_scale)?;
+            allowance.accrued = lamports / policy.scaled(1)?;
+            allowance.budget_scale = policy.budget_scale;
+        }
+        allowance.rate_per_second = rate_per_second;
+        allowance.ceiling = ceiling;
+        allowance.accrued = allowance.accrued.min(ceiling);
+        Ok(())
+    }
+
+    /// C.15) Register `recipient` as a payee of this policy. Authority or agent.
+    ///
+    /// Records who registered it; with `agent_requires_registered_recipient` on,
+    /// only authority-registered payees count for agent spends. The authority
+    /// re-registering an agent-registered payee takes it over.
+    pub fn register_recipient(ctx: Context<RegisterRecipient>) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        let caller_key = ctx.accounts.caller.key();
+        let (is_authority, _) =
+            authorize_caller(policy, &caller_key, ctx.accounts.agent_entry.as_deref())?;
+
+        let recipient_allow = &mut ctx.accounts.recipient_allow;
+        if recipient_allow.policy == Pubkey::default() {
+            // init_if_needed created the account; fill fixed fields.
+            recipient_allow.policy = policy.key();
+            recipient_allow.recipient = ctx.accounts.recipient.key();
+            recipient_allow.registered_by = caller_key;
+            recipient_allow.bump = ctx.bumps.recipient_allow;
+        } else if is_authority {
+            recipient_allow.registered_by = caller_key;
+        }
+        Ok(())
+    }
+
+    /// C.16) Authority restricts agent spends to authority-registered payees.
+    pub fn set_agent_requires_registered_recipient(
+        ctx: Context<SetPolicy>,
+        required: bool,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.agent_requires_registered_recipient = required;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.17) Authority sets a calendar-month budget (0 = none).
+    ///
+    /// The window resets on the 1st of each month (see `month_index`) at
+    /// midnight on the `day_offset_seconds` clock (UTC by default),
+    /// independent of the policy's time unit.
+    pub fn set_monthly_budget(ctx: Context<SetPolicy>, monthly_budget_lamports: u64) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.monthly_budget_lamports = monthly_budget_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.18) Authority sets the recipient allowlist merkle root (all zeroes = none).
+    ///
+    /// Leaves are `sha256(recipient)`; see `merkle_proof_valid`.
+    pub fn set_allowlist_merkle_root(
+        ctx: Context<SetPolicy>,
+        allowlist_merkle_root: [u8; 32],
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.allowlist_merkle_root = allowlist_merkle_root;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.19) Authority requires the recipient to co-sign every spend.
+    ///
+    /// While set, spends whose `recipient` is not a signer are rejected, so
+    /// funds only move to a payee that is online and expects them.
+    pub fn set_require_recipient_signature(ctx: Context<SetPolicy>, required: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.require_recipient_signature = required;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.20) Authority anchors daily windows to `epoch_anchor_ts` instead of UTC midnight.
+    ///
+    /// Days are counted as `(now - epoch_anchor_ts) / 86400`, so each window
+    /// starts at the anchor's time of day (e.g. a billing-cycle start). 0 = UTC
+    /// midnight; a timezone `offset` seconds from UTC is `epoch_anchor_ts =
+    /// -offset` (US Eastern, UTC-5: 18000). Only the day index follows the
+    /// anchor; for a timezone that also moves the week and month boundaries,
+    /// set `day_offset_seconds` via `set_policy_advanced` instead. Seconds time
+    /// unit only; slot windows are unaffected.
+    pub fn set_epoch_anchor(ctx: Context<SetPolicy>, epoch_anchor_ts: i64) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.epoch_anchor_ts = epoch_anchor_ts;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.21) Authority freezes or unfreezes an allowance's claims.
+    ///
+    /// A frozen allowance keeps accruing (up to its ceiling) but every claim is
+    /// denied with `REASON_ALLOWANCE_FROZEN`, e.g. while a dispute is open.
+    pub fn set_allowance_frozen(ctx: Context<SetAllowanceFrozen>, frozen: bool) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.policy.authority,
+            VaultError::Unauthorized
+        );
+        ctx.accounts.allowance.frozen = frozen;
+        Ok(())
+    }
+
+    /// C.22) Authority reserves part of the vault balance for itself (0 = none).
+    ///
+    /// Agent spends that would leave less than `reserved_for_authority_lamports`
+    /// in the vault (above rent) are denied; the authority can still spend it.
+    /// In raw lamports, whatever the `budget_scale`.
+    pub fn set_agent_reserve(
+        ctx: Context<SetPolicy>,
+        reserved_for_authority_lamports: u64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.reserved_for_authority_lamports = reserved_for_authority_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.23) Authority caps the agent's spending per week (0 = no weekly cap).
+    ///
+    /// Weeks are fixed windows of `(unix_timestamp + day_offset_seconds) /
+    /// (7 * 86_400)` (UTC unless `day_offset_seconds` is set); only agent
+    /// spends (the legacy `agent` and agents added with `add_agent`,
+    /// combined) count toward and are limited by this budget.
+    pub fn set_agent_weekly_budget(
+        ctx: Context<SetPolicy>,
+        agent_weekly_budget_lamports: u64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.agent_weekly_budget_lamports = agent_weekly_budget_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.24) Authority makes `next_sequence` count allowed spends only.
+    ///
+    /// While on, a denied attempt does not consume a sequence number: it bumps
+    /// `denial_sequence` instead, its AuditEvent is closed in the same call
+    /// (rent back to the caller) and only the `SpendRecorded` event remains,
+    /// carrying the `denial_sequence` number with `out_of_sequence` set.
+    pub fn set_sequence_on_allowed_only(ctx: Context<SetPolicy>, enabled: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.sequence_on_allowed_only = enabled;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.25) Authority releases the daily budget gradually over the window.
+    ///
+    /// While on, cumulative spend today may not exceed
+    /// `daily_budget * elapsed_in_period / period` (see `released_budget`);
+    /// spends beyond it are denied with `RELEASE_SCHEDULE`.
+    pub fn set_linear_release(ctx: Context<SetPolicy>, enabled: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.linear_release = enabled;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.26) Authority swaps the agent key in one step and emits `AgentRotated`.
+    ///
+    /// Meant for compromised keys: the old agent loses spend rights in the same
+    /// transaction the new one gains them. Pass `None` to remove the agent.
+    ///
+    /// On a locked policy only revocation (`new_agent = None`) is allowed, so a
+    /// lock cannot be used to slip in a new spender. Whenever there is an old
+    /// agent, its `add_agent` entry address must be passed as `old_agent_entry`
+    /// (`OldAgentEntryMismatch` otherwise); if an entry lives there it is closed
+    /// (rent to the authority) so that key cannot keep spending through it.
+    /// Pending spends the old agent already queued survive; `reject_spend` them
+    /// as needed.
+    pub fn rotate_agent_secure(
+        ctx: Context<RotateAgentSecure>,
+        new_agent: Option<Pubkey>,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            !policy.locked || new_agent.is_none(),
+            VaultError::PolicyLocked
+        );
+        let old_agent = policy.agent;
+        if let Some(old) = old_agent {
+            let (expected, _) = Pubkey::find_program_address(
+                &[b"agent", policy.key().as_ref(), old.as_ref()],
+                ctx.program_id,
+            );
+            let entry = ctx
+                .accounts
+                .old_agent_entry
+                .as_ref()
+                .ok_or(VaultError::OldAgentEntryMismatch)?;
+            require_keys_eq!(entry.key(), expected, VaultError::OldAgentEntryMismatch);
+            // Only `add_agent` creates accounts at this PDA, so a program-owned
+            // one is the old agent's entry; close it like `remove_agent` would.
+            if entry.owner == ctx.program_id {
+                let info = entry.to_account_info();
+                let rent = info.lamports();
+                info.sub_lamports(rent)?;
+                ctx.accounts.authority.add_lamports(rent)?;
+                info.assign(&System::id());
+                info.resize(0)?;
+            }
+        }
+        policy.agent = new_agent;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+
+        emit!(AgentRotated {
+            policy: policy.key(),
+            old_agent,
+            new_agent,
+            policy_version: policy.policy_version,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// C.27) Authority halts spends once the vault owner's account is gone.
+    ///
+    /// While on, the `spend_intent*` paths must be passed the vault owner account
+    /// and deny with `OWNER_GONE` when it holds no lamports.
+    pub fn set_require_owner_exists(ctx: Context<SetPolicy>, required: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.require_owner_exists = required;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.28) Authority has `record_refund` close emptied recipient trackers.
+    ///
+    /// While on, a refund that leaves a `RecipientSpend` with nothing spent today
+    /// and no lifetime total closes it, rent to the authority.
+    pub fn set_auto_close_empty_tracker(ctx: Context<SetPolicy>, enabled: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.auto_close_empty_tracker = enabled;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.29) Authority sets a minimum interval between spends to the same recipient
+    /// (0 = none), enforced by `spend_intent_v2` via the `RecipientSpend` tracker.
+    ///
+    /// With `throttle_attempts`, denied attempts restart the interval too, so a
+    /// recipient's limits cannot be probed by rapid retries.
+    pub fn set_recipient_min_interval(
+        ctx: Context<SetPolicy>,
+        interval_seconds: u32,
+        throttle_attempts: bool,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.recipient_min_interval_seconds = interval_seconds;
+        policy.recipient_throttle_attempts = throttle_attempts;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.30) Authority sets the power-of-ten unit for budgets and amounts.
+    ///
+    /// With `budget_scale = s`, budgets, counters and instruction `amount`s are
+    /// in units of `10^s` lamports (0 = raw lamports); only actual lamport
+    /// movements are scaled (see `Policy::scaled`). Change it while no spend
+    /// is counted for the day, or today's counters mix units.
+    ///
+    /// Vault-balance amounts stay raw lamports whatever the scale:
+    /// `min_reserve_lamports`, `reserved_for_authority_lamports`,
+    /// `timelocked_lamports`, `pending_lamports`, `ops_daily_budget_lamports`
+    /// and `fund_caller`'s `amount`. Timelocked claims, pending spends and allowances keep the
+    /// scale they were made under, so a change does not alter their payouts
+    /// (see `units_to_lamports`).
+    pub fn set_budget_scale(ctx: Context<SetPolicy>, budget_scale: u8) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            budget_scale <= MAX_BUDGET_SCALE,
+            VaultError::InvalidBudgetScale
+        );
+        policy.budget_scale = budget_scale;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.31) Add `recipient` to the multi-recipient allowlist. Authority only.
+    ///
+    /// Creates an `AllowedRecipient` PDA; while `allowlist_enabled` is on,
+    /// `spend_intent_v2` accepts a payee whose entry is passed.
+    pub fn add_allowed_recipient(ctx: Context<AddAllowedRecipient>) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+
+        let allowed_recipient = &mut ctx.accounts.allowed_recipient;
+        allowed_recipient.policy = policy.key();
+        allowed_recipient.recipient = ctx.accounts.recipient.key();
+        allowed_recipient.bump = ctx.bumps.allowed_recipient;
+        Ok(())
+    }
+
+    /// C.32) Remove `recipient` from the multi-recipient allowlist, reclaiming
+    /// the entry's rent. Authority only.
+    pub fn remove_allowed_recipient(ctx: Context<RemoveAllowedRecipient>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.policy.authority,
+            VaultError::Unauthorized
+        );
+        // The `close` constraint in the Accounts struct handles lamport transfer.
+        Ok(())
+    }
+
+    /// C.33) Permanently lock the policy's configuration. Authority only.
+    ///
+    /// One-way: there is no unlock. Afterwards every setter (including
+    /// `set_policy`/`set_policy_advanced`, and therefore pausing) and the
+    /// allowlist/registration instructions fail with `PolicyLocked`, while
+    /// spending under the frozen rules continues. Only a guardian `freeze` and
+    /// the authority's `unfreeze` can still toggle `paused`, and agents can
+    /// still be revoked through `remove_agent` and `rotate_agent_secure` (with
+    /// `new_agent = None`).
+    pub fn lock_policy(ctx: Context<SetPolicy>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.locked = true;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+
+        emit!(PolicyLocked {
+            policy: policy.key(),
+            authority: policy.authority,
+            policy_version: policy.policy_version,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// C.34) Authority sets the daily budget for `mint`, in the token's base
+    /// units (creates the `TokenBudget` PDA on first use).
+    pub fn set_token_budget(ctx: Context<SetTokenBudget>, daily_budget: u64) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+
+        let token_budget = &mut ctx.accounts.token_budget;
+        if token_budget.policy == Pubkey::default() {
+            // init_if_needed created the account; fill fixed fields.
+            token_budget.policy = policy.key();
+            token_budget.mint = ctx.accounts.mint.key();
+            token_budget.day_index = policy.day_index;
+            token_budget.bump = ctx.bumps.token_budget;
+        }
+        token_budget.daily_budget = daily_budget;
+        Ok(())
+    }
+
+    /// C.35) Authority proposes handing the policy to `new_authority`.
+    ///
+    /// Nothing changes until the new key calls `accept_authority_transfer`; a
+    /// later proposal overwrites the pending one.
+    pub fn propose_authority_transfer(
+        ctx: Context<SetPolicy>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.pending_authority = Some(new_authority);
+        policy.policy_version = policy.policy_version.saturating_add(1);
+
+        emit!(AuthorityTransferProposed {
+            policy: policy.key(),
+            authority: policy.authority,
+            pending_authority: new_authority,
+            policy_version: policy.policy_version,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// C.36) The pending authority accepts the transfer and becomes the
+    /// policy's authority.
+    pub fn accept_authority_transfer(ctx: Context<AcceptAuthorityTransfer>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let pending = policy
+            .pending_authority
+            .ok_or(VaultError::NoPendingAuthority)?;
+        require_keys_eq!(
+            ctx.accounts.new_authority.key(),
+            pending,
+            VaultError::Unauthorized
+        );
+
+        let old_authority = policy.authority;
+        policy.authority = pending;
+        policy.pending_authority = None;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+
+        emit!(AuthorityTransferAccepted {
+            policy: policy.key(),
+            old_authority,
+            new_authority: pending,
+            policy_version: policy.policy_version,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// C.37) Authority sets the payment unit for a registered recipient; v2
+    /// spends to it must be a multiple of `unit_lamports` (0 = any amount).
+    pub fn set_recipient_unit(ctx: Context<SetRecipientUnit>, unit_lamports: u64) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.policy.authority,
+            VaultError::Unauthorized
+        );
+        ctx.accounts.recipient_allow.unit_lamports = unit_lamports;
+        Ok(())
+    }
+
+    /// C.38) Authority sets a policy-wide weekly budget (0 = none).
+    ///
+    /// Weeks are the same fixed windows as the agent weekly budget and count
+    /// every allowed spend, whoever made it.
+    pub fn set_weekly_budget(ctx: Context<SetPolicy>, weekly_budget_lamports: u64) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.weekly_budget_lamports = weekly_budget_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.39) Authority denies spends to program accounts (`REASON_RECIPIENT_IS_PROGRAM`).
+    ///
+    /// Lamports sent to an executable or loader-owned account are unrecoverable.
+    pub fn set_block_program_recipients(ctx: Context<SetPolicy>, enabled: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.block_program_recipients = enabled;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.40) Authority sets a vault floor no spend may dip below (0 = none).
+    ///
+    /// Unlike the agent reserve it binds the authority too; denials record
+    /// `REASON_RESERVE_PROTECTED`. In raw lamports, whatever the
+    /// `budget_scale`.
+    pub fn set_min_reserve(ctx: Context<SetPolicy>, min_reserve_lamports: u64) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.min_reserve_lamports = min_reserve_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.41) Authority switches the per-recipient cap to strict "under the cap"
+    /// semantics: a spend reaching the cap exactly is denied.
+    pub fn set_recipient_cap_exclusive(ctx: Context<SetPolicy>, exclusive: bool) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.recipient_cap_exclusive = exclusive;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.42) Authorize `agent` to spend alongside the policy's legacy single
+    /// agent, optionally under its own daily sub-limit (0 = none). Authority only.
+    ///
+    /// Creates an `Agent` PDA; every instruction that authorizes agents accepts
+    /// the agent as caller when its entry is passed as `agent_entry` (see
+    /// `authorize_caller`), and the lamport spend paths count its sub-limit.
+    pub fn add_agent(ctx: Context<AddAgent>, daily_budget_lamports: u64) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+
+        let agent_entry = &mut ctx.accounts.agent_entry;
+        agent_entry.policy = policy.key();
+        agent_entry.agent = ctx.accounts.agent.key();
+        agent_entry.daily_budget_lamports = daily_budget_lamports;
+        agent_entry.spent_today_lamports = 0;
+        agent_entry.day_index = policy.day_index;
+        agent_entry.bump = ctx.bumps.agent_entry;
+        Ok(())
+    }
+
+    /// C.43) Revoke an agent added with `add_agent`, reclaiming the entry's
+    /// rent. Authority only; works on a locked policy too, so a compromised
+    /// key can always be cut off.
+    pub fn remove_agent(ctx: Context<RemoveAgent>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.policy.authority,
+            VaultError::Unauthorized
+        );
+        // The `close` constraint in the Accounts struct handles lamport transfer.
+        Ok(())
+    }
+
+    /// C.44) Authority sets the base of the exponential denial backoff
+    /// (0 = off).
+    ///
+    /// Each denial starts a wait of `base * 2^min(consecutive_denials, 10)`
+    /// seconds during which every spend attempt is denied with
+    /// `REASON_BACKOFF`; those denials neither extend the streak nor restart
+    /// the wait. Only the authority's and agents' denials count, not an
+    /// allowance recipient's. An allowed spend resets the streak.
+    pub fn set_denial_backoff(ctx: Context<SetPolicy>, base_seconds: u32) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.denial_backoff_base_seconds = base_seconds;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.45) Authority sets the daily cap for tagged spends in `region_code`
+    /// (creates the `RegionBudget` PDA on first use; 0 = no cap).
+    pub fn set_region_budget(
+        ctx: Context<SetRegionBudget>,
+        region_code: u16,
+        daily_cap_lamports: u64,
+    ) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+
+        let region_budget = &mut ctx.accounts.region_budget;
+        if region_budget.policy == Pubkey::default() {
+            // init_if_needed created the account; fill fixed fields.
+            region_budget.policy = policy.key();
+            region_budget.region_code = region_code;
+            region_budget.day_index = policy.day_index;
+            region_budget.bump = ctx.bumps.region_budget;
+        }
+        region_budget.daily_cap_lamports = daily_cap_lamports;
+        Ok(())
+    }
+
+    /// C.46) Authority sets the heartbeat threshold (0 = off).
+    ///
+    /// Nothing is blocked: the first allowed spend after more than
+    /// `max_spend_gap_seconds` without one emits `SpendHeartbeatMissed` with the
+    /// length of the silence. Updating `last_spend_ts` keeps it to once per gap.
+    pub fn set_max_spend_gap(ctx: Context<SetPolicy>, max_spend_gap_seconds: u32) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.max_spend_gap_seconds = max_spend_gap_seconds;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.47) Authority picks how the daily budget is windowed:
+    /// `BUDGET_MODE_CALENDAR` (reset at each day boundary) or
+    /// `BUDGET_MODE_ROLLING` (the last 24 hours, in seconds).
+    ///
+    /// Rolling mode sums the last `ROLLING_SPEND_SLOTS` allowed spends, which
+    /// are recorded in either mode, so switching takes effect at once. Refunds
+    /// only credit the calendar counters.
+    pub fn set_budget_mode(ctx: Context<SetPolicy>, budget_mode: u8) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            budget_mode <= BUDGET_MODE_ROLLING,
+            VaultError::InvalidBudgetMode
+        );
+        policy.budget_mode = budget_mode;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.48) Authority sets the daily lamport budget for `fund_caller`
+    /// top-ups (0 = none). In raw lamports, whatever the `budget_scale`.
+    pub fn set_ops_budget(ctx: Context<SetPolicy>, ops_daily_budget_lamports: u64) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.ops_daily_budget_lamports = ops_daily_budget_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.49) Authority sets (or clears with `None`) the program notified of
+    /// denied spends and spends above `notify_threshold_lamports`.
+    ///
+    /// Every spend path takes an optional `notify_program` account and notifies
+    /// from `record_spend`; token spends (`spend_intent_spl`) notify only when
+    /// denied, as their amounts are not lamports. See `notify_spend` for the
+    /// interface a notify program must implement.
+    pub fn set_notify_program(
+        ctx: Context<SetPolicy>,
+        notify_program: Option<Pubkey>,
+        notify_threshold_lamports: u64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            notify_program != Some(crate::ID),
+            VaultError::InvalidNotifyProgram
+        );
+        policy.notify_program = notify_program;
+        policy.notify_threshold_lamports = notify_threshold_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.50) Block `recipient`: every path that pays it (each spend
+    /// instruction, `spend_batch`, timelock commits and claims, allowance
+    /// claims and approvals) denies it with `REASON_RECIPIENT_BLOCKED` ahead of
+    /// any other check, whether or not the allowlist is on or lists it.
+    /// Authority only.
+    pub fn block_recipient(ctx: Context<BlockRecipient>) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+
+        let blocked_recipient = &mut ctx.accounts.blocked_recipient;
+        blocked_recipient.policy = policy.key();
+        blocked_recipient.recipient = ctx.accounts.recipient.key();
+        blocked_recipient.bump = ctx.bumps.blocked_recipient;
+        Ok(())
+    }
+
+    /// C.51) Unblock `recipient`, reclaiming the entry's rent. Authority only.
+    pub fn unblock_recipient(ctx: Context<UnblockRecipient>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.policy.authority,
+            VaultError::Unauthorized
+        );
+        // The `close` constraint in the Accounts struct handles lamport transfer.
+        Ok(())
+    }
+
+    /// C.52) Authority caps allowed spends per day (0 = no cap); further
+    /// attempts that day are denied with `REASON_COUNT_LIMIT`.
+    pub fn set_max_spends_per_day(ctx: Context<SetPolicy>, max_spends_per_day: u32) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.max_spends_per_day = max_spends_per_day;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.53) Authority holds agent spends above `approval_threshold_lamports`
+    /// (0 = off) for approval; a held spend can be approved for
+    /// `approval_ttl_seconds` after it is requested. Token spends cannot be
+    /// held, so `spend_intent_spl` denies every agent spend while it is set.
+    pub fn set_approval_threshold(
+        ctx: Context<SetPolicy>,
+        approval_threshold_lamports: u64,
+        approval_ttl_seconds: u32,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            approval_threshold_lamports == 0 || approval_ttl_seconds > 0,
+            VaultError::InvalidApprovalTtl
+        );
+        policy.approval_threshold_lamports = approval_threshold_lamports;
+        policy.approval_ttl_seconds = approval_ttl_seconds;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.54) Authority limits spends to a daily UTC window
+    /// `[allowed_start_seconds, allowed_end_seconds)` in seconds of day
+    /// (`unix_timestamp % 86400`; `day_offset_seconds` does not move it)
+    /// (`REASON_OUTSIDE_WINDOW` outside it). Start after end spans midnight,
+    /// e.g. 22:00–06:00; pass `0, 0` to remove the window.
+    pub fn set_spend_window(
+        ctx: Context<SetPolicy>,
+        allowed_start_seconds: u32,
+        allowed_end_seconds: u32,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        let in_day = |s: u32| (s as i64) < SECONDS_PER_DAY;
+        require!(
+            in_day(allowed_start_seconds)
+                && in_day(allowed_end_seconds)
+                && (allowed_start_seconds != allowed_end_seconds || allowed_start_seconds == 0),
+            VaultError::InvalidSpendWindow
+        );
+        policy.allowed_start_seconds = allowed_start_seconds;
+        policy.allowed_end_seconds = allowed_end_seconds;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.55) Authority sets (or clears with `None`) a Pyth SOL/USD price
+    /// account and a daily budget in USD cents for `spend_intent`.
+    ///
+    /// While set, each `spend_intent` must pass the feed in `remaining_accounts`;
+    /// the spend is priced at the feed's aggregate price and counted against
+    /// `daily_budget_usd_cents` per calendar day. A missing feed, a feed that
+    /// is not trading or a price older than `max_price_age_seconds` denies with
+    /// `REASON_STALE_PRICE`. The lamport budgets still apply on every spend
+    /// path; set them high to budget in USD only.
+    pub fn set_price_feed(
+        ctx: Context<SetPolicy>,
+        price_feed: Option<Pubkey>,
+        daily_budget_usd_cents: u64,
+        max_price_age_seconds: u32,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(price_feed != Some(crate::ID), VaultError::InvalidPriceFeed);
+        policy.price_feed = price_feed;
+        policy.daily_budget_usd_cents = daily_budget_usd_cents;
+        policy.max_price_age_seconds = max_price_age_seconds;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.56) Authority sets (or clears with `None`) the guardian, a
+    /// break-glass key that can only `freeze` the policy.
+    pub fn set_guardian(ctx: Context<SetPolicy>, guardian: Option<Pubkey>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.guardian = guardian;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.57) Guardian pauses the policy (break-glass). Guardian only.
+    ///
+    /// Only ever sets `paused`; unpausing stays with the authority
+    /// (`set_policy_advanced`, or `unfreeze`). Works on a locked policy too, so
+    /// a guardian set before locking can still stop spends in an incident; the
+    /// authority lifts it there with `unfreeze`.
+    pub fn freeze(ctx: Context<Freeze>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require!(
+            policy.guardian == Some(ctx.accounts.guardian.key()),
+            VaultError::Unauthorized
+        );
+        if !policy.paused {
+            policy.paused = true;
+            policy.policy_version = policy.policy_version.saturating_add(1);
+            emit!(PolicyPaused {
+                policy: policy.key(),
+                by: ctx.accounts.guardian.key(),
+                ts: Clock::get()?.unix_timestamp,
+            });
+        }
+        emit!(PolicyFrozen {
+            policy: policy.key(),
+            guardian: ctx.accounts.guardian.key(),
+            policy_version: policy.policy_version,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// C.62) Authority clears `paused`. Authority only.
+    ///
+    /// Unlike `set_policy_advanced` this also works on a locked policy, so a
+    /// guardian `freeze` there is not permanent. Changes nothing else.
+    pub fn unfreeze(ctx: Context<Unfreeze>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        if policy.paused {
+            policy.paused = false;
+            policy.policy_version = policy.policy_version.saturating_add(1);
+            emit!(PolicyUnpaused {
+                policy: policy.key(),
+                by: ctx.accounts.authority.key(),
+                ts: Clock::get()?.unix_timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// C.58) Authority selects what `cooldown_seconds` spaces out:
+    /// every spend (`COOLDOWN_SCOPE_GLOBAL`) or spends to the same recipient
+    /// (`COOLDOWN_SCOPE_RECIPIENT`).
+    ///
+    /// In recipient scope `spend_intent_v2` times the cooldown from the
+    /// recipient's `RecipientSpend.last_spend_ts` (the tracker becomes required)
+    /// and ignores the policy-wide `last_spend_ts`. Paths without a tracker keep
+    /// the policy-wide cooldown. Seconds mode only.
+    pub fn set_cooldown_scope(ctx: Context<SetPolicy>, cooldown_scope: u8) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            cooldown_scope == COOLDOWN_SCOPE_GLOBAL
+                || (cooldown_scope == COOLDOWN_SCOPE_RECIPIENT
+                    && policy.time_unit == TIME_UNIT_SECONDS),
+            VaultError::InvalidCooldownScope
+        );
+        policy.cooldown_scope = cooldown_scope;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.59) Authority zeroes today's spend counters, e.g. after lowering the
+    /// daily budget mid-day; with `recipient_spend`, that recipient's daily
+    /// counters too. Clears the rolling window in rolling mode. Emits
+    /// `CountersReset`. Not available on a locked policy.
+    pub fn reset_spend_counters(ctx: Context<ResetSpendCounters>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        let clock = Clock::get()?;
+        let current_day = policy.current_day(&clock);
+        policy.reset_day(current_day);
+        policy.rolling_spends = [RollingSpend::default(); ROLLING_SPEND_SLOTS];
+        policy.rolling_head = 0;
+
+        let recipient = ctx.accounts.recipient_spend.as_mut().map(|recipient_spend| {
+            recipient_spend.spent_today_lamports = 0;
+            recipient_spend.spend_count_today = 0;
+            recipient_spend.day_index = current_day;
+            recipient_spend.recipient
+        });
+        emit!(CountersReset {
+            policy: policy.key(),
+            authority: ctx.accounts.authority.key(),
+            recipient,
+            day_index: current_day,
+            ts: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// C.60) Authority sets (or clears with `None`) the recipient registry
+    /// `spend_intent_v2` consults after the allowlist.
+    ///
+    /// See `registry_allows` for the interface a registry program must implement.
+    pub fn set_registry_program(
+        ctx: Context<SetPolicy>,
+        registry_program: Option<Pubkey>,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            registry_program != Some(crate::ID),
+            VaultError::InvalidRegistryProgram
+        );
+        policy.registry_program = registry_program;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// C.61) Authority caps the total ever paid to any one recipient (0 = none),
+    /// enforced by `spend_intent_v2` via the `RecipientSpend` tracker plus the
+    /// recipient's `RecipientLifetime` carry-over (`REASON_RECIPIENT_LIFETIME_CAP`).
+    pub fn set_recipient_lifetime_cap(
+        ctx: Context<SetPolicy>,
+        per_recipient_lifetime_cap_lamports: u64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.per_recipient_lifetime_cap_lamports = per_recipient_lifetime_cap_lamports;
+        policy.policy_version = policy.policy_version.saturating_add(1);
+        Ok(())
+    }
+
+    /// D) Record a spend intent; enforce policy, optionally execute SOL transfer.
+    ///
+    /// Authorization: caller must be either `policy.authority`, `policy.agent` (if set),
+    /// or an agent whose `Agent` entry is passed as `agent_entry`.
+    /// When allowed, lamports are transferred from the vault PDA to the recipient.
+    /// When denied, no transfer occurs but the audit event is still recorded.
+    /// A paused policy (`set_policy_advanced` or a guardian `freeze`) denies
+    /// with `REASON_PAUSED`; a payee blocked with `block_recipient` (pass its
+    /// `BlockedRecipient` PDA as `blocked_recipient`) with
+    /// `REASON_RECIPIENT_BLOCKED`, ahead of every other check.
+    /// An agent spend above `approval_threshold_lamports` is held instead: it
+    /// is recorded as `REASON_APPROVAL_REQUIRED` and a `PendingSpend` (pass it
+    /// as `pending_spend`) waits for `approve_spend` or `reject_spend`.
+    /// If `policy.hook_program` is set, pass it as `hook_program` and any accounts
+    /// the hook needs as `remaining_accounts`.
+    pub fn spend_intent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SpendIntent<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        run_spend_intent(ctx.accounts, ctx.remaining_accounts, amount, 0, None, 0)?;
+        Ok(())
+    }
+
+    /// D.2) Spend intent with per-recipient tracking.
+    ///
+    /// Adds enforceable switches:
+    /// - `paused` (kill switch) and the scheduled maintenance window
+    /// - `allowlist_enabled` with `AllowedRecipient` entries (or the legacy
+    ///   single `allowed_recipient`)
+    /// - `BlockedRecipient` entries, which deny a payee even when it is allowed
+    /// - `per_recipient_daily_cap_lamports` enforced via `RecipientSpend` PDA
+    /// - `per_recipient_max_count` (payments per recipient per day)
+    /// - `per_recipient_lifetime_cap_lamports` (total ever paid per recipient;
+    ///   pass the payee's `RecipientLifetime` PDA as `recipient_lifetime`)
+    /// - `hook_program` (owner-supplied validation via CPI, see `spend_hook_approves`)
+    /// - `registry_program` (recipient registry queried via CPI, see
+    ///   `registry_allows`)
+    ///
+    /// Checks run in a fixed order and the first failing one is reported:
+    /// blocklist → denial backoff → amount → daily spend count → fixed amounts →
+    /// recipient unit → invalid recipient → program recipient → registration →
+    /// reserve → minimum reserve → owner → pause → maintenance → spend window →
+    /// allowlist → registry → daily budget or per-recipient cap → recipient
+    /// lifetime cap → release schedule → weekly budget → monthly budget → agent
+    /// weekly budget → agent sub-limit → cooldown → slot → per-recipient count → per-recipient interval → hook.
+    /// When the daily budget and the per-recipient cap both bind, the more
+    /// restrictive one (less headroom left) is reported; on a tie, the
+    /// per-recipient cap (see `binding_cap_reason`). The recipient unit is checked only when the payee's
+    /// `RecipientAllow` is passed.
+    /// An agent spend above `approval_threshold_lamports` that passes every
+    /// check is held as in `spend_intent` (pass `pending_spend`).
+    pub fn spend_intent_v2<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SpendIntentV2<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let caller_key = ctx.accounts.caller.key();
+
+        // ── Authorization: caller must be authority or agent ──
+        let (is_authority, is_agent) =
+            authorize_caller(policy, &caller_key, ctx.accounts.agent_entry.as_deref())?;
+
+        // ── First-spend checkpoint: authority co-signs until one spend goes through ──
+        if policy.needs_first_spend_approval() && !is_authority {
+            let approver = ctx
+                .accounts
+                .approver
+                .as_ref()
+                .ok_or(VaultError::FirstSpendApprovalRequired)?;
+            require_keys_eq!(
+                approver.key(),
+                policy.authority,
+                VaultError::FirstSpendApprovalRequired
+            );
+        }
+
+        // ── Pull-confirm: recipient co-signs to acknowledge the transfer ──
+        require!(
+            !policy.require_recipient_signature || ctx.accounts.recipient.is_signer,
+            VaultError::RecipientSignatureRequired
+        );
+
+        let clock = Clock::get()?;
+        roll_policy_windows(policy, &clock);
+        let current_day = policy.day_index;
+
+        // Rent the caller paid for this instruction's AuditEvent (and tracker, if new).
+        let rent = Rent::get()?;
+        let mut rent_paid = rent.minimum_balance(AuditEvent::SIZE);
+
+        // The per-recipient tracker is only required while a per-recipient limit
+        // is on; otherwise callers may omit it and skip its rent.
+        let tracking = policy.tracks_recipients();
+        require!(
+            !tracking || ctx.accounts.recipient_spend.is_some(),
+            VaultError::RecipientSpendRequired
+        );
+        // Totals of closed trackers live on in `RecipientLifetime`, so the
+        // lifetime cap needs it too (uninitialized until a tracker is closed).
+        let carried_lifetime = if policy.per_recipient_lifetime_cap_lamports > 0 {
+            let info = ctx
+                .accounts
+                .recipient_lifetime
+                .as_ref()
+                .ok_or(VaultError::RecipientLifetimeRequired)?;
+            if *info.owner == crate::ID {
+                RecipientLifetime::try_deserialize(&mut &info.try_borrow_data()?[..])?
+                    .lifetime_spent_lamports
+            } else {
+                0
+            }
+        } else {
+            0
+        };
+
+        // Keep per-recipient tracker on same day window.
+        if let Some(recipient_spend) = ctx.accounts.recipient_spend.as_mut() {
+            if recipient_spend.policy == Pubkey::default() {
+                // init_if_needed created the account; fill fixed fields.
+                rent_paid = rent_paid.saturating_add(rent.minimum_balance(RecipientSpend::SIZE));
+                recipient_spend.policy = policy.key();
+                recipient_spend.recipient = ctx.accounts.recipient.key();
+                recipient_spend.spent_today_lamports = 0;
+                recipient_spend.day_index = current_day;
+                recipient_spend.bump = ctx
+                    .bumps
+                    .recipient_spend
+                    .ok_or(VaultError::RecipientSpendRequired)?;
+                recipient_spend.spend_count_today = 0;
+            } else {
+                // An existing tracker must already belong to this policy and
+                // payee: do not rely on the seeds alone to rule out a re-init.
+                require_keys_eq!(
+                    recipient_spend.policy,
+                    policy.key(),
+                    VaultError::RecipientSpendMismatch
+                );
+                require_keys_eq!(
+                    recipient_spend.recipient,
+                    ctx.accounts.recipient.key(),
+                    VaultError::RecipientSpendMismatch
+                );
+                if recipient_spend.day_index != current_day {
+                    recipient_spend.spent_today_lamports = 0;
+                    recipient_spend.day_index = current_day;
+                    recipient_spend.spend_count_today = 0;
+                }
+            }
+        }
+        let (recipient_spent_today, recipient_count_today, recipient_last_spend_ts) = ctx
+            .accounts
+            .recipient_spend
+            .as_ref()
+            .map_or((0, 0, 0), |rs| {
+                (
+                    rs.spent_today_lamports,
+                    rs.spend_count_today,
+                    rs.last_spend_ts,
+                )
+            });
+        let recipient_lifetime_spent = ctx
+            .accounts
+            .recipient_spend
+            .as_ref()
+            .map_or(0, |rs| rs.lifetime_spent_lamports)
+            .saturating_add(carried_lifetime);
+
+        let lamports = policy.scaled(amount)?;
+        let reserve_breached = policy.breaches_agent_reserve(
+            &caller_key,
+            &ctx.accounts.vault.to_account_info(),
+            lamports,
+        )?;
+        let reserve_protected =
+            policy.breaches_min_reserve(&ctx.accounts.vault.to_account_info(), lamports);
+        let owner_gone = policy.owner_gone(ctx.accounts.owner.as_deref())?;
+        if let Some(agent_entry) = ctx.accounts.agent_entry.as_mut() {
+            agent_entry.roll_day(policy.day_index);
+        }
+        let agent_over_budget = ctx
+            .accounts
+            .agent_entry
+            .as_ref()
+            .is_some_and(|entry| entry.exceeds_daily_budget(amount));
+
+        let recipient_blocked = *ctx.accounts.blocked_recipient.owner == crate::ID;
+
+        // Determine if the intent is allowed.
+        let (allowed, reason_code) = if recipient_blocked {
+            (false, REASON_RECIPIENT_BLOCKED)
+        } else if policy.in_denial_backoff(&clock) {
+            (false, REASON_BACKOFF)
+        } else if amount == 0 {
+            (false, REASON_INVALID_AMOUNT)
+        } else if policy.exceeds_spend_count() {
+            (false, REASON_COUNT_LIMIT)
+        } else if !policy.amount_allowed(ctx.accounts.fixed_amounts.as_deref(), amount) {
+            (false, REASON_AMOUNT_NOT_ALLOWED)
+        } else if !RecipientAllow::amount_fits_unit(ctx.accounts.recipient_allow.as_deref(), amount)
+        {
+            (false, REASON_BAD_RECIPIENT_UNIT)
+        } else if policy.is_invalid_recipient(&policy.key(), &ctx.accounts.recipient.key()) {
+            (false, REASON_INVALID_RECIPIENT)
+        } else if policy.blocks_program_recipient(&ctx.accounts.recipient) {
+            (false, REASON_RECIPIENT_IS_PROGRAM)
+        } else if !policy
+            .recipient_registered_for(&caller_key, ctx.accounts.recipient_allow.as_deref())
+        {
+            (false, REASON_RECIPIENT_NOT_REGISTERED)
+        } else if reserve_breached {
+            (false, REASON_AGENT_RESERVE_VIOLATION)
+        } else if reserve_protected {
+            (false, REASON_RESERVE_PROTECTED)
+        } else if owner_gone {
+            (false, REASON_OWNER_GONE)
+        } else if policy.paused {
+            (false, REASON_PAUSED)
+        } else if policy.in_maintenance(&clock) {
+            (false, REASON_MAINTENANCE)
+        } else if policy.seconds_until_window_opens(clock.unix_timestamp) > 0 {
+            (false, REASON_OUTSIDE_WINDOW)
+        } else if !policy.recipient_allowlisted(
+            &ctx.accounts.recipient.key(),
+            ctx.accounts.allowed_recipient.as_deref(),
+        ) {
+            (false, REASON_RECIPIENT_NOT_ALLOWED)
+        } else if let Some(registry_program) = policy.registry_program {
+            if registry_allows(
+                &registry_program,
+                ctx.accounts.registry_program.as_ref(),
+                [
+                    policy.to_account_info(),
+                    ctx.accounts.recipient.to_account_info(),
+                ],
+            )? {
+                (true, REASON_OK)
+            } else {
+                (false, REASON_RECIPIENT_NOT_ALLOWED)
+            }
+        } else {
+            (true, REASON_OK)
+        };
+
+        // Daily budget vs per-recipient cap: report the more restrictive one.
+        let cap_reason = binding_cap_reason(
+            amount,
+            policy
+                .daily_budget_lamports
+                .saturating_sub(policy.spent_today(&clock)),
+            policy.recipient_cap_headroom(recipient_spent_today),
+        );
+
+        // Apply caps / cooldown / daily budget only if we haven't denied already.
+        let decision = if !allowed {
+            (allowed, reason_code)
+        } else if let Some(reason) = cap_reason {
+            (false, reason)
+        } else if policy.exceeds_recipient_lifetime_cap(recipient_lifetime_spent, amount) {
+            (false, REASON_RECIPIENT_LIFETIME_CAP)
+        } else if policy.exceeds_release_schedule(&clock, policy.daily_budget_lamports, amount) {
+            (false, REASON_RELEASE_SCHEDULE)
+        } else if policy.exceeds_weekly_budget(amount) {
+            (false, REASON_WEEKLY_EXCEEDED)
+        } else if policy.exceeds_monthly_budget(amount) {
+            (false, REASON_MONTHLY_BUDGET_EXCEEDED)
+        } else if policy.exceeds_agent_weekly_budget(is_agent, amount) {
+            (false, REASON_AGENT_WEEKLY_BUDGET)
+        } else if agent_over_budget {
+            (false, REASON_AGENT_BUDGET_EXCEEDED)
+        } else if policy.in_scoped_cooldown(recipient_last_spend_ts, &clock) {
+            (false, REASON_COOLDOWN)
+        } else if policy.slot_already_spent(&clock) {
+            (false, REASON_SLOT_ALREADY_SPENT)
+        } else if policy.per_recipient_max_count > 0
+            && recipient_count_today >= policy.per_recipient_max_count
+        {
+            (false, REASON_RECIPIENT_COUNT_EXCEEDED)
+        } else if policy.recipient_in_cooldown(recipient_last_spend_ts, &clock) {
+            (false, REASON_RECIPIENT_COOLDOWN)
+        } else {
+            (true, REASON_OK)
+        };
+
+        let decision = apply_spend_hook(
+            decision,
+            policy,
+            ctx.accounts.hook_program.as_ref(),
+            [
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.recipient.to_account_info(),
+                ctx.accounts.caller.to_account_info(),
+            ],
+            ctx.remaining_accounts,
+            amount,
+        )?;
+        let held_for_approval = decision.0 && policy.needs_approval(is_authority, amount);
+        let decision = if held_for_approval {
+            (false, REASON_APPROVAL_REQUIRED)
+        } else {
+            decision
+        };
+
+        finalize_spend(
+            policy,
+            &mut ctx.accounts.audit_event,
+            &ctx.accounts.vault,
+            &ctx.accounts.recipient,
+            &ctx.accounts.caller.to_account_info(),
+            is_agent,
+            amount,
+            decision,
+            rent_paid,
+            &clock,
+            0,
+            ctx.accounts.notify_program.as_ref(),
+        )?;
+        settle_pending_spend(
+            &mut ctx.accounts.policy,
+            ctx.accounts.pending_spend.as_mut(),
+            held_for_approval,
+            ctx.accounts.recipient.key(),
+            &ctx.accounts.caller.to_account_info(),
+            amount,
+            ctx.accounts.audit_event.sequence,
+            &clock,
+        )?;
+        let policy = &mut ctx.accounts.policy;
+
+        if decision.0 {
+            if let Some(agent_entry) = ctx.accounts.agent_entry.as_mut() {
+                agent_entry.record(amount);
+            }
+        }
+        if decision.0 && tracking {
+            if let Some(recipient_spend) = ctx.accounts.recipient_spend.as_mut() {
+                if recipient_spend.spend_count_today == 0 {
+                    policy.distinct_recipients_today =
+                        policy.distinct_recipients_today.saturating_add(1);
+                }
+                recipient_spend.spent_today_lamports = recipient_spend
+                    .spent_today_lamports
+                    .checked_add(amount)
+                    .ok_or(VaultError::ArithmeticOverflow)?;
+                recipient_spend.spend_count_today =
+                    recipient_spend.spend_count_today.saturating_add(1);
+                recipient_spend.lifetime_spent_lamports = recipient_spend
+                    .lifetime_spent_lamports
+                    .saturating_add(amount);
+            }
+        }
+        // The per-recipient interval runs from the last payment, or from the
+        // last attempt of any outcome with `recipient_throttle_attempts`.
+        if tracking && (decision.0 || policy.recipient_throttle_attempts) {
+            if let Some(recipient_spend) = ctx.accounts.recipient_spend.as_mut() {
+                recipient_spend.last_spend_ts = clock.unix_timestamp;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// D.3) Spend intent against a usage-metered daily budget.
+    ///
+    /// The effective budget is `counter * meter_lamports_per_unit`, where
+    /// `counter` is the little-endian `u64` at `meter_offset` in the account
+    /// configured via `set_meter` (e.g. jobs completed, tracked by another
+    /// program). It replaces `daily_budget_lamports` (and paces the release
+    /// schedule); every other check is `spend_intent`'s, sharing its counters,
+    /// plus the allowlist as in `spend_intent_v2`. Region caps do not apply.
+    /// An agent spend above `approval_threshold_lamports` is denied with
+    /// `REASON_APPROVAL_UNAVAILABLE`: `approve_spend` cannot re-check the
+    /// metered budget, so nothing is held.
+    pub fn spend_intent_metered<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SpendIntentMetered<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let caller_key = ctx.accounts.caller.key();
+
+        // ── Authorization: caller must be authority or agent ──
+        let (is_authority, is_agent) =
+            authorize_caller(policy, &caller_key, ctx.accounts.agent_entry.as_deref())?;
+
+        // ── First-spend checkpoint: authority co-signs until one spend goes through ──
+        if policy.needs_first_spend_approval() && !is_authority {
+            let approver = ctx
+                .accounts
+                .approver
+                .as_ref()
+                .ok_or(VaultError::FirstSpendApprovalRequired)?;
+            require_keys_eq!(
+                approver.key(),
+                policy.authority,
+                VaultError::FirstSpendApprovalRequired
+            );
+        }
+
+        // ── Pull-confirm: recipient co-signs to acknowledge the transfer ──
+        require!(
+            !policy.require_recipient_signature || ctx.accounts.recipient.is_signer,
+            VaultError::RecipientSignatureRequired
+        );
+
+        // ── Read the metered budget ──
+        let meter_key = policy.meter_account.ok_or(VaultError::MeterNotConfigured)?;
+        require_keys_eq!(
+            ctx.accounts.meter.key(),
+            meter_key,
+            VaultError::MeterMismatch
+        );
+        let counter = {
+            let data = ctx.accounts.meter.try_borrow_data()?;
+            let start = policy.meter_offset as usize;
+            let bytes: [u8; 8] = data
+                .get(start..start + 8)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or(VaultError::InvalidMeterOffset)?;
+            u64::from_le_bytes(bytes)
+        };
+        let metered_budget = counter.saturating_mul(policy.meter_lamports_per_unit);
+
+        let clock = Clock::get()?;
+        roll_policy_windows(policy, &clock);
+
+        if let Some(agent_entry) = ctx.accounts.agent_entry.as_mut() {
+            agent_entry.roll_day(policy.day_index);
+        }
+        let agent_over_budget = ctx
+            .accounts
+            .agent_entry
+            .as_ref()
+            .is_some_and(|entry| entry.exceeds_daily_budget(amount));
+        let usd_cents =
+            usd_cents_for(policy, ctx.remaining_accounts, clock.unix_timestamp, amount)?;
+        let recipient_allowed = policy.recipient_allowlisted(
+            &ctx.accounts.recipient.key(),
+            ctx.accounts.allowed_recipient.as_deref(),
+        );
+
+        // Determine if the intent is allowed.
+        let decision = spend_intent_decision(
+            policy,
+            &policy.key(),
+            &caller_key,
+            is_agent,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.recipient,
+            ctx.accounts.fixed_amounts.as_deref(),
+            ctx.accounts.recipient_allow.as_deref(),
+            ctx.accounts.owner.as_deref(),
+            *ctx.accounts.blocked_recipient.owner == crate::ID,
+            recipient_allowed,
+            metered_budget,
+            agent_over_budget,
+            false,
+            usd_cents,
+            &clock,
+            amount,
+        )?;
+
+        let decision = apply_spend_hook(
+            decision,
+            policy,
+            ctx.accounts.hook_program.as_ref(),
+            [
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.recipient.to_account_info(),
+                ctx.accounts.caller.to_account_info(),
+            ],
+            ctx.remaining_accounts,
+            amount,
+        )?;
+        // Approval cannot re-check the metered budget, so there is no
+        // `PendingSpend` to queue here.
+        let decision = if decision.0 && policy.needs_approval(is_authority, amount) {
+            (false, REASON_APPROVAL_UNAVAILABLE)
+        } else {
+            decision
+        };
+
+        // Rent the caller paid for this instruction's AuditEvent.
+        let rent_paid = Rent::get()?.minimum_balance(AuditEvent::SIZE);
+
+        finalize_spend(
+            policy,
+            &mut ctx.accounts.audit_event,
+            &ctx.accounts.vault,
+            &ctx.accounts.recipient,
+            &ctx.accounts.caller.to_account_info(),
+            is_agent,
+            amount,
+            decision,
+            rent_paid,
+            &clock,
+            0,
+            ctx.accounts.notify_program.as_ref(),
+        )?;
+
+        if decision.0 {
+            if let Some(agent_entry) = ctx.accounts.agent_entry.as_mut() {
+                agent_entry.record(amount);
+            }
+            if let Some(cents) = usd_cents {
+                let policy = &mut ctx.accounts.policy;
+                policy.spent_today_usd_cents = policy.spent_today_usd_cents.saturating_add(cents);
+            }
+        }
+        Ok(())
+    }
+
+    /// D.4) Recipient pulls its accrued allowance from the vault.
+    ///
+    /// Accrues since `last_accrual_ts` (capped at `ceiling`), then pays out as
+    /// much as today's and this month's remaining budgets allow; the rest stays
+    /// accrued. That payout is checked like a spend: a frozen allowance, the
+    /// daily spend count, the agent and minimum reserves, pause, maintenance,
+    /// the spend window, the release schedule and the weekly budget deny the
+    /// claim (accrual still happens). Every claim writes an AuditEvent.
+    pub fn accrue_and_claim(ctx: Context<AccrueAndClaim>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let allowance = &mut ctx.accounts.allowance;
+
+        let clock = Clock::get()?;
+        roll_policy_windows(policy, &clock);
+        allowance.accrue(clock.unix_timestamp);
+
+        let remaining = policy
+            .daily_budget_lamports
+            .saturating_sub(policy.spent_today(&clock));
+        let claim = allowance
+            .accrued
+            .min(remaining)
+            .min(policy.monthly_remaining());
+
+        // Determine if the claim is allowed. The payout uses the scale the
+        // allowance accrued under.
+        let lamports = units_to_lamports(claim, allowance.budget_scale)?;
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let decision = if *ctx.accounts.blocked_recipient.owner == crate::ID {
+            (false, REASON_RECIPIENT_BLOCKED)
+        } else if allowance.frozen {
+            (false, REASON_ALLOWANCE_FROZEN)
+        } else if allowance.accrued == 0 {
+            (false, REASON_INVALID_AMOUNT)
+        } else if policy.exceeds_spend_count() {
+            (false, REASON_COUNT_LIMIT)
+        } else if policy.breaches_agent_reserve(
+            &ctx.accounts.recipient.key(),
+            &vault_info,
+            lamports,
+        )? {
+            (false, REASON_AGENT_RESERVE_VIOLATION)
+        } else if policy.breaches_min_reserve(&vault_info, lamports) {
+            (false, REASON_RESERVE_PROTECTED)
+        } else if policy.paused {
+            (false, REASON_PAUSED)
+        } else if policy.in_maintenance(&clock) {
+            (false, REASON_MAINTENANCE)
+        } else if policy.seconds_until_window_opens(clock.unix_timestamp) > 0 {
+            (false, REASON_OUTSIDE_WINDOW)
+        } else if remaining == 0 {
+            (false, REASON_BUDGET_EXCEEDED)
+        } else if claim == 0 {
+            (false, REASON_MONTHLY_BUDGET_EXCEEDED)
+        } else if policy.exceeds_release_schedule(&clock, policy.daily_budget_lamports, claim) {
+            (false, REASON_RELEASE_SCHEDULE)
+        } else if policy.exceeds_weekly_budget(claim) {
+            (false, REASON_WEEKLY_EXCEEDED)
+        } else {
+            (true, REASON_OK)
+        };
+        // Denied claims record the full accrued balance that was requested.
+        let amount = if decision.0 { claim } else { allowance.accrued };
+
+        // Rent the caller paid for this instruction's AuditEvent.
+        let rent_paid = Rent::get()?.minimum_balance(AuditEvent::SIZE);
+
+        record_spend(
+            policy,
+            &mut ctx.accounts.audit_event,
+            &ctx.accounts.vault,
+            &ctx.accounts.recipient,
+            &ctx.accounts.recipient.to_account_info(),
+            false,
+            amount,
+            decision,
+            rent_paid,
+            &clock,
+            None,
+            0,
+            ctx.accounts.notify_program.as_ref(),
+        )?;
+
+        if decision.0 {
+            pay_from_vault(&vault_info, &ctx.accounts.recipient, lamports)?;
+            allowance.accrued -= claim;
+        }
+        Ok(())
+    }
+
+    /// D.5) Authority commits `amount` to `recipient`, claimable from `unlock_ts`.
+    ///
+    /// Budget, minimum reserve, pause, maintenance, release schedule, weekly and
+    /// monthly budgets and cooldown apply now, as for a spend, and the attempt
+    /// writes an AuditEvent. The lamports stay in the vault until
+    /// `claim_timelock` but are held in `timelocked_lamports`, which later
+    /// spends cannot touch; `check_solvency` counts them as committed. A denied
+    /// attempt keeps no `TimelockedClaim`.
+    pub fn spend_to_timelock(
+        ctx: Context<SpendToTimelock>,
+        amount: u64,
+        unlock_ts: i64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.caller.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        let clock = Clock::get()?;
+        require!(
+            unlock_ts > clock.unix_timestamp,
+            VaultError::InvalidUnlockTs
+        );
+        roll_policy_windows(policy, &clock);
+
+        let decision = if *ctx.accounts.blocked_recipient.owner == crate::ID {
+            (false, REASON_RECIPIENT_BLOCKED)
+        } else if policy.in_denial_backoff(&clock) {
+            (false, REASON_BACKOFF)
+        } else if amount == 0 {
+            (false, REASON_INVALID_AMOUNT)
+        } else if policy.exceeds_spend_count() {
+            (false, REASON_COUNT_LIMIT)
+        } else if policy.is_invalid_recipient(&policy.key(), &ctx.accounts.recipient.key()) {
+            (false, REASON_INVALID_RECIPIENT)
+        } else if policy.blocks_program_recipient(&ctx.accounts.recipient) {
+            (false, REASON_RECIPIENT_IS_PROGRAM)
+        } else if policy.breaches_min_reserve(
+            &ctx.accounts.vault.to_account_info(),
+            policy.scaled(amount)?,
+        ) {
+            (false, REASON_RESERVE_PROTECTED)
+        } else if policy.paused {
+            (false, REASON_PAUSED)
+        } else if policy.in_maintenance(&clock) {
+            (false, REASON_MAINTENANCE)
+        } else if policy.seconds_until_window_opens(clock.unix_timestamp) > 0 {
+            (false, REASON_OUTSIDE_WINDOW)
+        } else if exceeds_limit(
+            policy.spent_today(&clock),
+            amount,
+            policy.daily_budget_lamports,
+        ) {
+            (false, REASON_BUDGET_EXCEEDED)
+        } else if policy.exceeds_release_schedule(&clock, policy.daily_budget_lamports, amount) {
+            (false, REASON_RELEASE_SCHEDULE)
+        } else if policy.exceeds_weekly_budget(amount) {
+            (false, REASON_WEEKLY_EXCEEDED)
+        } else if policy.exceeds_monthly_budget(amount) {
+            (false, REASON_MONTHLY_BUDGET_EXCEEDED)
+        } else if policy.in_cooldown(&clock) {
+            (false, REASON_COOLDOWN)
+        } else {
+            (true, REASON_OK)
+        };
+
+        let timelock = &mut ctx.accounts.timelock;
+        timelock.policy = policy.key();
+        timelock.recipient = ctx.accounts.recipient.key();
+        timelock.payer = ctx.accounts.caller.key();
+        timelock.amount = amount;
+        timelock.unlock_ts = unlock_ts;
+        timelock.sequence = policy.next_sequence;
+        timelock.bump = ctx.bumps.timelock;
+        timelock.budget_scale = policy.budget_scale;
+
+        // Rent the caller paid for the AuditEvent (and the claim, if kept).
+        let rent = Rent::get()?;
+        let mut rent_paid = rent.minimum_balance(AuditEvent::SIZE);
+        if decision.0 {
+            rent_paid = rent_paid.saturating_add(rent.minimum_balance(TimelockedClaim::SIZE));
+        }
+
+        record_spend(
+            policy,
+            &mut ctx.accounts.audit_event,
+            &ctx.accounts.vault,
+            &ctx.accounts.recipient,
+            &ctx.accounts.caller.to_account_info(),
+            false,
+            amount,
+            decision,
+            rent_paid,
+            &clock,
+            None,
+            0,
+            ctx.accounts.notify_program.as_ref(),
+        )?;
+
+        if decision.0 {
+            let policy = &mut ctx.accounts.policy;
+            policy.timelocked_lamports = policy
+                .timelocked_lamports
+                .checked_add(ctx.accounts.timelock.lamports()?)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+        } else {
+            ctx.accounts
+                .timelock
+                .close(ctx.accounts.caller.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    /// D.6) Recipient collects a matured timelocked claim from the vault.
+    ///
+    /// Its budget was counted when it was committed, so only the pause switch
+    /// and the minimum reserve apply now. Either way the attempt writes an
+    /// AuditEvent; a paid claim is closed and its rent returned to whoever
+    /// created it, a denied one stays open for a retry.
+    pub fn claim_timelock(ctx: Context<ClaimTimelock>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let timelock = &ctx.accounts.timelock;
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp >= timelock.unlock_ts,
+            VaultError::TimelockNotMatured
+        );
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let lamports = timelock.lamports()?;
+        // The claim's own lamports are part of `timelocked_lamports`.
+        let available = vault_info
+            .lamports()
+            .saturating_sub(policy.timelocked_lamports.saturating_sub(lamports));
+        let decision = if *ctx.accounts.blocked_recipient.owner == crate::ID {
+            (false, REASON_RECIPIENT_BLOCKED)
+        } else if policy.paused {
+            (false, REASON_PAUSED)
+        } else if policy.breaches_reserve(available, lamports) {
+            (false, REASON_RESERVE_PROTECTED)
+        } else {
+            (true, REASON_OK)
+        };
+
+        write_audit_event(
+            policy,
+            &mut ctx.accounts.audit_event,
+            timelock.recipient,
+            &ctx.accounts.recipient.to_account_info(),
+            false,
+            timelock.amount,
+            decision,
+            Rent::get()?.minimum_balance(AuditEvent::SIZE),
+            &clock,
+        )?;
+        if !decision.0 {
+            return Ok(());
+        }
+
+        pay_from_vault(
+            &vault_info,
+            &ctx.accounts.recipient.to_account_info(),
+            lamports,
+        )?;
+        policy.timelocked_lamports = policy.timelocked_lamports.saturating_sub(lamports);
+
+        emit!(TimelockClaimed {
+            policy: timelock.policy,
+            recipient: timelock.recipient,
+            amount: timelock.amount,
+            sequence: timelock.sequence,
+            ts: clock.unix_timestamp,
+        });
+        ctx.accounts
+            .timelock
+            .close(ctx.accounts.payer.to_account_info())?;
+        Ok(())
+    }
+
+    /// D.7) Authority cancels a timelocked claim before it unlocks.
+    ///
+    /// The lamports never left the vault, so the claim is closed (rent back to
+    /// its creator) and its lamports released from `timelocked_lamports`. The
+    /// budget it used is not credited back. Writes an AuditEvent with
+    /// `REASON_TIMELOCK_CANCELLED`.
+    pub fn cancel_timelock(ctx: Context<CancelTimelock>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        let timelock = &ctx.accounts.timelock;
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp < timelock.unlock_ts,
+            VaultError::TimelockMatured
+        );
+
+        write_audit_event(
+            policy,
+            &mut ctx.accounts.audit_event,
+            timelock.recipient,
+            &ctx.accounts.authority.to_account_info(),
+            false,
+            timelock.amount,
+            (false, REASON_TIMELOCK_CANCELLED),
+            Rent::get()?.minimum_balance(AuditEvent::SIZE),
+            &clock,
+        )?;
+        policy.timelocked_lamports = policy
+            .timelocked_lamports
+            .saturating_sub(timelock.lamports()?);
+
+        emit!(TimelockCancelled {
+            policy: timelock.policy,
+            recipient: timelock.recipient,
+            amount: timelock.amount,
+            sequence: timelock.sequence,
+            ts: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// D.8) Spend `amount` base units of an SPL token from the vault's
+    /// associated token account to the recipient's.
+    ///
+    /// Runs `spend_intent_v2`'s unit-independent checks — first-spend co-sign,
+    /// recipient signature, blocklist, registration, owner, pause, maintenance,
+    /// spend window, allowlist, registry, cooldown, slot and the spend hook
+    /// (called with the token amount) — with the mint's `TokenBudget` in place
+    /// of the lamport budgets. Lamport-denominated limits (daily, weekly,
+    /// monthly, reserves, per-recipient limits, fixed amounts, `budget_scale`,
+    /// an `add_agent` sub-limit) do not apply. A `PendingSpend` can only pay
+    /// lamports, so while `approval_threshold_lamports` is set every agent
+    /// token spend is denied with `REASON_APPROVAL_UNAVAILABLE` instead of
+    /// held. Checks run in order: blocklist → denial backoff → amount → daily
+    /// spend count → invalid recipient → program recipient → registration →
+    /// owner → pause → maintenance → spend window → allowlist → registry →
+    /// token daily budget → cooldown → slot → hook → approval. The audit and
+    /// `SpendRecorded` carry the mint.
+    pub fn spend_intent_spl<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SpendIntentSpl<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let caller_key = ctx.accounts.caller.key();
+
+        let (is_authority, is_agent) =
+            authorize_caller(policy, &caller_key, ctx.accounts.agent_entry.as_deref())?;
+
+        // ── First-spend checkpoint: authority co-signs until one spend goes through ──
+        if policy.needs_first_spend_approval() && !is_authority {
+            let approver = ctx
+                .accounts
+                .approver
+                .as_ref()
+                .ok_or(VaultError::FirstSpendApprovalRequired)?;
+            require_keys_eq!(
+                approver.key(),
+                policy.authority,
+                VaultError::FirstSpendApprovalRequired
+            );
+        }
+
+        // ── Pull-confirm: recipient co-signs to acknowledge the transfer ──
+        require!(
+            !policy.require_recipient_signature || ctx.accounts.recipient.is_signer,
+            VaultError::RecipientSignatureRequired
+        );
+
+        let clock = Clock::get()?;
+        roll_policy_windows(policy, &clock);
+
+        let token_budget = &mut ctx.accounts.token_budget;
+        if token_budget.day_index != policy.day_index {
+            token_budget.spent_today = 0;
+            token_budget.day_index = policy.day_index;
+        }
+        let owner_gone = policy.owner_gone(ctx.accounts.owner.as_deref())?;
+
+        let (allowed, reason_code) = if *ctx.accounts.blocked_recipient.owner == crate::ID {
+            (false, REASON_RECIPIENT_BLOCKED)
+        } else if policy.in_denial_backoff(&clock) {
+            (false, REASON_BACKOFF)
+        } else if amount == 0 {
+            (false, REASON_INVALID_AMOUNT)
+        } else if policy.exceeds_spend_count() {
+            (false, REASON_COUNT_LIMIT)
+        } else if policy.is_invalid_recipient(&policy.key(), &ctx.accounts.recipient.key()) {
+            (false, REASON_INVALID_RECIPIENT)
+        } else if policy.blocks_program_recipient(&ctx.accounts.recipient) {
+            (false, REASON_RECIPIENT_IS_PROGRAM)
+        } else if !policy
+            .recipient_registered_for(&caller_key, ctx.accounts.recipient_allow.as_deref())
+        {
+            (false, REASON_RECIPIENT_NOT_REGISTERED)
+        } else if owner_gone {
+            (false, REASON_OWNER_GONE)
+        } else if policy.paused {
+            (false, REASON_PAUSED)
+        } else if policy.in_maintenance(&clock) {
+            (false, REASON_MAINTENANCE)
+        } else if policy.seconds_until_window_opens(clock.unix_timestamp) > 0 {
+            (false, REASON_OUTSIDE_WINDOW)
+        } else if !policy.recipient_allowlisted(
+            &ctx.accounts.recipient.key(),
+            ctx.accounts.allowed_recipient.as_deref(),
+        ) {
+            (false, REASON_RECIPIENT_NOT_ALLOWED)
+        } else if let Some(registry_program) = policy.registry_program {
+            if registry_allows(
+                &registry_program,
+                ctx.accounts.registry_program.as_ref(),
+                [
+                    policy.to_account_info(),
+                    ctx.accounts.recipient.to_account_info(),
+                ],
+            )? {
+                (true, REASON_OK)
+            } else {
+                (false, REASON_RECIPIENT_NOT_ALLOWED)
+            }
+        } else {
+            (true, REASON_OK)
+        };
+
+        let decision = if !allowed {
+            (allowed, reason_code)
+        } else if exceeds_limit(token_budget.spent_today, amount, token_budget.daily_budget) {
+            (false, REASON_BUDGET_EXCEEDED)
+        } else if policy.in_cooldown(&clock) {
+            (false, REASON_COOLDOWN)
+        } else if policy.slot_already_spent(&clock) {
+            (false, REASON_SLOT_ALREADY_SPENT)
+        } else {
+            (true, REASON_OK)
+        };
+
+        let decision = apply_spend_hook(
+            decision,
+            policy,
+            ctx.accounts.hook_program.as_ref(),
+            [
+                ctx.accounts.vault.to_account_info(),
+                ctx.accounts.recipient.to_account_info(),
+                ctx.accounts.caller.to_account_info(),
+            ],
+            ctx.remaining_accounts,
+            amount,
+        )?;
+        let decision = if decision.0 && !is_authority && policy.approval_threshold_lamports > 0 {
+            (false, REASON_APPROVAL_UNAVAILABLE)
+        } else {
+            decision
+        };
+
+        let rent_paid = Rent::get()?.minimum_balance(AuditEvent::SIZE);
+        record_spend(
+            policy,
+            &mut ctx.accounts.audit_event,
+            &ctx.accounts.vault,
+            &ctx.accounts.recipient,
+            &ctx.accounts.caller.to_account_info(),
+            is_agent,
+            amount,
+            decision,
+            rent_paid,
+            &clock,
+            Some(ctx.accounts.mint.key()),
+            0,
+            ctx.accounts.notify_program.as_ref(),
+        )?;
+
+        if decision.0 {
+            token_budget.spent_today = token_budget
+                .spent_today
+                .checked_add(amount)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+
+            let vault = &ctx.accounts.vault;
+            let signer_seeds: &[&[&[u8]]] = &[&[b"vault", vault.owner.as_ref(), &[vault.bump]]];
+            token::transfer_checked(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: ctx.accounts.vault_token_account.to_account_info(),
+                        mint: ctx.accounts.mint.to_account_info(),
+                        to: ctx.accounts.recipient_token_account.to_account_info(),
+                        authority: vault.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                amount,
+                ctx.accounts.mint.decimals,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// D.9) Fund the vault from `funder`, then run `spend_intent` for
+    /// `spend_amount` in the same instruction.
+    ///
+    /// `deposit_amount` is raw lamports. The deposit is kept when the spend is
+    /// denied. Emits `Deposited` for the deposit alongside the spend's
+    /// `SpendRecorded`.
+    pub fn deposit_and_spend<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositAndSpend<'info>>,
+        deposit_amount: u64,
+        spend_amount: u64,
+    ) -> Result<()> {
+        let spend = &mut ctx.accounts.spend;
+        deposit_into_vault(
+            &spend.system_program,
+            &ctx.accounts.funder,
+            &spend.vault,
+            deposit_amount,
+        )?;
+        run_spend_intent(spend, ctx.remaining_accounts, spend_amount, 0, None, 0)?;
+        Ok(())
+    }
+
+    /// D.10) `spend_intent` tagged with a jurisdiction `region_code`, recorded
+    /// in the AuditEvent and `SpendRecorded`.
+    ///
+    /// Always pass the region's `RegionBudget` PDA; when it has been created
+    /// with `set_region_budget` its daily cap applies (`REASON_REGION_CAP`),
+    /// otherwise the region is uncapped.
+    pub fn spend_intent_tagged<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SpendIntentTagged<'info>>,
+        amount: u64,
+        region_code: u16,
+    ) -> Result<()> {
+        let region_info = ctx.accounts.region_budget.to_account_info();
+        let mut region_budget = if *region_info.owner == crate::ID {
+            Some(RegionBudget::try_deserialize(
+                &mut &region_info.try_borrow_data()?[..],
+            )?)
+        } else {
+            None
+        };
+
+        run_spend_intent(
+            &mut ctx.accounts.spend,
+            ctx.remaining_accounts,
+            amount,
+            region_code,
+            region_budget.as_mut(),
+            0,
+        )?;
+
+        if let Some(region_budget) = region_budget {
+            region_budget.try_serialize(&mut &mut region_info.try_borrow_mut_data()?[..])?;
+        }
+        Ok(())
+    }
+
+    /// D.11) Top up the caller's own SOL for rent and fees from the vault.
+    /// Authority or agent.
+    ///
+    /// `amount` is raw lamports, counted against `ops_daily_budget_lamports`
+    /// (0 = no top-ups) rather than the spend budgets, and it does not start
+    /// a cooldown. The denial backoff, pause, maintenance, the ops budget, the
+    /// agent reserve (for an agent) and the minimum reserve can deny it, which
+    /// moves nothing. Either way the attempt writes an AuditEvent with the
+    /// caller as its recipient (not counted towards the denial backoff) and
+    /// emits `CallerFunded`.
+    pub fn fund_caller(ctx: Context<FundCaller>, amount: u64) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let caller_key = ctx.accounts.caller.key();
+        let (_, is_agent) =
+            authorize_caller(policy, &caller_key, ctx.accounts.agent_entry.as_deref())?;
+
+        let clock = Clock::get()?;
+        roll_policy_windows(policy, &clock);
+        if policy.ops_day_index != policy.day_index {
+            policy.ops_funding_spent_today = 0;
+            policy.ops_day_index = policy.day_index;
+        }
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let (allowed, reason_code) = if policy.in_denial_backoff(&clock) {
+            (false, REASON_BACKOFF)
+        } else if amount == 0 {
+            (false, REASON_INVALID_AMOUNT)
+        } else if policy.paused {
+            (false, REASON_PAUSED)
+        } else if policy.in_maintenance(&clock) {
+            (false, REASON_MAINTENANCE)
+        } else if exceeds_limit(
+            policy.ops_funding_spent_today,
+            amount,
+            policy.ops_daily_budget_lamports,
+        ) {
+            (false, REASON_OPS_BUDGET_EXCEEDED)
+        } else if policy.breaches_agent_reserve(&caller_key, &vault_info, amount)? {
+            (false, REASON_AGENT_RESERVE_VIOLATION)
+        } else if policy.breaches_min_reserve(&vault_info, amount) {
+            (false, REASON_RESERVE_PROTECTED)
+        } else {
+            (true, REASON_OK)
+        };
+
+        write_audit_event(
+            policy,
+            &mut ctx.accounts.audit_event,
+            caller_key,
+            &ctx.accounts.caller.to_account_info(),
+            is_agent,
+            amount,
+            (allowed, reason_code),
+            Rent::get()?.minimum_balance(AuditEvent::SIZE),
+            &clock,
+        )?;
+        if allowed {
+            pay_from_vault(&vault_info, &ctx.accounts.caller.to_account_info(), amount)?;
+            policy.ops_funding_spent_today = policy.ops_funding_spent_today.saturating_add(amount);
+        }
+        emit!(CallerFunded {
+            policy: policy.key(),
+            caller: caller_key,
+            amount,
+            allowed,
+            reason_code,
+            ops_funding_spent_today: policy.ops_funding_spent_today,
+            ts: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// D.12) Authority executes a `PendingSpend` held by `spend_intent` or
+    /// `spend_intent_v2`.
+    ///
+    /// The payment is re-checked against the blocklist, the pause switch, the
+    /// daily spend count, the agent and minimum reserves and the daily,
+    /// weekly, monthly and agent weekly budgets, as they stand at approval.
+    /// The approval stands in for the rest: window, maintenance, cooldown,
+    /// the release schedule and the per-recipient limits, which are not
+    /// charged for it. The payment writes its own AuditEvent (caller: the
+    /// authority, `caller_is_agent` set since the agent made the request) and
+    /// counts against the budgets like any agent spend. A denied approval
+    /// keeps the `PendingSpend` for a retry until it expires; an executed one
+    /// is closed (rent back to its payer).
+    pub fn approve_spend(ctx: Context<ApproveSpend>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        let pending = &ctx.accounts.pending_spend;
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp <= pending.expires_ts,
+            VaultError::PendingSpendExpired
+        );
+        roll_policy_windows(policy, &clock);
+
+        let amount = pending.amount;
+        // Paid at the scale the spend was requested under.
+        let lamports = pending.lamports()?;
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let decision = if *ctx.accounts.blocked_recipient.owner == crate::ID {
+            (false, REASON_RECIPIENT_BLOCKED)
+        } else if policy.paused {
+            (false, REASON_PAUSED)
+        } else if policy.exceeds_spend_count() {
+            (false, REASON_COUNT_LIMIT)
+        } else if policy.breaches_agent_reserve(&pending.payer, &vault_info, lamports)? {
+            (false, REASON_AGENT_RESERVE_VIOLATION)
+        } else if policy.breaches_min_reserve(&vault_info, lamports) {
+            (false, REASON_RESERVE_PROTECTED)
+        } else if exceeds_limit(
+            policy.spent_today(&clock),
+            amount,
+            policy.daily_budget_lamports,
+        ) {
+            (false, REASON_BUDGET_EXCEEDED)
+        } else if policy.exceeds_weekly_budget(amount) {
+            (false, REASON_WEEKLY_EXCEEDED)
+        } else if policy.exceeds_monthly_budget(amount) {
+            (false, REASON_MONTHLY_BUDGET_EXCEEDED)
+        } else if policy.exceeds_agent_weekly_budget(true, amount) {
+            (false, REASON_AGENT_WEEKLY_BUDGET)
+        } else {
+            (true, REASON_OK)
+        };
+
+        record_spend(
+            policy,
+            &mut ctx.accounts.audit_event,
+            &ctx.accounts.vault,
+            &ctx.accounts.recipient,
+            &ctx.accounts.authority.to_account_info(),
+            true,
+            amount,
+            decision,
+            Rent::get()?.minimum_balance(AuditEvent::SIZE),
+            &clock,
+            None,
+            0,
+            ctx.accounts.notify_program.as_ref(),
+        )?;
+
+        if decision.0 {
+            pay_from_vault(&vault_info, &ctx.accounts.recipient, lamports)?;
+            policy.pending_lamports = policy.pending_lamports.saturating_sub(lamports);
+            ctx.accounts
+                .pending_spend
+                .close(ctx.accounts.payer.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    /// D.13) Authority rejects a `PendingSpend`, closing it (rent back to its
+    /// payer) and releasing its lamports from `pending_lamports`. Works after
+    /// expiry too, to clean up.
+    pub fn reject_spend(ctx: Context<RejectSpend>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        let pending = &ctx.accounts.pending_spend;
+        policy.pending_lamports = policy.pending_lamports.saturating_sub(pending.lamports()?);
+        emit!(PendingSpendRejected {
+            policy: pending.policy,
+            recipient: pending.recipient,
+            amount: pending.amount,
+            id: pending.id,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// D.14) Pay several recipients from the vault in one instruction.
+    ///
+    /// `remaining_accounts` holds one writable `(recipient, BlockedRecipient
+    /// PDA)` pair per entry of `amounts`, at most `MAX_BATCH_RECIPIENTS`. The
+    /// batch total is checked against the same limits as a single spend, and
+    /// every recipient against the per-payment checks; if any fails the
+    /// instruction errors with `BatchDenied` (reason logged) and nothing is
+    /// paid. One `BatchAuditEvent` records the total and per-recipient split.
+    ///
+    /// Not available (`BatchUnsupported`) while the policy needs per-spend
+    /// accounts: a spend hook, fixed amounts, recipient signatures, the owner
+    /// check, the allowlist, a recipient registry, per-recipient limits or a
+    /// price feed; nor for the
+    /// agent under first-spend approval or registered recipients.
+    pub fn spend_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SpendBatch<'info>>,
+        amounts: Vec<u64>,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        let caller_key = ctx.accounts.caller.key();
+
+        // ── Authorization: caller must be authority or agent ──
+        let (is_authority, is_agent) =
+            authorize_caller(policy, &caller_key, ctx.accounts.agent_entry.as_deref())?;
+        require!(
+            !amounts.is_empty() && amounts.len() <= MAX_BATCH_RECIPIENTS,
+            VaultError::InvalidBatchSize
+        );
+        require!(
+            ctx.remaining_accounts.len() == amounts.len() * 2,
+            VaultError::InvalidBatchAccounts
+        );
+        require!(
+            policy.hook_program.is_none()
+                && !policy.enforce_fixed_amounts
+                && !policy.require_recipient_signature
+                && !policy.require_owner_exists
+                && !policy.allowlist_enabled
+                && !policy.tracks_recipients()
+                && policy.price_feed.is_none()
+                && policy.registry_program.is_none()
+                && (is_authority
+                    || !(policy.needs_first_spend_approval()
+                        || policy.agent_requires_registered_recipient)),
+            VaultError::BatchUnsupported
+        );
+
+        let clock = Clock::get()?;
+        roll_policy_windows(policy, &clock);
+
+        // Per-recipient checks; the first failing one decides the batch.
+        let policy_key = policy.key();
+        let mut entry_denial = None;
+        let mut total: u64 = 0;
+        for (pair, &amount) in ctx.remaining_accounts.chunks_exact(2).zip(&amounts) {
+            let (recipient, blocked_recipient) = (&pair[0], &pair[1]);
+            let (blocked_key, _) = Pubkey::find_program_address(
+                &[b"blocked", policy_key.as_ref(), recipient.key.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(
+                blocked_recipient.key(),
+                blocked_key,
+                VaultError::InvalidBatchAccounts
+            );
+            total = total
+                .checked_add(amount)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            let reason = if *blocked_recipient.owner == crate::ID {
+                REASON_RECIPIENT_BLOCKED
+            } else if amount == 0 {
+                REASON_INVALID_AMOUNT
+            } else if policy.is_invalid_recipient(&policy_key, recipient.key) {
+                REASON_INVALID_RECIPIENT
+            } else if policy.blocks_program_recipient(recipient) {
+                REASON_RECIPIENT_IS_PROGRAM
+            } else {
+                continue;
+            };
+            entry_denial.get_or_insert(reason);
+        }
+
+        if let Some(agent_entry) = ctx.accounts.agent_entry.as_mut() {
+            agent_entry.roll_day(policy.day_index);
+        }
+        let agent_over_budget = ctx
+            .accounts
+            .agent_entry
+            .as_ref()
+            .is_some_and(|entry| entry.exceeds_daily_budget(total));
+
+        let vault_info = ctx.accounts.vault.to_account_info();
+        let reason_code = if policy.in_denial_backoff(&clock) {
+            REASON_BACKOFF
+        } else if let Some(reason) = entry_denial {
+            reason
+        } else if policy.max_spends_per_day > 0
+            && policy
+                .allowed_count_today
+                .saturating_add(amounts.len() as u32)
+                > policy.max_spends_per_day
+        {
+            REASON_COUNT_LIMIT
+        } else if policy.breaches_agent_reserve(&caller_key, &vault_info, policy.scaled(total)?)? {
+            REASON_AGENT_RESERVE_VIOLATION
+        } else if policy.breaches_min_reserve(&vault_info, policy.scaled(total)?) {
+            REASON_RESERVE_PROTECTED
+        } else if policy.paused {
+            REASON_PAUSED
+        } else if policy.in_maintenance(&clock) {
+            REASON_MAINTENANCE
+        } else if policy.seconds_until_window_opens(clock.unix_timestamp) > 0 {
+            REASON_OUTSIDE_WINDOW
+        } else if exceeds_limit(
+            policy.spent_today(&clock),
+            total,
+            policy.daily_budget_lamports,
+        ) {
+            REASON_BUDGET_EXCEEDED
+        } else if policy.exceeds_release_schedule(&clock, policy.daily_budget_lamports, total) {
+            REASON_RELEASE_SCHEDULE
+        } else if policy.exceeds_weekly_budget(total) {
+            REASON_WEEKLY_EXCEEDED
+        } else if policy.exceeds_monthly_budget(total) {
+            REASON_MONTHLY_BUDGET_EXCEEDED
+        } else if policy.exceeds_agent_weekly_budget(is_agent, total) {
+            REASON_AGENT_WEEKLY_BUDGET
+        } else if agent_over_budget {
+            REASON_AGENT_BUDGET_EXCEEDED
+        } else if policy.in_cooldown(&clock) {
+            REASON_COOLDOWN
+        } else if policy.slot_already_spent(&clock) {
+            REASON_SLOT_ALREADY_SPENT
+        } else if policy.needs_approval(is_authority, total) {
+            REASON_APPROVAL_REQUIRED
+        } else {
+            REASON_OK
+        };
+        if reason_code != REASON_OK {
+            msg!("spend_batch denied: reason {}", reason_code);
+            return err!(VaultError::BatchDenied);
+        }
+
+        policy.allowed_count_today = policy
+            .allowed_count_today
+            .saturating_add(amounts.len() as u32);
+        count_allowed_spend(policy, &caller_key, is_agent, total, &clock, true)?;
+        if let Some(agent_entry) = ctx.accounts.agent_entry.as_mut() {
+            agent_entry.record(total);
+        }
+        let mut entries = Vec::with_capacity(amounts.len());
+        for (pair, &amount) in ctx.remaining_accounts.chunks_exact(2).zip(&amounts) {
+            pay_from_vault(&vault_info, &pair[0], policy.scaled(amount)?)?;
+            entries.push(BatchEntry {
+                recipient: pair[0].key(),
+                amount,
+            });
+        }
+
+        let batch = &mut ctx.accounts.batch_audit;
+        batch.policy = policy_key;
+        batch.batch_id = policy.next_batch_id;
+        batch.ts = clock.unix_timestamp;
+        batch.caller = caller_key;
+        batch.total = total;
+        batch.policy_version = policy.policy_version;
+        batch.bump = ctx.bumps.batch_audit;
+        batch.entries = entries;
+        policy.next_batch_id = policy.next_batch_id.saturating_add(1);
+
+        emit!(BatchSpendRecorded {
+            policy: policy_key,
+            batch_id: batch.batch_id,
+            caller: caller_key,
+            total,
+            count: amounts.len() as u8,
+            ts: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// D.15) `spend_intent` guarded by a client-chosen `idempotency_key`.
+    ///
+    /// The key seeds a `SpendReceipt` PDA (`[b"spend_receipt", policy, key]`)
+    /// created in the same instruction, so a retry with the same key fails
+    /// with "already in use" instead of paying twice. The AuditEvent PDA is
+    /// still seeded by `next_sequence`; the receipt records the outcome and
+    /// the sequence the key produced (a `denial_sequence` number when
+    /// `out_of_sequence`, as in `SpendRecorded`).
+    pub fn spend_intent_idempotent<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SpendIntentIdempotent<'info>>,
+        amount: u64,
+        idempotency_key: [u8; 16],
+    ) -> Result<()> {
+        let (allowed, reason_code) = run_spend_intent(
+            &mut ctx.accounts.spend,
+            ctx.remaining_accounts,
+            amount,
+            0,
+            None,
+            Rent::get()?.minimum_balance(SpendReceipt::SIZE),
+        )?;
+
+        let policy = &ctx.accounts.spend.policy;
+        let out_of_sequence = !allowed && policy.sequence_on_allowed_only;
+        let receipt = &mut ctx.accounts.spend_receipt;
+        receipt.policy = policy.key();
+        receipt.idempotency_key = idempotency_key;
+        // `record_spend` advanced exactly one of the two counters.
+        receipt.sequence = if out_of_sequence {
+            policy.denial_sequence.saturating_sub(1)
+        } else {
+            policy.next_sequence.saturating_sub(1)
+        };
+        receipt.allowed = allowed;
+        receipt.reason_code = reason_code;
+        receipt.out_of_sequence = out_of_sequence;
+        receipt.bump = ctx.bumps.spend_receipt;
+        Ok(())
+    }
+
+    /// E) Reclaim rent from an old AuditEvent account. Authority only.
+    ///
+    /// The sequence is recorded on the policy (see `Policy::mark_audit_closed`)
+    /// so readers can tell a reclaimed event from a missing one.
+    pub fn close_audit_event(ctx: Context<CloseAuditEvent>) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        policy.mark_audit_closed(ctx.accounts.audit_event.sequence);
+        // The `close` constraint in the Accounts struct handles lamport transfer.
+        Ok(())
+    }
+
+    /// E.3) Reclaim rent from a per-recipient spend tracker. Authority only.
+    ///
+    /// The tracker's lifetime total is first carried into the recipient's
+    /// `RecipientLifetime` PDA (created on first close), so closing and later
+    /// recreating a tracker does not reset lifetime accounting.
+    pub fn close_recipient_spend(ctx: Context<CloseRecipientSpend>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.policy.authority,
+            VaultError::Unauthorized
+        );
+
+        let recipient_lifetime = &mut ctx.accounts.recipient_lifetime;
+        if recipient_lifetime.policy == Pubkey::default() {
+            // init_if_needed created the account; fill fixed fields.
+            recipient_lifetime.policy = ctx.accounts.policy.key();
+            recipient_lifetime.recipient = ctx.accounts.recipient.key();
+            recipient_lifetime.bump = ctx.bumps.recipient_lifetime;
+        }
+        recipient_lifetime.lifetime_spent_lamports = recipient_lifetime
+            .lifetime_spent_lamports
+            .saturating_add(ctx.accounts.recipient_spend.lifetime_spent_lamports);
+
+        // The `close` constraint in the Accounts struct handles lamport transfer.
+        Ok(())
+    }
+
+    /// E.4) Sweep lamports mistakenly sent to one of this policy's PDAs.
+    ///
+    /// Only the balance above the target's rent-exempt minimum is moved to the
+    /// authority, so the account itself stays intact. The vault is not a valid
+    /// target — its balance is spendable funds, not a mistake.
+    pub fn recover_misdirected(ctx: Context<RecoverMisdirected>) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+
+        let target = ctx.accounts.target.to_account_info();
+        let belongs_to_policy = {
+            let data = target.try_borrow_data()?;
+            let disc = data.get(..8).ok_or(VaultError::InvalidRecoveryTarget)?;
+            if disc == Policy::DISCRIMINATOR {
+                target.key() == policy.key()
+            } else if disc == AuditEvent::DISCRIMINATOR {
+                AuditEvent::try_deserialize(&mut &data[..])?.policy == policy.key()
+            } else if disc == RecipientSpend::DISCRIMINATOR {
+                RecipientSpend::try_deserialize(&mut &data[..])?.policy == policy.key()
+            } else if disc == RecipientLifetime::DISCRIMINATOR {
+                RecipientLifetime::try_deserialize(&mut &data[..])?.policy == policy.key()
+            } else if disc == RecipientAllow::DISCRIMINATOR {
+                RecipientAllow::try_deserialize(&mut &data[..])?.policy == policy.key()
+            } else if disc == AllowedRecipient::DISCRIMINATOR {
+                AllowedRecipient::try_deserialize(&mut &data[..])?.policy == policy.key()
+            } else if disc == BlockedRecipient::DISCRIMINATOR {
+                BlockedRecipient::try_deserialize(&mut &data[..])?.policy == policy.key()
+            } else if disc == Allowance::DISCRIMINATOR {
+                Allowance::try_deserialize(&mut &data[..])?.policy == policy.key()
+            } else {
+                false
+            }
+        };
+        require!(belongs_to_policy, VaultError::InvalidRecoveryTarget);
+
+        let min_balance = Rent::get()?.minimum_balance(target.data_len());
+        let amount = target.lamports().saturating_sub(min_balance);
+        require!(amount > 0, VaultError::NothingToRecover);
+
+        let authority_info = ctx.accounts.authority.to_account_info();
+        **target.try_borrow_mut_lamports()? -= amount;
+        **authority_info.try_borrow_mut_lamports()? = authority_info
+            .lamports()
+            .checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+
+        emit!(MisdirectedRecovered {
+            policy: policy.key(),
+            target: target.key(),
+            amount,
+            authority: authority_info.key(),
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// E.5) Move the policy to another vault owned by the authority.
+    ///
+    /// The policy PDA is seeded by its vault, so it cannot be re-pointed in
+    /// place: its state (rules, counters, sequence, event hash) is copied to a
+    /// new Policy PDA under `new_vault` and the old one is closed to the
+    /// authority. Accounts seeded by the old policy key — audit events,
+    /// trackers, registrations, allowances, timelocked claims, fixed amounts,
+    /// token budgets, version history — stay behind and cannot be closed once it is gone, so
+    /// close or export them first and recreate what is still needed.
+    pub fn reassign_policy_vault(ctx: Context<ReassignPolicyVault>) -> Result<()> {
+        let old_policy = &ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            old_policy.authority,
+            VaultError::Unauthorized
+        );
+
+        let new_policy = &mut ctx.accounts.new_policy;
+        new_policy.set_inner((**old_policy).clone());
+        new_policy.vault = ctx.accounts.new_vault.key();
+        new_policy.bump = ctx.bumps.new_policy;
+        // Claims and pending spends left behind cannot be settled against the
+        // new policy.
+        new_policy.timelocked_lamports = 0;
+        new_policy.pending_lamports = 0;
+        new_policy.policy_version = new_policy.policy_version.saturating_add(1);
+
+        emit!(PolicyReassigned {
+            old_policy: old_policy.key(),
+            new_policy: new_policy.key(),
+            old_vault: ctx.accounts.vault.key(),
+            new_vault: ctx.accounts.new_vault.key(),
+            authority: ctx.accounts.authority.key(),
+            policy_version: new_policy.policy_version,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        // The `close` constraint in the Accounts struct handles lamport transfer.
+        Ok(())
+    }
+
+    /// E.6) Close the Policy PDA, returning its rent to the authority.
+    /// Authority only; not while the policy is locked.
+    ///
+    /// Accounts seeded by the policy key (audit events, trackers, allowlist and
+    /// blocklist entries, allowances, pending spends, timelocked claims) are not
+    /// tracked on-chain, so this cannot check for them. They can no longer be
+    /// closed once the policy is gone: close or export them first. Spends stop
+    /// working immediately; the owner can still `withdraw` the vault's funds.
+    pub fn close_policy(ctx: Context<ClosePolicy>) -> Result<()> {
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            ctx.accounts.policy.authority,
+            VaultError::Unauthorized
+        );
+        // The `close` constraint in the Accounts struct handles lamport transfer.
+        Ok(())
+    }
+
+    /// E.7) Close the Vault PDA, returning its rent to the owner. Vault owner
+    /// only.
+    ///
+    /// The vault's policy must already be closed (`close_policy`) and its
+    /// balance drained to the rent-exempt minimum (`withdraw`), so no funds
+    /// or spend rules are lost with it.
+    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+        let policy = ctx.accounts.policy.to_account_info();
+        require!(
+            policy.lamports() == 0 && policy.data_is_empty(),
+            VaultError::PolicyStillOpen
+        );
+        let rent_exempt = Rent::get()?.minimum_balance(Vault::SIZE);
+        require!(
+            ctx.accounts.vault.to_account_info().lamports() <= rent_exempt,
+            VaultError::VaultNotEmpty
+        );
+        // The `close` constraint in the Accounts struct handles lamport transfer.
+        Ok(())
+    }
+
+    /// E.8) Reclaim rent from up to `MAX_AUDIT_CLOSE_BATCH` AuditEvents at
+    /// once. Authority only.
+    ///
+    /// Pass the AuditEvent PDAs (writable) as `remaining_accounts`; each must
+    /// belong to this policy, otherwise the call fails and nothing is closed.
+    /// Sequences are recorded as by `close_audit_event`.
+    pub fn close_audit_events_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseAuditEventsBatch<'info>>,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+        require!(
+            (1..=MAX_AUDIT_CLOSE_BATCH).contains(&ctx.remaining_accounts.len()),
+            VaultError::InvalidAuditCloseBatch
+        );
+
+        for info in ctx.remaining_accounts.iter() {
+            let audit = Account::<AuditEvent>::try_from(info)
+                .map_err(|_| error!(VaultError::InvalidAuditEvent))?;
+            require_keys_eq!(audit.policy, policy.key(), VaultError::InvalidAuditEvent);
+            policy.mark_audit_closed(audit.sequence);
+            audit.close(ctx.accounts.authority.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    /// F) Grow an account created under an older layout to its current size.
+    ///
+    /// Fields added after the initial release are appended at the end, so
+    /// resizing zero-fills them — which is the default for every such field.
+    /// Permissionless: the payer only covers the extra rent.
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        let info = ctx.accounts.account.to_account_info();
+        let target_len = {
+            let data = info.try_borrow_data()?;
+            let disc = data.get(..8).ok_or(VaultError::UnknownAccountType)?;
+            if disc == Policy::DISCRIMINATOR {
+                Policy::SIZE
+            } else if disc == RecipientSpend::DISCRIMINATOR {
+                RecipientSpend::SIZE
+            } else if disc == AuditEvent::DISCRIMINATOR {
+                AuditEvent::SIZE
+            } else if disc == Allowance::DISCRIMINATOR {
+                Allowance::SIZE
+            } else if disc == RecipientAllow::DISCRIMINATOR {
+                RecipientAllow::SIZE
+            } else {
+                return err!(VaultError::UnknownAccountType);
+            }
+        };
+        if info.data_len() >= target_len {
+            return Ok(());
+        }
+
+        let min_balance = Rent::get()?.minimum_balance(target_len);
+        let top_up = min_balance.saturating_sub(info.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        info.resize(target_len)?;
+        Ok(())
+    }
+
+    /// G) Read-only summary of many policies in one call (multi-vault dashboards).
+    ///
+    /// Pass Policy PDAs as `remaining_accounts` (at most `MAX_POLICIES_PER_SUMMARY`).
+    /// Each must be owned by this program and deserialize as a `Policy`, otherwise
+    /// the call fails. Summaries are returned in input order via return data.
+    pub fn get_policies_summary(ctx: Context<GetPoliciesSummary>) -> Result<Vec<PolicySummary>> {
+        require!(
+            ctx.remaining_accounts.len() <= MAX_POLICIES_PER_SUMMARY,
+            VaultError::TooManyAccounts
+        );
+        let clock = Clock::get()?;
+
+        let mut summaries = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts.iter() {
+            require_keys_eq!(*info.owner, crate::ID, VaultError::InvalidPolicyAccount);
+            let data = info.try_borrow_data()?;
+            let policy = Policy::try_deserialize(&mut &data[..])
+                .map_err(|_| error!(VaultError::InvalidPolicyAccount))?;
+
+            let spent_today = policy.spent_today(&clock);
+            summaries.push(PolicySummary {
+                daily_budget_lamports: policy.daily_budget_lamports,
+                remaining_today_lamports: policy.daily_budget_lamports.saturating_sub(spent_today),
+                paused: policy.paused,
+                policy_version: policy.policy_version,
+            });
+        }
+        Ok(summaries)
+    }
+
+    /// H) Read-only projection of today's end-of-day spend at the current rate:
+    /// `spent_today * period / elapsed_in_period`, saturating at `u64::MAX`.
+    ///
+    /// Elapsed time is floored at one unit so the first second (or slot) of a
+    /// window cannot divide by zero. Returned via return data.
+    pub fn project_eod_spend(ctx: Context<ProjectEodSpend>) -> Result<u64> {
+        let policy = &ctx.accounts.policy;
+        let clock = Clock::get()?;
+
+        let (elapsed, period) = policy.day_progress(&clock);
+        let projected =
+            policy.spent_today(&clock) as u128 * period as u128 / elapsed.max(1) as u128;
+        Ok(u64::try_from(projected).unwrap_or(u64::MAX))
+    }
+
+    /// I) Read-only check of a recipient's merkle proof against the current
+    /// `allowlist_merkle_root`, so clients can detect stale proofs before
+    /// building a spend. Always `false` while no root is set.
+    pub fn verify_merkle_proof(
+        ctx: Context<VerifyMerkleProof>,
+        recipient: Pubkey,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<bool> {
+        let root = ctx.accounts.policy.allowlist_merkle_root;
+        Ok(root != [0; 32] && merkle_proof_valid(&root, &recipient, &proof))
+    }
+
+    /// J) Read-only bitmask of the policy's boolean flags (`FLAG_*` bits).
+    ///
+    /// Bit positions are stable; new flags only ever take the next free bit.
+    pub fn get_policy_flags(ctx: Context<GetPolicyFlags>) -> Result<u32> {
+        Ok(ctx.accounts.policy.flags())
+    }
+
+    /// K) A recipient returns lamports to the vault and the refund is credited
+    /// back against today's spend counters.
+    ///
+    /// Transfers `amount` from the signing `refunder` into the vault, then
+    /// reduces the policy's daily, weekly and monthly counters (saturating);
+    /// in rolling mode the last 24 hours' spends are credited, newest first
+    /// (see `refund_rolling_spend`). Pass the
+    /// refunder's `RecipientSpend` tracker to credit its lifetime total and, while
+    /// it tracks the current day, its daily total as well.
+    ///
+    /// With `auto_close_empty_tracker` on, a tracker left at zero on both is
+    /// closed to the authority, when the authority account is passed.
+    pub fn record_refund(ctx: Context<RecordRefund>, amount: u64) -> Result<()> {
+        require!(amount > 0, VaultError::InvalidRefundAmount);
+
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.refunder.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            ctx.accounts.policy.scaled(amount)?,
+        )?;
+
+        let policy = &mut ctx.accounts.policy;
+        let clock = Clock::get()?;
+        roll_policy_windows(policy, &clock);
+        policy.spent_today_lamports = policy.spent_today_lamports.saturating_sub(amount);
+        policy.refund_rolling_spend(clock.unix_timestamp, amount);
+        policy.spent_this_week_lamports = policy.spent_this_week_lamports.saturating_sub(amount);
+        policy.spent_this_month_lamports = policy.spent_this_month_lamports.saturating_sub(amount);
+
+        let mut tracker_empty = false;
+        if let Some(recipient_spend) = ctx.accounts.recipient_spend.as_mut() {
+            if recipient_spend.day_index == policy.day_index {
+                recipient_spend.spent_today_lamports =
+                    recipient_spend.spent_today_lamports.saturating_sub(amount);
+            }
+            recipient_spend.lifetime_spent_lamports = recipient_spend
+                .lifetime_spent_lamports
+                .saturating_sub(amount);
+            tracker_empty = recipient_spend.spent_today_lamports == 0
+                && recipient_spend.lifetime_spent_lamports == 0;
+        }
+
+        emit!(RefundRecorded {
+            policy: policy.key(),
+            recipient: ctx.accounts.refunder.key(),
+            amount,
+            ts: clock.unix_timestamp,
+        });
+
+        if tracker_empty && policy.auto_close_empty_tracker {
+            if let (Some(recipient_spend), Some(authority)) = (
+                ctx.accounts.recipient_spend.as_mut(),
+                ctx.accounts.authority.as_ref(),
+            ) {
+                recipient_spend.close(authority.to_account_info())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// L) Read-only solvency check: vault lamports above rent, minus outstanding
+    /// commitments, minus `reserved_for_authority_lamports`. Negative means the
+    /// vault cannot cover everything it has promised.
+    ///
+    /// Pass commitment PDAs as `remaining_accounts`. Tallied types:
+    /// - `Allowance`: its accrued balance as of now (frozen ones included)
+    /// - `TimelockedClaim`: its full amount, locked or not
+    ///
+    /// Each must belong to this policy, otherwise the call fails. Returned via
+    /// return data.
+    ///
+    /// Timelocked claims and pending spends are also held back from
+    /// `withdraw` (via `timelocked_lamports` and `pending_lamports`), but
+    /// allowances accrue lazily and nothing reserves them: an owner
+    /// withdrawal can leave accrued allowances unfunded, and their claims are
+    /// then denied by the reserve checks. Use this call to watch for that.
+    pub fn check_solvency<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CheckSolvency<'info>>,
+    ) -> Result<i64> {
+        let policy = &ctx.accounts.policy;
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut committed: u128 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            let (owner_policy, outstanding) =
+                if let Ok(allowance) = Account::<Allowance>::try_from(info) {
+                    (
+                        allowance.policy,
+                        units_to_lamports(allowance.accrued_at(now), allowance.budget_scale)?,
+                    )
+                } else {
+                    let claim = Account::<TimelockedClaim>::try_from(info)
+                        .map_err(|_| error!(VaultError::InvalidCommitmentAccount))?;
+                    (claim.policy, claim.lamports()?)
+                };
+            require_keys_eq!(
+                owner_policy,
+                policy.key(),
+                VaultError::InvalidCommitmentAccount
+            );
+            committed += outstanding as u128;
+        }
+
+        let vault = ctx.accounts.vault.to_account_info();
+        let rent = Rent::get()?.minimum_balance(vault.data_len());
+        let free = vault.lamports() as i128
+            - rent as i128
+            - committed as i128
+            - policy.reserved_for_authority_lamports as i128;
+        Ok(free.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+
+    /// M) Read-only canonical PDA addresses for `owner`'s vault, so clients need
+    /// not re-derive seeds themselves.
+    ///
+    /// Pass the policy account to also get the next spend's AuditEvent address
+    /// (from `policy.next_sequence`); it must be the derived policy PDA.
+    pub fn derive_addresses(
+        ctx: Context<DeriveAddresses>,
+        owner: Pubkey,
+    ) -> Result<DerivedAddresses> {
+        let (vault, _) = Pubkey::find_program_address(&[b"vault", owner.as_ref()], &crate::ID);
+        let (policy, _) = Pubkey::find_program_address(&[b"policy", vault.as_ref()], &crate::ID);
+        let (fixed_amounts, _) =
+            Pubkey::find_program_address(&[b"fixed_amounts", policy.as_ref()], &crate::ID);
+
+        let next_audit_event = match ctx.accounts.policy.as_ref() {
+            Some(account) => {
+                require_keys_eq!(account.key(), policy, VaultError::InvalidPolicyAccount);
+                let seq = account.next_sequence.to_le_bytes();
+                Some(Pubkey::find_program_address(&[b"audit", policy.as_ref(), &seq], &crate::ID).0)
+            }
+            None => None,
+        };
+
+        Ok(DerivedAddresses {
+            vault,
+            policy,
+            fixed_amounts,
+            next_audit_event,
+        })
+    }
+
+    /// N) Re-emit AuditEvents as `AuditExported` log records for archiving,
+    /// optionally closing them (rent to the authority) in the same call.
+    ///
+    /// Pass the AuditEvent PDAs as `remaining_accounts` (writable when
+    /// `close`). Each must belong to this policy, otherwise the call fails.
+    /// Closed sequences are recorded as by `close_audit_event`. Authority only.
+    pub fn export_audit_events<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExportAuditEvents<'info>>,
+        close: bool,
+    ) -> Result<()> {
+        let policy = &mut ctx.accounts.policy;
+        require_keys_eq!(
+            ctx.accounts.authority.key(),
+            policy.authority,
+            VaultError::Unauthorized
+        );
+
+        for info in ctx.remaining_accounts.iter() {
+            let audit = Account::<AuditEvent>::try_from(info)
+                .map_err(|_| error!(VaultError::InvalidAuditEvent))?;
+            require_keys_eq!(audit.policy, policy.key(), VaultError::InvalidAuditEvent);
+
+            emit!(AuditExported {
+                audit_event: info.key(),
+                policy: audit.policy,
+                sequence: audit.sequence,
+                ts: audit.ts,
+                recipient: audit.recipient,
+                amount: audit.amount,
+                allowed: audit.allowed,
+                reason_code: audit.reason_code,
+                policy_version: audit.policy_version,
+                environment_tag: audit.environment_tag,
+                mint: audit.mint,
+                region_code: audit.region_code,
+                caller: audit.caller,
+                caller_is_agent: audit.caller_is_agent,
+            });
+            if close {
+                policy.mark_audit_closed(audit.sequence);
+                audit.close(ctx.accounts.authority.to_account_info())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// O) Append the current `policy_version` and its `config_digest` to the
+    /// policy's `VersionHistory` PDA. Permissionless; the payer funds growth.
+    ///
+    /// Idempotent: a no-op when the latest entry already records the current
+    /// version. Send it after each setter (same transaction) to keep the
+    /// history gap-free.
+    pub fn record_policy_version(ctx: Context<RecordPolicyVersion>) -> Result<()> {
+        let policy = &ctx.accounts.policy;
+        let history = &mut ctx.accounts.version_history;
+        if history.policy == Pubkey::default() {
+            // init_if_needed created the account; fill fixed fields.
+            history.policy = policy.key();
+            history.bump = ctx.bumps.version_history;
+        }
+        if history
+            .entries
+            .last()
+            .is_some_and(|entry| entry.policy_version == policy.policy_version)
+        {
+            return Ok(());
+        }
+
+        let info = history.to_account_info();
+        let target_len = VersionHistory::space(history.entries.len() + 1);
+        let min_balance = Rent::get()?.minimum_balance(target_len);
+        let top_up = min_balance.saturating_sub(info.lamports());
+        if top_up > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: info.clone(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        info.resize(target_len)?;
+
+        history.entries.push(VersionEntry {
+            policy_version: policy.policy_version,
+            digest: policy.config_digest()?,
+            ts: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// P) Read-only wait, in seconds, before a spend could pass the pause,
+    /// maintenance and cooldown checks (see `Policy::seconds_until_spendable`).
+    /// Returned via return data.
+    pub fn seconds_until_spendable(ctx: Context<SecondsUntilSpendable>) -> Result<i64> {
+        let clock = Clock::get()?;
+        Ok(ctx.accounts.policy.seconds_until_spendable(&clock))
+    }
+
+    /// Q) Read-only daily budget status as of now: what is left of today's
+    /// budget (a rolled-over day counts as unspent, without writing the
+    /// policy), seconds until the cooldown clears, and the pause flag.
+    /// Returned via return data.
+    pub fn remaining_budget(ctx: Context<RemainingBudget>) -> Result<DailyBudgetStatus> {
+        let policy = &ctx.accounts.policy;
+        let clock = Clock::get()?;
+        Ok(DailyBudgetStatus {
+            remaining_today: policy
+                .daily_budget_lamports
+                .saturating_sub(policy.spent_today(&clock)),
+            seconds_until_cooldown_clears: policy.seconds_until_cooldown_clears(&clock),
+            paused: policy.paused,
+        })
+    }
+
+    /// R) Read-only lamports the vault could pay out right now: its balance
+    /// less the lamports held for timelocked claims and less the larger of its
+    /// rent-exempt minimum (for `Vault::SIZE`) and `min_reserve_lamports`.
+    /// Budgets and cooldowns are not applied. Returned via return data.
+    pub fn spendable_balance(ctx: Context<SpendableBalance>) -> Result<u64> {
+        let policy = &ctx.accounts.policy;
+        let floor = Rent::get()?
+            .minimum_balance(Vault::SIZE)
+            .max(policy.min_reserve_lamports);
+        Ok(ctx
+            .accounts
+            .vault
+            .to_account_info()
+            .lamports()
+            .saturating_sub(policy.timelocked_lamports)
+            .saturating_sub(floor))
+    }
+
+    /// S) Read-only dry run of `spend_intent`: the outcome it would record for
+    /// `caller` paying `amount` to `recipient` now, without creating an
+    /// AuditEvent, touching counters or moving lamports. The spend hook is not
+    /// called and signature requirements are not checked; an agent spend over
+    /// the approval threshold reports `REASON_APPROVAL_REQUIRED`. Pass the price
+    /// feed in `remaining_accounts` when one is configured. Returned via return
+    /// data.
+    pub fn simulate_spend<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SimulateSpend<'info>>,
+        amount: u64,
+    ) -> Result<SpendSimulation> {
+        let clock = Clock::get()?;
+        let mut policy = ctx.accounts.policy.clone().into_inner();
+        policy.roll_windows(&clock);
+        let caller_key = ctx.accounts.caller.key();
+        let (is_authority, is_agent) =
+            authorize_caller(&policy, &caller_key, ctx.accounts.agent_entry.as_deref())?;
+
+        let agent_over_budget = ctx.accounts.agent_entry.as_deref().is_some_and(|entry| {
+            let mut entry = entry.clone();
+            entry.roll_day(policy.day_index);
+            entry.exceeds_daily_budget(amount)
+        });
+        let usd_cents = usd_cents_for(
+            &policy,
+            ctx.remaining_accounts,
+            clock.unix_timestamp,
+            amount,
+        )?;
+        let (allowed, reason_code) = spend_intent_decision(
+            &policy,
+            &ctx.accounts.policy.key(),
+            &caller_key,
+            is_agent,
+            &ctx.accounts.vault.to_account_info(),
+            &ctx.accounts.recipient,
+            ctx.accounts.fixed_amounts.as_deref(),
+            ctx.accounts.recipient_allow.as_deref(),
+            ctx.accounts.owner.as_deref(),
+            *ctx.accounts.blocked_recipient.owner == crate::ID,
+            true,
+            policy.daily_budget_lamports,
+            agent_over_budget,
+            false,
+            usd_cents,
+            &clock,
+            amount,
+        )?;
+        Ok(if allowed && policy.needs_approval(is_authority, amount) {
+            SpendSimulation {
+                allowed: false,
+                reason_code: REASON_APPROVAL_REQUIRED,
+            }
+        } else {
+            SpendSimulation {
+                allowed,
+                reason_code,
+            }
+        })
+    }
+}
+
+// ──────────────── Accounts ────────────────
+
+/// One allowed spend in `Policy::rolling_spends`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RollingSpend {
+    pub ts: i64,     // 8
+    pub amount: u64, // 8
+}
+
+#[account]
+pub struct Vault {
+    pub owner: Pubkey, // 32
+    pub bump: u8,      // 1
+}
+
+// 8 discriminator + 32 + 1 = 41
+impl Vault {
+    pub const SIZE: usize = 8 + 32 + 1;
+}
+
+#[account]
+pub struct Policy {
+    pub vault: Pubkey,              // 32
+    pub authority: Pubkey,          // 32
+    pub agent: Option<Pubkey>,      // 1 + 32 = 33
+    pub daily_budget_lamports: u64, // 8
+    pub spent_today_lamports: u64,  // 8
+    pub day_index: i64,             // 8
+    pub cooldown_seconds: u32,      // 4
+    pub last_spend_ts: i64,         // 8
+    pub next_sequence: u64,         // 8
+
+    // ── advanced policy fields ──
+    pub paused: bool,                          // 1
+    pub allowlist_enabled: bool,               // 1
+    pub allowed_recipient: Option<Pubkey>,     // 1 + 32 = 33
+    pub per_recipient_daily_cap_lamports: u64, // 8
+    pub policy_version: u16,                   // 2
+
+    pub bump: u8, // 1
+
+    // ── appended fields (zero default; grown in place by `migrate_account`) ──
+    pub per_recipient_max_count: u16,              // 2
+    pub time_unit: u8,                             // 1
+    pub last_spend_slot: u64,                      // 8
+    pub maintenance_start_ts: i64,                 // 8
+    pub maintenance_end_ts: i64,                   // 8
+    pub hook_program: Option<Pubkey>,              // 1 + 32 = 33
+    pub meter_account: Option<Pubkey>,             // 1 + 32 = 33
+    pub meter_offset: u32,                         // 4
+    pub meter_lamports_per_unit: u64,              // 8
+    pub require_first_spend_approval: bool,        // 1
+    pub has_spent_once: bool,                      // 1
+    pub one_spend_per_slot: bool,                  // 1
+    pub environment_tag: u8,                       // 1
+    pub enforce_fixed_amounts: bool,               // 1
+    pub per_recipient_cap_bps: u16,                // 2
+    pub agent_requires_registered_recipient: bool, // 1
+    pub monthly_budget_lamports: u64,              // 8
+    pub spent_this_month_lamports: u64,            // 8
+    pub month_index: i64,                          // 8
+    pub allowlist_merkle_root: [u8; 32],           // 32
+    pub require_recipient_signature: bool,         // 1
+    pub epoch_anchor_ts: i64,                      // 8
+    pub reserved_for_authority_lamports: u64,      // 8
+    pub last_event_hash: [u8; 32],                 // 32
+    pub agent_weekly_budget_lamports: u64,         // 8
+    pub agent_spent_this_week_lamports: u64,       // 8
+    pub agent_week_index: i64,                     // 8
+    pub sequence_on_allowed_only: bool,            // 1
+    pub denial_sequence: u64,                      // 8
+    pub linear_release: bool,                      // 1
+    pub allowed_count_today: u32,                  // 4
+    pub denied_count_today: u32,                   // 4
+    pub distinct_recipients_today: u32,            // 4
+    pub require_owner_exists: bool,                // 1
+    pub auto_close_empty_tracker: bool,            // 1
+    pub recipient_min_interval_seconds: u32,       // 4
+    pub recipient_throttle_attempts: bool,         // 1
+    pub budget_scale: u8,                          // 1
+    pub locked: bool,                              // 1
+    pub pending_authority: Option<Pubkey>,         // 1 + 32 = 33
+    pub weekly_budget_lamports: u64,               // 8
+    pub spent_this_week_lamports: u64,             // 8
+    pub week_index: i64,                           // 8
+    pub block_program_recipients: bool,            // 1
+    pub min_reserve_lamports: u64,                 // 8
+    pub recipient_cap_exclusive: bool,             // 1
+    pub denial_backoff_base_seconds: u32,          // 4
+    pub consecutive_denials: u32,                  // 4
+    pub last_denial_ts: i64,                       // 8
+    pub max_spend_gap_seconds: u32,                // 4
+    pub budget_mode: u8,                           // 1
+    pub rolling_spends: [RollingSpend; 16],        // 16 * 16 = 256
+    pub rolling_head: u8,                          // 1
+    pub ops_daily_budget_lamports: u64,            // 8
+    pub ops_funding_spent_today: u64,              // 8
+    pub ops_day_index: i64,                        // 8
+    pub notify_program: Option<Pubkey>,            // 1 + 32 = 33
+    pub notify_threshold_lamports: u64,            // 8
+    pub max_spends_per_day: u32,                   // 4
+    pub approval_threshold_lamports: u64,          // 8
+    pub approval_ttl_seconds: u32,                 // 4
+    pub next_pending_id: u64,                      // 8
+    pub allowed_start_seconds: u32,                // 4
+    pub allowed_end_seconds: u32,                  // 4
+    pub price_feed: Option<Pubkey>,                // 1 + 32 = 33
+    pub daily_budget_usd_cents: u64,               // 8
+    pub max_price_age_seconds: u32,                // 4
+    pub spent_today_usd_cents: u64,                // 8
+    pub audit_closed_through: u64,                 // 8
+    pub audit_closed_bitmap: u64,                  // 8
+    pub guardian: Option<Pubkey>,                  // 1 + 32 = 33
+    pub next_batch_id: u64,                        // 8
+    pub cooldown_scope: u8,                        // 1
+    pub registry_program: Option<Pubkey>,          // 1 + 32 = 33
+    pub per_recipient_lifetime_cap_lamports: u64,  // 8
+    pub timelocked_lamports: u64,                  // 8
+    pub day_offset_seconds: i32,                   // 4
+    pub pending_lamports: u64,                     // 8
+}
+
+// v1: 8 discriminator + 32 + 32 + 33 + 8 + 8 + 8 + 4 + 8 + 8 + 1 + 1 + 33 + 8 + 2 + 1 = 195
+// appended: 2 + 1 + 8 + 8 + 8 + 33 + 33 + 4 + 8 + 1 + 1 + 1 + 1 + 1 + 2 + 1 + 8 + 8 + 8 + 32 + 1 + 8 + 8 + 32 + 8 + 8 + 8 + 1 + 8 + 1 + 4 + 4 + 4 + 1 + 1 + 4 + 1 + 1 + 1 + 1 + 32 + 8 + 8 + 8 + 1 + 8 + 1 + 4 + 4 + 8 + 4 + 1 + 16 * 16 + 1 + 8 + 8 + 8 + 1 + 32 + 8 + 4 + 8 + 4 + 8 + 4 + 4 + 1 + 32 + 8 + 4 + 8 + 8 + 8 + 1 + 32 + 8 + 1 + 1 + 32 + 8 + 8 + 4 + 8 = 887
+// 195 + 887 = 1082
+impl Policy {
+    pub const SIZE: usize = 8
+        + 32
+        + 32
+        + 33
+        + 8
+        + 8
+        + 8
+        + 4
+        + 8
+        + 8
+        + 1
+        + 1
+        + 33
+        + 8
+        + 2
+        + 1
+        + (2 + 1
+            + 8
+            + 8
+            + 8
+            + 33
+            + 33
+            + 4
+            + 8
+            + 1
+            + 1
+            + 1
+            + 1
+            + 1
+            + 2
+            + 1
+            + 8
+            + 8
+            + 8
+            + 32
+            + 1
+            + 8
+            + 8
+            + 32
+            + 8
+            + 8
+            + 8
+            + 1
+            + 8
+            + 1
+            + 4
+            + 4
+            + 4
+            + 1
+            + 1
+            + 4
+            + 1
+            + 1
+            + 1
+            + 1
+            + 32
+            + 8
+            + 8
+            + 8
+            + 1
+            + 8
+            + 1
+            + 4
+            + 4
+            + 8
+            + 4
+            + 1
+            + 16 * 16
+            + 1
+            + 8
+            + 8
+            + 8
+            + 1
+            + 32
+            + 8
+            + 4
+            + 8
+            + 4
+            + 8
+            + 4
+            + 4
+            + 1
+            + 32
+            + 8
+            + 4
+            + 8
+            + 8
+            + 8
+            + 1
+            + 32
+            + 8
+            + 1
+            + 1
+            + 32
+            + 8
+            + 8
+            + 4
+            + 8);
+
+    /// Write a freshly created policy: the given parameters plus defaults for
+    /// everything else.
+    pub fn init(
+        &mut self,
+        vault: Pubkey,
+        authority: Pubkey,
+        agent: Option<Pubkey>,
+        daily_budget_lamports: u64,
+        cooldown_seconds: u32,
+        bump: u8,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.vault = vault;
+        self.authority = authority;
+        self.agent = agent;
+        self.daily_budget_lamports = daily_budget_lamports;
+        self.spent_today_lamports = 0;
+        self.day_index = now / SECONDS_PER_DAY;
+        self.time_unit = TIME_UNIT_SECONDS;
+        self.last_spend_slot = 0;
+        self.maintenance_start_ts = 0;
+        self.maintenance_end_ts = 0;
+        self.hook_program = None;
+        self.meter_account = None;
+        self.meter_offset = 0;
+        self.meter_lamports_per_unit = 0;
+        self.require_first_spend_approval = false;
+        self.has_spent_once = false;
+        self.one_spend_per_slot = false;
+        self.environment_tag = 0;
+        self.enforce_fixed_amounts = false;
+        self.per_recipient_cap_bps = 0;
+        self.agent_requires_registered_recipient = false;
+        self.monthly_budget_lamports = 0;
+        self.spent_this_month_lamports = 0;
+        self.month_index = month_index(now);
+        self.allowlist_merkle_root = [0; 32];
+        self.require_recipient_signature = false;
+        self.epoch_anchor_ts = 0;
+        self.reserved_for_authority_lamports = 0;
+        self.last_event_hash = [0; 32];
+        self.agent_weekly_budget_lamports = 0;
+        self.agent_spent_this_week_lamports = 0;
+        self.agent_week_index = now.div_euclid(SECONDS_PER_WEEK);
+        self.sequence_on_allowed_only = false;
+        self.denial_sequence = 0;
+        self.linear_release = false;
+        self.allowed_count_today = 0;
+        self.denied_count_today = 0;
+        self.distinct_recipients_today = 0;
+        self.require_owner_exists = false;
+        self.auto_close_empty_tracker = false;
+        self.recipient_min_interval_seconds = 0;
+        self.recipient_throttle_attempts = false;
+        self.budget_scale = 0;
+        self.locked = false;
+        self.pending_authority = None;
+        self.weekly_budget_lamports = 0;
+        self.spent_this_week_lamports = 0;
+        self.week_index = now.div_euclid(SECONDS_PER_WEEK);
+        self.block_program_recipients = false;
+        self.min_reserve_lamports = 0;
+        self.recipient_cap_exclusive = false;
+        self.denial_backoff_base_seconds = 0;
+        self.consecutive_denials = 0;
+        self.last_denial_ts = 0;
+        self.max_spend_gap_seconds = 0;
+        self.budget_mode = BUDGET_MODE_CALENDAR;
+        self.rolling_spends = [RollingSpend::default(); ROLLING_SPEND_SLOTS];
+        self.rolling_head = 0;
+        self.ops_daily_budget_lamports = 0;
+        self.ops_funding_spent_today = 0;
+        self.ops_day_index = 0;
+        self.notify_program = None;
+        self.notify_threshold_lamports = 0;
+        self.max_spends_per_day = 0;
+        self.approval_threshold_lamports = 0;
+        self.approval_ttl_seconds = 0;
+        self.next_pending_id = 0;
+        self.allowed_start_seconds = 0;
+        self.allowed_end_seconds = 0;
+        self.price_feed = None;
+        self.daily_budget_usd_cents = 0;
+        self.max_price_age_seconds = 0;
+        self.spent_today_usd_cents = 0;
+        self.audit_closed_through = 0;
+        self.audit_closed_bitmap = 0;
+        self.guardian = None;
+        self.next_batch_id = 0;
+        self.cooldown_scope = COOLDOWN_SCOPE_GLOBAL;
+        self.registry_program = None;
+        self.per_recipient_lifetime_cap_lamports = 0;
+        self.day_offset_seconds = 0;
+
+        self.cooldown_seconds = cooldown_seconds;
+        self.last_spend_ts = 0;
+        self.next_sequence = 0;
+        // advanced defaults
+        self.paused = false;
+        self.allowlist_enabled = false;
+        self.allowed_recipient = None;
+        self.per_recipient_daily_cap_lamports = 0;
+        self.per_recipient_max_count = 0;
+        self.policy_version = 1;
+        self.bump = bump;
+        Ok(())
+    }
+
+    /// Whether `amount` passes the fixed-amount whitelist. Fails closed when
+    /// enforcement is on but the `FixedAmounts` account was not passed.
+    pub fn amount_allowed(&self, fixed_amounts: Option<&FixedAmounts>, amount: u64) -> bool {
+        !self.enforce_fixed_amounts
+            || fixed_amounts.is_some_and(|fixed| fixed.amounts.contains(&amount))
+    }
+
+    /// Lamports a recipient may still receive today under the per-recipient cap
+    /// (`None` = no cap). With `recipient_cap_exclusive` it is one unit less,
+    /// so a spend may not bring the recipient's total exactly to the cap.
+    pub fn recipient_cap_headroom(&self, recipient_spent_today: u64) -> Option<u64> {
+        self.recipient_cap_lamports().map(|cap| {
+            cap.saturating_sub(recipient_spent_today)
+                .saturating_sub(self.recipient_cap_exclusive as u64)
+        })
+    }
+
+    /// Whether any per-recipient limit is on, making `RecipientSpend` tracking required.
+    pub fn tracks_recipients(&self) -> bool {
+        self.recipient_cap_lamports().is_some()
+            || self.per_recipient_max_count > 0
+            || self.recipient_min_interval_seconds > 0
+            || (self.cooldown_scope == COOLDOWN_SCOPE_RECIPIENT && self.cooldown_seconds > 0)
+            || self.per_recipient_lifetime_cap_lamports > 0
+    }
+
+    /// Effective per-recipient daily cap, if any: `per_recipient_cap_bps` of
+    /// the daily budget when set, otherwise the fixed lamport cap (0 = none).
+    pub fn recipient_cap_lamports(&self) -> Option<u64> {
+        if self.per_recipient_cap_bps > 0 {
+            Some(
+                (self.daily_budget_lamports as u128 * self.per_recipient_cap_bps as u128 / 10_000)
+                    as u64,
+            )
+        } else if self.per_recipient_daily_cap_lamports > 0 {
+            Some(self.per_recipient_daily_cap_lamports)
+        } else {
+            None
+        }
+    }
+
+    /// Whether `caller` may pay a recipient under `agent_requires_registered_recipient`:
+    /// the authority always may; an agent needs an authority-registered `RecipientAllow`.
+    pub fn recipient_registered_for(
+        &self,
+        caller: &Pubkey,
+        recipient_allow: Option<&RecipientAllow>,
+    ) -> bool {
+        *caller == self.authority
+            || !self.agent_requires_registered_recipient
+            || recipient_allow.is_some_and(|allow| allow.registered_by == self.authority)
+    }
+
+    /// Whether `recipient` passes the allowlist: always when it is off;
+    /// otherwise via its `AllowedRecipient` entry, falling back to the legacy
+    /// single `allowed_recipient` when no entry is passed.
+    pub fn recipient_allowlisted(
+        &self,
+        recipient: &Pubkey,
+        allowed_recipient: Option<&AllowedRecipient>,
+    ) -> bool {
+        !self.allowlist_enabled
+            || allowed_recipient.is_some()
+            || self.allowed_recipient == Some(*recipient)
+    }
+
+    /// sha256 of the policy's borsh body with runtime state (spend counters,
+    /// windows, sequence numbers, event hash) zeroed, so it changes only when
+    /// the configuration does. Recorded per version in `VersionHistory`.
+    pub fn config_digest(&self) -> Result<[u8; 32]> {
+        let mut config = self.clone();
+        config.clear_runtime_state();
+
+        let mut data = Vec::new();
+        config.serialize(&mut data)?;
+        Ok(hashv(&[&data]).to_bytes())
+    }
+
+    /// Zero the runtime state: spend counters, windows, sequence numbers and
+    /// the event hash. What is left is the configuration.
+    pub fn clear_runtime_state(&mut self) {
+        self.spent_today_lamports = 0;
+        self.day_index = 0;
+        self.last_spend_ts = 0;
+        self.next_sequence = 0;
+        self.last_spend_slot = 0;
+        self.has_spent_once = false;
+        self.spent_this_month_lamports = 0;
+        self.month_index = 0;
+        self.last_event_hash = [0; 32];
+        self.agent_spent_this_week_lamports = 0;
+        self.agent_week_index = 0;
+        self.spent_this_week_lamports = 0;
+        self.week_index = 0;
+        self.denial_sequence = 0;
+        self.allowed_count_today = 0;
+        self.denied_count_today = 0;
+        self.distinct_recipients_today = 0;
+        self.next_pending_id = 0;
+        self.spent_today_usd_cents = 0;
+        self.audit_closed_through = 0;
+        self.audit_closed_bitmap = 0;
+        self.next_batch_id = 0;
+        self.consecutive_denials = 0;
+        self.last_denial_ts = 0;
+        self.rolling_spends = [RollingSpend::default(); ROLLING_SPEND_SLOTS];
+        self.rolling_head = 0;
+        self.ops_funding_spent_today = 0;
+        self.ops_day_index = 0;
+        self.timelocked_lamports = 0;
+        self.pending_lamports = 0;
+    }
+
+    /// Whether an otherwise-allowed spend of `amount` must wait for the
+    /// authority's `approve_spend`: agent spends above
+    /// `approval_threshold_lamports` (0 = off).
+    pub fn needs_approval(&self, is_authority: bool, amount: u64) -> bool {
+        !is_authority
+            && self.approval_threshold_lamports > 0
+            && amount > self.approval_threshold_lamports
+    }
+
+    /// Note that the AuditEvent at `sequence` was reclaimed on purpose.
+    ///
+    /// `audit_closed_through` is the high-water mark: every sequence below it
+    /// was closed. Bit `i` of `audit_closed_bitmap` marks sequence
+    /// `audit_closed_through + 1 + i` as closed ahead of the mark; the mark
+    /// advances over them once the gap fills. Closing oldest first keeps both
+    /// exact; a close more than 64 ahead of the mark is not tracked.
+    pub fn mark_audit_closed(&mut self, sequence: u64) {
+        if sequence == self.audit_closed_through {
+            self.audit_closed_through += 1;
+            while self.audit_closed_bitmap & 1 == 1 {
+                self.audit_closed_bitmap >>= 1;
+                self.audit_closed_through += 1;
+            }
+            self.audit_closed_bitmap >>= 1;
+        } else if let Some(offset) = sequence
+            .checked_sub(self.audit_closed_through + 1)
+            .filter(|offset| *offset < 64)
+        {
+            self.audit_closed_bitmap |= 1 << offset;
+        }
+    }
+
+    /// Whether `max_spends_per_day` is set and today's allowed spends reached it.
+    pub fn exceeds_spend_count(&self) -> bool {
+        self.max_spends_per_day > 0 && self.allowed_count_today >= self.max_spends_per_day
+    }
+
+    /// Whether `recipient` is the vault, this policy or the default (all-zero)
+    /// key: a self-transfer that would still count against budget, or a burn.
+    pub fn is_invalid_recipient(&self, policy: &Pubkey, recipient: &Pubkey) -> bool {
+        *recipient == self.vault || recipient == policy || *recipient == Pubkey::default()
+    }
+
+    /// Whether `block_program_recipients` is on and `recipient` is a program
+    /// (executable) or loader-owned account such as program data.
+    pub fn blocks_program_recipient(&self, recipient: &AccountInfo) -> bool {
+        self.block_program_recipients
+            && (recipient.executable || BPF_LOADERS.contains(recipient.owner))
+    }
+
+    /// Boolean flags packed as `FLAG_*` bits.
+    pub fn flags(&self) -> u32 {
+        [
+            (self.paused, FLAG_PAUSED),
+            (self.allowlist_enabled, FLAG_ALLOWLIST_ENABLED),
+            (
+                self.require_first_spend_approval,
+                FLAG_REQUIRE_FIRST_SPEND_APPROVAL,
+            ),
+            (self.has_spent_once, FLAG_HAS_SPENT_ONCE),
+            (self.one_spend_per_slot, FLAG_ONE_SPEND_PER_SLOT),
+            (self.enforce_fixed_amounts, FLAG_ENFORCE_FIXED_AMOUNTS),
+            (
+                self.agent_requires_registered_recipient,
+                FLAG_AGENT_REQUIRES_REGISTERED_RECIPIENT,
+            ),
+            (
+                self.require_recipient_signature,
+                FLAG_REQUIRE_RECIPIENT_SIGNATURE,
+            ),
+            (self.sequence_on_allowed_only, FLAG_SEQUENCE_ON_ALLOWED_ONLY),
+            (self.linear_release, FLAG_LINEAR_RELEASE),
+            (self.require_owner_exists, FLAG_REQUIRE_OWNER_EXISTS),
+            (self.auto_close_empty_tracker, FLAG_AUTO_CLOSE_EMPTY_TRACKER),
+            (
+                self.recipient_throttle_attempts,
+                FLAG_RECIPIENT_THROTTLE_ATTEMPTS,
+            ),
+            (self.locked, FLAG_LOCKED),
+            (self.block_program_recipients, FLAG_BLOCK_PROGRAM_RECIPIENTS),
+            (self.recipient_cap_exclusive, FLAG_RECIPIENT_CAP_EXCLUSIVE),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |bits, (_, bit)| bits | bit)
+    }
+
+    /// Whether paying `lamports` would dip into the lamports held for
+    /// timelocked claims or leave the vault below `min_reserve_lamports`
+    /// (0 = no floor). Applies to every caller, the authority included.
+    pub fn breaches_min_reserve(&self, vault: &AccountInfo, lamports: u64) -> bool {
+        let available = vault.lamports().saturating_sub(self.timelocked_lamports);
+        self.breaches_reserve(available, lamports)
+    }
+
+    /// Whether paying `lamports` out of `available` (the vault balance not
+    /// held for timelocked claims) overdraws it or leaves less than
+    /// `min_reserve_lamports`.
+    pub fn breaches_reserve(&self, available: u64, lamports: u64) -> bool {
+        lamports > available
+            || (self.min_reserve_lamports > 0 && available - lamports < self.min_reserve_lamports)
+    }
+
+    /// Whether an agent paying out `lamports` would dip into the
+    /// authority-only reserve, i.e. exceed the vault's balance above rent
+    /// minus the reserve.
+    pub fn breaches_agent_reserve(
+        &self,
+        caller: &Pubkey,
+        vault: &AccountInfo,
+        lamports: u64,
+    ) -> Result<bool> {
+        if *caller == self.authority || self.reserved_for_authority_lamports == 0 {
+            return Ok(false);
+        }
+        let rent = Rent::get()?.minimum_balance(vault.data_len());
+        let spendable = vault
+            .lamports()
+            .saturating_sub(rent)
+            .saturating_sub(self.timelocked_lamports)
+            .saturating_sub(self.reserved_for_authority_lamports);
+        Ok(lamports > spendable)
+    }
+
+    /// Whether `require_owner_exists` is on and the vault owner's account holds
+    /// no lamports. Errors if the owner account was not passed.
+    pub fn owner_gone(&self, owner: Option<&AccountInfo>) -> Result<bool> {
+        if !self.require_owner_exists {
+            return Ok(false);
+        }
+        let owner = owner.ok_or(VaultError::OwnerAccountRequired)?;
+        Ok(owner.lamports() == 0)
+    }
+
+    /// Lamports for `amount` policy units: `amount * 10^budget_scale`.
+    pub fn scaled(&self, amount: u64) -> Result<u64> {
+        units_to_lamports(amount, self.budget_scale)
+    }
+
+    /// Whether the authority must still co-sign an agent's spend.
+    pub fn needs_first_spend_approval(&self) -> bool {
+        self.require_first_spend_approval && !self.has_spent_once
+    }
+
+    /// Start fresh daily / weekly / monthly windows if they rolled over since the last spend.
+    pub fn roll_windows(&mut self, clock: &Clock) {
+        let current_day = self.current_day(clock);
+        if current_day != self.day_index {
+            self.reset_day(current_day);
+        }
+        let local = self.local_ts(clock.unix_timestamp);
+        let current_month = month_index(local);
+        if current_month != self.month_index {
+            self.spent_this_month_lamports = 0;
+            self.month_index = current_month;
+        }
+        let current_week = local.div_euclid(SECONDS_PER_WEEK);
+        if current_week != self.agent_week_index {
+            self.agent_spent_this_week_lamports = 0;
+            self.agent_week_index = current_week;
+        }
+        if current_week != self.week_index {
+            self.spent_this_week_lamports = 0;
+            self.week_index = current_week;
+        }
+    }
+
+    /// Start daily window `current_day` with empty counters.
+    pub fn reset_day(&mut self, current_day: i64) {
+        self.spent_today_lamports = 0;
+        self.spent_today_usd_cents = 0;
+        self.day_index = current_day;
+        self.allowed_count_today = 0;
+        self.denied_count_today = 0;
+        self.distinct_recipients_today = 0;
+    }
+
+    /// Lamports left in this calendar month (`u64::MAX` without a monthly budget).
+    pub fn monthly_remaining(&self) -> u64 {
+        if self.monthly_budget_lamports == 0 {
+            u64::MAX
+        } else {
+            self.monthly_budget_lamports
+                .saturating_sub(self.spent_this_month_lamports)
+        }
+    }
+
+    /// Whether `amount` would exceed the monthly budget (0 = no monthly budget).
+    pub fn exceeds_monthly_budget(&self, amount: u64) -> bool {
+        amount > self.monthly_remaining()
+    }
+
+    /// Whether `amount` would exceed the weekly budget (0 = no weekly budget).
+    pub fn exceeds_weekly_budget(&self, amount: u64) -> bool {
+        self.weekly_budget_lamports > 0
+            && exceeds_limit(
+                self.spent_this_week_lamports,
+                amount,
+                self.weekly_budget_lamports,
+            )
+    }
+
+    /// Whether an agent spend of `amount` would exceed the agents' weekly
+    /// budget. `is_agent` covers the legacy `agent` and `add_agent` agents
+    /// alike. Always `false` for other callers or without a weekly budget.
+    pub fn exceeds_agent_weekly_budget(&self, is_agent: bool, amount: u64) -> bool {
+        self.agent_weekly_budget_lamports > 0
+            && is_agent
+            && exceeds_limit(
+                self.agent_spent_this_week_lamports,
+                amount,
+                self.agent_weekly_budget_lamports,
+            )
+    }
+
+    /// `now` on the policy's local clock: shifted by `day_offset_seconds`,
+    /// which the day, week and month boundaries follow.
+    pub fn local_ts(&self, now: i64) -> i64 {
+        now + self.day_offset_seconds as i64
+    }
+
+    /// Index of the current daily window in the policy's time unit.
+    pub fn current_day(&self, clock: &Clock) -> i64 {
+        if self.time_unit == TIME_UNIT_SLOTS {
+            clock.slot as i64 / SLOTS_PER_DAY
+        } else {
+            (self.local_ts(clock.unix_timestamp) - self.epoch_anchor_ts).div_euclid(SECONDS_PER_DAY)
+        }
+    }
+
+    /// Whether an allowed spend already landed in this slot while
+    /// `one_spend_per_slot` is on.
+    pub fn slot_already_spent(&self, clock: &Clock) -> bool {
+        self.one_spend_per_slot && self.last_spend_slot > 0 && self.last_spend_slot == clock.slot
+    }
+
+    /// Spend in the current daily window, treating a stale window as empty
+    /// (same rollover rule as the spend path, applied read-only). In rolling
+    /// mode, the spend of the last 24 hours instead.
+    pub fn spent_today(&self, clock: &Clock) -> u64 {
+        if self.budget_mode == BUDGET_MODE_ROLLING {
+            self.rolling_spent(clock.unix_timestamp)
+        } else if self.day_index == self.current_day(clock) {
+            self.spent_today_lamports
+        } else {
+            0
+        }
+    }
+
+    /// Sum of `rolling_spends` within the 24 hours before `now`.
+    pub fn rolling_spent(&self, now: i64) -> u64 {
+        self.rolling_spends
+            .iter()
+            .filter(|entry| entry.amount > 0 && now - entry.ts < SECONDS_PER_DAY)
+            .fold(0u64, |sum, entry| sum.saturating_add(entry.amount))
+    }
+
+    /// Record an allowed spend in the rolling ring buffer, overwriting the
+    /// oldest entry. An overwritten entry still inside the window is folded
+    /// into the next-oldest one, so the rolling sum can over- but never
+    /// under-count.
+    pub fn record_rolling_spend(&mut self, now: i64, amount: u64) {
+        let head = self.rolling_head as usize % ROLLING_SPEND_SLOTS;
+        let evicted = self.rolling_spends[head];
+        if evicted.amount > 0 && now - evicted.ts < SECONDS_PER_DAY {
+            let next = &mut self.rolling_spends[(head + 1) % ROLLING_SPEND_SLOTS];
+            next.amount = next.amount.saturating_add(evicted.amount);
+        }
+        self.rolling_spends[head] = RollingSpend { ts: now, amount };
+        self.rolling_head = ((head + 1) % ROLLING_SPEND_SLOTS) as u8;
+    }
+
+    /// Credit a refund of `amount` against the rolling ring buffer, newest
+    /// in-window entries first (saturating).
+    pub fn refund_rolling_spend(&mut self, now: i64, mut amount: u64) {
+        let head = self.rolling_head as usize % ROLLING_SPEND_SLOTS;
+        for back in 1..=ROLLING_SPEND_SLOTS {
+            if amount == 0 {
+                break;
+            }
+            let entry =
+                &mut self.rolling_spends[(head + ROLLING_SPEND_SLOTS - back) % ROLLING_SPEND_SLOTS];
+            if entry.amount == 0 || now - entry.ts >= SECONDS_PER_DAY {
+                continue;
+            }
+            let credited = entry.amount.min(amount);
+            entry.amount -= credited;
+            amount -= credited;
+        }
+    }
+
+    /// `(elapsed, period)` of the current daily window in the policy's time unit.
+    pub fn day_progress(&self, clock: &Clock) -> (i64, i64) {
+        if self.time_unit == TIME_UNIT_SLOTS {
+            ((clock.slot as i64).rem_euclid(SLOTS_PER_DAY), SLOTS_PER_DAY)
+        } else {
+            (
+                (self.local_ts(clock.unix_timestamp) - self.epoch_anchor_ts)
+                    .rem_euclid(SECONDS_PER_DAY),
+                SECONDS_PER_DAY,
+            )
+        }
+    }
+
+    /// Whether, with `linear_release` on, spending `amount` would take today's
+    /// total past the share of `budget` released so far.
+    pub fn exceeds_release_schedule(&self, clock: &Clock, budget: u64, amount: u64) -> bool {
+        if !self.linear_release {
+            return false;
+        }
+        let (elapsed, period) = self.day_progress(clock);
+        exceeds_limit(
+            self.spent_today(clock),
+            amount,
+            released_budget(budget, elapsed, period),
+        )
+    }
+
+    /// Whether `now` falls inside the scheduled maintenance window (both 0 = none).
+    pub fn in_maintenance(&self, clock: &Clock) -> bool {
+        (self.maintenance_start_ts != 0 || self.maintenance_end_ts != 0)
+            && clock.unix_timestamp >= self.maintenance_start_ts
+            && clock.unix_timestamp < self.maintenance_end_ts
+    }
+
+    /// Seconds until the daily spend window `[allowed_start_seconds,
+    /// allowed_end_seconds)` (UTC seconds of day) next opens; 0 while inside
+    /// it or when it is off (both 0). Start after end spans midnight.
+    pub fn seconds_until_window_opens(&self, now: i64) -> i64 {
+        let (start, end) = (
+            self.allowed_start_seconds as i64,
+            self.allowed_end_seconds as i64,
+        );
+        if start == 0 && end == 0 {
+            return 0;
+        }
+        let t = now.rem_euclid(SECONDS_PER_DAY);
+        let inside = if start < end {
+            t >= start && t < end
+        } else {
+            t >= start || t < end
+        };
+        if inside {
+            0
+        } else {
+            (start - t).rem_euclid(SECONDS_PER_DAY)
+        }
+    }
+
+    /// Whether paying `amount` to a recipient already paid `lifetime_spent` in
+    /// total would exceed the per-recipient lifetime cap (0 = none).
+    pub fn exceeds_recipient_lifetime_cap(&self, lifetime_spent: u64, amount: u64) -> bool {
+        self.per_recipient_lifetime_cap_lamports > 0
+            && exceeds_limit(
+                lifetime_spent,
+                amount,
+                self.per_recipient_lifetime_cap_lamports,
+            )
+    }
+
+    /// Whether the per-recipient minimum interval since `recipient_last_spend_ts`
+    /// is still running (0 = no interval).
+    pub fn recipient_in_cooldown(&self, recipient_last_spend_ts: i64, clock: &Clock) -> bool {
+        self.recipient_min_interval_seconds > 0
+            && recipient_last_spend_ts > 0
+            && clock.unix_timestamp - recipient_last_spend_ts
+                < self.recipient_min_interval_seconds as i64
+    }
+
+    /// Whether the cooldown blocks a spend to a recipient last paid at
+    /// `recipient_last_spend_ts`: that recipient's own cooldown in
+    /// `COOLDOWN_SCOPE_RECIPIENT`, otherwise the policy-wide one.
+    pub fn in_scoped_cooldown(&self, recipient_last_spend_ts: i64, clock: &Clock) -> bool {
+        if self.cooldown_scope == COOLDOWN_SCOPE_RECIPIENT {
+            recipient_last_spend_ts > 0
+                && clock.unix_timestamp - recipient_last_spend_ts < self.cooldown_seconds as i64
+        } else {
+            self.in_cooldown(clock)
+        }
+    }
+
+    /// Seconds until a spend could clear the pause, maintenance window, daily
+    /// spend window, cooldown and denial backoff: the longest of those waits, 0 if none
+    /// applies, `i64::MAX` while paused (every spend path, `spend_intent`
+    /// included, denies a paused policy). Slot-mode cooldowns are converted at
+    /// nominal slot length.
+    pub fn seconds_until_spendable(&self, clock: &Clock) -> i64 {
+        if self.paused {
+            return i64::MAX;
+        }
+        let cooldown_wait = self.seconds_until_cooldown_clears(clock);
+        let maintenance_wait = if self.in_maintenance(clock) {
+            self.maintenance_end_ts - clock.unix_timestamp
+        } else {
+            0
+        };
+        cooldown_wait
+            .max(maintenance_wait)
+            .max(self.seconds_until_window_opens(clock.unix_timestamp))
+            .max(self.denial_backoff_remaining(clock))
+            .max(0)
+    }
+
+    /// Seconds until the policy-wide cooldown clears (0 if it already has).
+    /// Slot-mode cooldowns are converted at nominal slot length.
+    pub fn seconds_until_cooldown_clears(&self, clock: &Clock) -> i64 {
+        let wait = if self.time_unit == TIME_UNIT_SLOTS {
+            if self.last_spend_slot > 0 {
+                let slots_left = (self.cooldown_seconds as u64)
+                    .saturating_sub(clock.slot.saturating_sub(self.last_spend_slot));
+                (slots_left * SECONDS_PER_DAY as u64).div_ceil(SLOTS_PER_DAY as u64) as i64
+            } else {
+                0
+            }
+        } else if self.last_spend_ts > 0 {
+            self.last_spend_ts + self.cooldown_seconds as i64 - clock.unix_timestamp
+        } else {
+            0
+        };
+        wait.max(0)
+    }
+
+    /// Seconds left of the denial backoff, `base * 2^min(consecutive_denials, 10)`
+    /// from the last denial; 0 while off or after an allowed spend.
+    pub fn denial_backoff_remaining(&self, clock: &Clock) -> i64 {
+        if self.denial_backoff_base_seconds == 0 || self.consecutive_denials == 0 {
+            return 0;
+        }
+        let backoff = (self.denial_backoff_base_seconds as i64)
+            << self.consecutive_denials.min(DENIAL_BACKOFF_MAX_EXPONENT);
+        (self.last_denial_ts + backoff - clock.unix_timestamp).max(0)
+    }
+
+    /// Length of the silence an allowed spend at `now` ends, when it exceeds
+    /// `max_spend_gap_seconds` (0 = off). `None` before the first spend.
+    pub fn missed_heartbeat(&self, now: i64) -> Option<i64> {
+        let gap = now - self.last_spend_ts;
+        (self.max_spend_gap_seconds > 0
+            && self.last_spend_ts > 0
+            && gap > self.max_spend_gap_seconds as i64)
+            .then_some(gap)
+    }
+
+    /// Whether a denial backoff is still running (see `denial_backoff_remaining`).
+    pub fn in_denial_backoff(&self, clock: &Clock) -> bool {
+        self.denial_backoff_remaining(clock) > 0
+    }
+
+    /// Whether the policy-wide cooldown since the last allowed spend is still running.
+    pub fn in_cooldown(&self, clock: &Clock) -> bool {
+        if self.time_unit == TIME_UNIT_SLOTS {
+            self.last_spend_slot > 0
+                && clock.slot.saturating_sub(self.last_spend_slot) < self.cooldown_seconds as u64
+        } else {
+            self.last_spend_ts > 0
+                && clock.unix_timestamp - self.last_spend_ts < self.cooldown_seconds as i64
+        }
+    }
+}
+
+#[account]
+pub struct AuditEvent {
+    pub policy: Pubkey,      // 32
+    pub sequence: u64,       // 8
+    pub ts: i64,             // 8
+    pub recipient: Pubkey,   // 32
+    pub amount: u64,         // 8
+    pub allowed: bool,       // 1
+    pub reason_code: u16,    // 2
+    pub policy_version: u16, // 2
+
+    // ── appended fields (zero default; grown in place by `migrate_account`) ──
+    /// Copied from `policy.environment_tag`; at byte offset 101 for memcmp filters.
+    pub environment_tag: u8, // 1
+    /// Token mint for `spend_intent_spl`; `None` for SOL spends.
+    pub mint: Option<Pubkey>, // 1 + 32 = 33
+    /// Jurisdiction code from `spend_intent_tagged`; 0 = untagged.
+    pub region_code: u16, // 2
+    /// Signer that submitted the spend (and paid for this account).
+    pub caller: Pubkey, // 32
+    /// Whether the spend path authorized `caller` as an agent (the legacy
+    /// `agent` or an `Agent` entry) rather than as the authority or as the
+    /// recipient claiming its own funds.
+    pub caller_is_agent: bool, // 1
+}
+
+// 8 + 32 + 8 + 8 + 32 + 8 + 1 + 2 + 2 + 1 + 33 + 2 + 32 + 1 = 170
+impl AuditEvent {
+    pub const SIZE: usize = 8 + 32 + 8 + 8 + 32 + 8 + 1 + 2 + 2 + 1 + 33 + 2 + 32 + 1;
+}
+
+#[account]
+pub struct RecipientSpend {
+    pub policy: Pubkey,            // 32
+    pub recipient: Pubkey,         // 32
+    pub spent_today_lamports: u64, // 8
+    pub day_index: i64,            // 8
+    pub bump: u8,                  // 1
+
+    // ── appended fields (zero default; grown in place by `migrate_account`) ──
+    pub spend_count_today: u16,       // 2
+    pub lifetime_spent_lamports: u64, // 8
+    pub last_spend_ts: i64,           // 8
+}
+
+// 8 + 32 + 32 + 8 + 8 + 1 + 2 + 8 + 8 = 107
+impl RecipientSpend {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 8 + 1 + 2 + 8 + 8;
+}
+
+/// Lifetime total carried over from closed `RecipientSpend` trackers.
+/// A recipient's lifetime spend is this plus its live tracker's total.
+#[account]
+pub struct RecipientLifetime {
+    pub policy: Pubkey,               // 32
+    pub recipient: Pubkey,            // 32
+    pub lifetime_spent_lamports: u64, // 8
+    pub bump: u8,                     // 1
+}
+
+// 8 + 32 + 32 + 8 + 1 = 81
+impl RecipientLifetime {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 1;
+}
+
+#[account]
+pub struct FixedAmounts {
+    pub policy: Pubkey,    // 32
+    pub amounts: Vec<u64>, // 4 + 8 * MAX_FIXED_AMOUNTS
+    pub bump: u8,          // 1
+}
+
+// 8 + 32 + (4 + 8 * 16) + 1 = 173
+impl FixedAmounts {
+    pub const SIZE: usize = 8 + 32 + (4 + 8 * MAX_FIXED_AMOUNTS) + 1;
+}
+
+#[account]
+pub struct RecipientAllow {
+    pub policy: Pubkey,        // 32
+    pub recipient: Pubkey,     // 32
+    pub registered_by: Pubkey, // 32
+    pub bump: u8,              // 1
+
+    // ── appended fields (zero default; grown in place by `migrate_account`) ──
+    /// Amounts paid to this recipient must be a multiple of this (0 = any).
+    pub unit_lamports: u64, // 8
+}
+
+// 8 + 32 + 32 + 32 + 1 + 8 = 113
+impl RecipientAllow {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 1 + 8;
+
+    /// Whether `amount` is a whole number of the recipient's `unit_lamports`;
+    /// always true without a registration or unit.
+    pub fn amount_fits_unit(recipient_allow: Option<&RecipientAllow>, amount: u64) -> bool {
+        // `checked_rem` is `None` for a zero unit, i.e. no constraint.
+        match recipient_allow.and_then(|allow| amount.checked_rem(allow.unit_lamports)) {
+            Some(remainder) => remainder == 0,
+            None => true,
+        }
+    }
+}
+
+#[account]
+pub struct AllowedRecipient {
+    pub policy: Pubkey,    // 32
+    pub recipient: Pubkey, // 32
+    pub bump: u8,          // 1
+}
+
+// 8 + 32 + 32 + 1 = 73
+impl AllowedRecipient {
+    pub const SIZE: usize = 8 + 32 + 32 + 1;
+}
+
+/// Denylist entry: `spend_intent_v2` denies this payee ahead of every other
+/// check, the allowlist included.
+#[account]
+pub struct BlockedRecipient {
+    pub policy: Pubkey,    // 32
+    pub recipient: Pubkey, // 32
+    pub bump: u8,          // 1
+}
+
+// 8 + 32 + 32 + 1 = 73
+impl BlockedRecipient {
+    pub const SIZE: usize = 8 + 32 + 32 + 1;
+}
+
+/// Marks an `idempotency_key` as used by `spend_intent_idempotent` and
+/// records what that spend did.
+#[account]
+pub struct SpendReceipt {
+    pub policy: Pubkey,            // 32
+    pub idempotency_key: [u8; 16], // 16
+    pub sequence: u64,             // 8
+    pub allowed: bool,             // 1
+    pub reason_code: u16,          // 2
+    pub out_of_sequence: bool,     // 1
+    pub bump: u8,                  // 1
+}
+
+// 8 + 32 + 16 + 8 + 1 + 2 + 1 + 1 = 69
+impl SpendReceipt {
+    pub const SIZE: usize = 8 + 32 + 16 + 8 + 1 + 2 + 1 + 1;
+}
+
+#[account]
+pub struct Agent {
+    pub policy: Pubkey,             // 32
+    pub agent: Pubkey,              // 32
+    pub daily_budget_lamports: u64, // 8
+    pub spent_today_lamports: u64,  // 8
+    pub day_index: i64,             // 8
+    pub bump: u8,                   // 1
+}
+
+// 8 + 32 + 32 + 8 + 8 + 8 + 1 = 97
+impl Agent {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
+
+    /// Start a fresh daily window if the policy's day rolled over.
+    pub fn roll_day(&mut self, current_day: i64) {
+        if self.day_index != current_day {
+            self.spent_today_lamports = 0;
+            self.day_index = current_day;
+        }
+    }
+
+    /// Whether `amount` would exceed this agent's daily sub-limit (0 = none).
+    pub fn exceeds_daily_budget(&self, amount: u64) -> bool {
+        self.daily_budget_lamports > 0
+            && exceeds_limit(
+                self.spent_today_lamports,
+                amount,
+                self.daily_budget_lamports,
+            )
+    }
+
+    /// Count an allowed spend against today's sub-limit.
+    pub fn record(&mut self, amount: u64) {
+        self.spent_today_lamports = self.spent_today_lamports.saturating_add(amount);
+    }
+}
+
+#[account]
+pub struct Allowance {
+    pub policy: Pubkey,       // 32
+    pub recipient: Pubkey,    // 32
+    pub rate_per_second: u64, // 8
+    pub ceiling: u64,         // 8
+    pub accrued: u64,         // 8
+    pub last_accrual_ts: i64, // 8
+    pub bump: u8,             // 1
+
+    // ── appended fields (zero default; grown in place by `migrate_account`) ──
+    pub frozen: bool, // 1
+    /// `policy.budget_scale` the rate, ceiling and accrued balance are in;
+    /// set by `set_allowance`.
+    pub budget_scale: u8, // 1
+}
+
+// 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 1 + 1 = 107
+impl Allowance {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 1 + 1;
+
+    /// Add what accrued since `last_accrual_ts`, capped at `ceiling`.
+    pub fn accrue(&mut self, now: i64) {
+        self.accrued = self.accrued_at(now);
+        self.last_accrual_ts = now;
+    }
+
+    /// Accrued balance as of `now`, without updating the account.
+    pub fn accrued_at(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.last_accrual_ts).max(0) as u64;
+        self.accrued
+            .saturating_add(elapsed.saturating_mul(self.rate_per_second))
+            .min(self.ceiling)
+    }
+}
+
+/// Lamports committed to `recipient` by `spend_to_timelock`, claimable from
+/// `unlock_ts`. Seeded by the creating spend's audit `sequence`.
 #[account]
-pub struct Policy {
-    pub vault: Pubkey,              // 32
-    pub authority: Pubkey,          // 32
-    pub agent: Option<Pubkey>,      // 1 + 32 = 33
-    pub daily_budget_lamports: u64, // 8
-    pub spent_today_lamports: u64,  // 8
-    pub day_index: i64,             // 8
-    pub cooldown_seconds: u32,      // 4
-    pub last_spend_ts: i64,         // 8
-    pub next_sequence: u64,         // 8
+pub struct TimelockedClaim {
+    pub policy: Pubkey,    // 32
+    pub recipient: Pubkey, // 32
+    pub payer: Pubkey,     // 32
+    pub amount: u64,       // 8
+    pub unlock_ts: i64,    // 8
+    pub sequence: u64,     // 8
+    pub bump: u8,          // 1
+    /// `policy.budget_scale` when committed; `amount` is in its units.
+    pub budget_scale: u8, // 1
+}
+
+// 8 + 32 + 32 + 32 + 8 + 8 + 8 + 1 + 1 = 130
+impl TimelockedClaim {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 1 + 1;
+
+    /// Lamports the claim pays out.
+    pub fn lamports(&self) -> Result<u64> {
+        units_to_lamports(self.amount, self.budget_scale)
+    }
+}
+
+/// Agent spend above `approval_threshold_lamports`, held by `spend_intent` or
+/// `spend_intent_v2` until the authority approves it (before `expires_ts`) or rejects it.
+/// Seeded by `id`, taken from `policy.next_pending_id`.
+#[account]
+pub struct PendingSpend {
+    pub policy: Pubkey,    // 32
+    pub recipient: Pubkey, // 32
+    pub payer: Pubkey,     // 32
+    pub amount: u64,       // 8
+    pub requested_ts: i64, // 8
+    pub expires_ts: i64,   // 8
+    pub id: u64,           // 8
+    /// Sequence of the AuditEvent that recorded the request (closed again when
+    /// `sequence_on_allowed_only` is on).
+    pub sequence: u64, // 8
+    pub bump: u8,          // 1
+    /// `policy.budget_scale` when requested; `amount` is in its units.
+    pub budget_scale: u8, // 1
+}
+
+// 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1 = 146
+impl PendingSpend {
+    pub const SIZE: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1;
+
+    /// Lamports the spend pays out once approved.
+    pub fn lamports(&self) -> Result<u64> {
+        units_to_lamports(self.amount, self.budget_scale)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BatchEntry {
+    pub recipient: Pubkey, // 32
+    pub amount: u64,       // 8
+}
+
+// 32 + 8 = 40
+impl BatchEntry {
+    pub const SIZE: usize = 32 + 8;
+}
+
+/// Audit record of one `spend_batch`, seeded by `batch_id` (taken from
+/// `policy.next_batch_id`). Batches are numbered apart from the AuditEvent
+/// sequence.
+#[account]
+pub struct BatchAuditEvent {
+    pub policy: Pubkey,           // 32
+    pub batch_id: u64,            // 8
+    pub ts: i64,                  // 8
+    pub caller: Pubkey,           // 32
+    pub total: u64,               // 8
+    pub policy_version: u16,      // 2
+    pub bump: u8,                 // 1
+    pub entries: Vec<BatchEntry>, // 4 + 40 * n
+}
+
+// 8 + 32 + 8 + 8 + 32 + 8 + 2 + 1 + (4 + 40 * n)
+impl BatchAuditEvent {
+    pub fn space(entries: usize) -> usize {
+        8 + 32 + 8 + 8 + 32 + 8 + 2 + 1 + 4 + BatchEntry::SIZE * entries
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct VersionEntry {
+    pub policy_version: u16, // 2
+    pub digest: [u8; 32],    // 32
+    pub ts: i64,             // 8
+}
+
+// 2 + 32 + 8 = 42
+impl VersionEntry {
+    pub const SIZE: usize = 2 + 32 + 8;
+}
+
+/// Append-only `(policy_version, config_digest)` log; grows by one entry per
+/// `record_policy_version`.
+#[account]
+pub struct VersionHistory {
+    pub policy: Pubkey,             // 32
+    pub bump: u8,                   // 1
+    pub entries: Vec<VersionEntry>, // 4 + 42 * n
+}
+
+// 8 + 32 + 1 + (4 + 42 * n)
+impl VersionHistory {
+    pub fn space(entries: usize) -> usize {
+        8 + 32 + 1 + 4 + VersionEntry::SIZE * entries
+    }
+}
+
+#[account]
+pub struct TokenBudget {
+    pub policy: Pubkey,    // 32
+    pub mint: Pubkey,      // 32
+    pub daily_budget: u64, // 8
+    pub spent_today: u64,  // 8
+    pub day_index: i64,    // 8
+    pub bump: u8,          // 1
+}
+
+// 8 + 32 + 32 + 8 + 8 + 8 + 1 = 97
+impl TokenBudget {
+    pub const SIZE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+pub struct RegionBudget {
+    pub policy: Pubkey,            // 32
+    pub region_code: u16,          // 2
+    pub daily_cap_lamports: u64,   // 8
+    pub spent_today_lamports: u64, // 8
+    pub day_index: i64,            // 8
+    pub bump: u8,                  // 1
+}
+
+// 8 + 32 + 2 + 8 + 8 + 8 + 1 = 67
+impl RegionBudget {
+    pub const SIZE: usize = 8 + 32 + 2 + 8 + 8 + 8 + 1;
+
+    /// Whether `amount` would exceed the region's daily cap (0 = no cap),
+    /// rolling the window to `current_day` first.
+    pub fn exceeds_daily_cap(&mut self, current_day: i64, amount: u64) -> bool {
+        if self.day_index != current_day {
+            self.spent_today_lamports = 0;
+            self.day_index = current_day;
+        }
+        self.daily_cap_lamports > 0
+            && exceeds_limit(self.spent_today_lamports, amount, self.daily_cap_lamports)
+    }
+}
+
+// ──────────────── Instruction Contexts ────────────────
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        has_one = owner,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: The vault's Policy PDA, address-checked; read for its reserve
+    /// and commitments unless it was never created or is closed.
+    #[account(seeds = [b"policy", vault.key().as_ref()], bump)]
+    pub policy: UncheckedAccount<'info>,
+    pub owner: Signer<'info>,
+    /// CHECK: Any account the owner names; only credited lamports.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = Vault::SIZE,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePolicy<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = Policy::SIZE,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        has_one = owner,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClonePolicy<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Policy::SIZE,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        has_one = owner,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        has_one = authority,
+        seeds = [b"policy", source_policy.vault.as_ref()],
+        bump = source_policy.bump,
+    )]
+    pub source_policy: Account<'info, Policy>,
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePolicyWithRecipient<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = Policy::SIZE,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        init,
+        payer = owner,
+        space = RecipientAllow::SIZE,
+        seeds = [b"recipient_allow", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub recipient_allow: Account<'info, RecipientAllow>,
+    #[account(
+        has_one = owner,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Only used as the allowed payee and for PDA derivation.
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetPolicy<'info> {
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RotateAgentSecure<'info> {
+    // No lock constraint: a compromised agent must be revocable on a locked
+    // policy too. The handler allows only `new_agent = None` there.
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: The outgoing agent's `add_agent` entry address; required whenever
+    /// `policy.agent` is set. Checked against the PDA in the handler and closed
+    /// if an entry exists there.
+    #[account(mut)]
+    pub old_agent_entry: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Freeze<'info> {
+    #[account(
+        mut,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    pub guardian: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Unfreeze<'info> {
+    #[account(
+        mut,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResetSpendCounters<'info> {
+    #[account(
+        mut,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    /// Optional: a recipient tracker to reset as well.
+    #[account(
+        mut,
+        has_one = policy,
+        seeds = [
+            b"recipient",
+            policy.key().as_ref(),
+            recipient_spend.recipient.as_ref(),
+        ],
+        bump = recipient_spend.bump,
+    )]
+    pub recipient_spend: Option<Account<'info, RecipientSpend>>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthorityTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFixedAmounts<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = FixedAmounts::SIZE,
+        seeds = [b"fixed_amounts", policy.key().as_ref()],
+        bump,
+    )]
+    pub fixed_amounts: Account<'info, FixedAmounts>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowance<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = Allowance::SIZE,
+        seeds = [b"allowance", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub allowance: Account<'info, Allowance>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Only used for PDA derivation; becomes the allowance's recipient.
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterRecipient<'info> {
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = RecipientAllow::SIZE,
+        seeds = [b"recipient_allow", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub recipient_allow: Account<'info, RecipientAllow>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Only used for PDA derivation; the payee being registered.
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Only required when the caller is an agent added with `add_agent`.
+    #[account(
+        seeds = [b"agent", policy.key().as_ref(), caller.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Option<Account<'info, Agent>>,
+}
+
+#[derive(Accounts)]
+pub struct AddAllowedRecipient<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = AllowedRecipient::SIZE,
+        seeds = [b"allowed", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub allowed_recipient: Account<'info, AllowedRecipient>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Only used for PDA derivation; the payee being allowed.
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAllowedRecipient<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = policy,
+        seeds = [b"allowed", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = allowed_recipient.bump,
+    )]
+    pub allowed_recipient: Account<'info, AllowedRecipient>,
+    #[account(constraint = !policy.locked @ VaultError::PolicyLocked)]
+    pub policy: Account<'info, Policy>,
+    /// CHECK: Only used for PDA derivation.
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BlockRecipient<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = BlockedRecipient::SIZE,
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: Account<'info, BlockedRecipient>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Only used for PDA derivation; the payee being blocked.
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnblockRecipient<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = policy,
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = blocked_recipient.bump,
+    )]
+    pub blocked_recipient: Account<'info, BlockedRecipient>,
+    #[account(constraint = !policy.locked @ VaultError::PolicyLocked)]
+    pub policy: Account<'info, Policy>,
+    /// CHECK: Only used for PDA derivation.
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddAgent<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Agent::SIZE,
+        seeds = [b"agent", policy.key().as_ref(), agent.key().as_ref()],
+        bump,
+    )]
+    pub agent_entry: Account<'info, Agent>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Only used for PDA derivation; the key being authorized.
+    pub agent: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAgent<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = policy,
+        seeds = [b"agent", policy.key().as_ref(), agent.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Account<'info, Agent>,
+    // No lock constraint: revoking an agent never widens the policy.
+    pub policy: Account<'info, Policy>,
+    /// CHECK: Only used for PDA derivation.
+    pub agent: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(region_code: u16)]
+pub struct SetRegionBudget<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RegionBudget::SIZE,
+        seeds = [b"region", policy.key().as_ref(), region_code.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub region_budget: Account<'info, RegionBudget>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetTokenBudget<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = TokenBudget::SIZE,
+        seeds = [b"tokbudget", policy.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub token_budget: Account<'info, TokenBudget>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowanceFrozen<'info> {
+    #[account(
+        mut,
+        has_one = policy,
+        seeds = [b"allowance", policy.key().as_ref(), allowance.recipient.as_ref()],
+        bump = allowance.bump,
+    )]
+    pub allowance: Account<'info, Allowance>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRecipientUnit<'info> {
+    #[account(
+        mut,
+        has_one = policy,
+        seeds = [b"recipient_allow", policy.key().as_ref(), recipient_allow.recipient.as_ref()],
+        bump = recipient_allow.bump,
+    )]
+    pub recipient_allow: Account<'info, RecipientAllow>,
+    #[account(
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SpendIntent<'info> {
+    #[account(
+        init,
+        payer = caller,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Recipient of the SOL transfer. Validated by system_program CPI.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Must match `policy.hook_program`; only required when a hook is configured.
+    pub hook_program: Option<UncheckedAccount<'info>>,
+    /// Authority co-signature; only required for an agent's first spend when
+    /// `require_first_spend_approval` is set.
+    pub approver: Option<Signer<'info>>,
+    /// Only required when `policy.enforce_fixed_amounts` is set.
+    #[account(
+        seeds = [b"fixed_amounts", policy.key().as_ref()],
+        bump = fixed_amounts.bump,
+    )]
+    pub fixed_amounts: Option<Account<'info, FixedAmounts>>,
+    /// Only required for agent spends when `agent_requires_registered_recipient` is set.
+    #[account(
+        seeds = [b"recipient_allow", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_allow.bump,
+    )]
+    pub recipient_allow: Option<Account<'info, RecipientAllow>>,
+    /// CHECK: The vault owner, checked for existence only; only required when
+    /// `policy.require_owner_exists` is set.
+    #[account(address = vault.owner)]
+    pub owner: Option<UncheckedAccount<'info>>,
+    /// Only required when the caller is an agent added with `add_agent`.
+    #[account(
+        mut,
+        seeds = [b"agent", policy.key().as_ref(), caller.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Option<Account<'info, Agent>>,
+    /// CHECK: Must match `policy.notify_program`; only needed for notifications
+    /// (see `notify_spend`).
+    pub notify_program: Option<UncheckedAccount<'info>>,
+    /// Only required for an agent spend above `approval_threshold_lamports`.
+    #[account(
+        init,
+        payer = caller,
+        space = PendingSpend::SIZE,
+        seeds = [
+            b"pending",
+            policy.key().as_ref(),
+            policy.next_pending_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub pending_spend: Option<Account<'info, PendingSpend>>,
+}
+
+#[derive(Accounts)]
+pub struct DepositAndSpend<'info> {
+    pub spend: SpendIntent<'info>,
+    #[account(mut)]
+    pub funder: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundCaller<'info> {
+    #[account(
+        init,
+        payer = caller,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    /// Only required when the caller is an agent added with `add_agent`.
+    #[account(
+        seeds = [b"agent", policy.key().as_ref(), caller.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Option<Account<'info, Agent>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, region_code: u16)]
+pub struct SpendIntentTagged<'info> {
+    pub spend: SpendIntent<'info>,
+    /// CHECK: The region's `RegionBudget` PDA, address-checked; uninitialized
+    /// when the region has no cap.
+    #[account(
+        mut,
+        seeds = [b"region", spend.policy.key().as_ref(), region_code.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub region_budget: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, idempotency_key: [u8; 16])]
+pub struct SpendIntentIdempotent<'info> {
+    pub spend: SpendIntent<'info>,
+    #[account(
+        init,
+        payer = spend.caller,
+        space = SpendReceipt::SIZE,
+        seeds = [b"spend_receipt", spend.policy.key().as_ref(), idempotency_key.as_ref()],
+        bump,
+    )]
+    pub spend_receipt: Account<'info, SpendReceipt>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SpendIntentV2<'info> {
+    #[account(
+        init,
+        payer = caller,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+
+    /// Only required while a per-recipient limit is on (see `tracks_recipients`).
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = RecipientSpend::SIZE,
+        seeds = [
+            b"recipient",
+            policy.key().as_ref(),
+            recipient.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub recipient_spend: Option<Account<'info, RecipientSpend>>,
+
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Recipient of the SOL transfer. Validated by system_program CPI.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Must match `policy.hook_program`; only required when a hook is configured.
+    pub hook_program: Option<UncheckedAccount<'info>>,
+    /// Authority co-signature; only required for an agent's first spend when
+    /// `require_first_spend_approval` is set.
+    pub approver: Option<Signer<'info>>,
+    /// Only required when `policy.enforce_fixed_amounts` is set.
+    #[account(
+        seeds = [b"fixed_amounts", policy.key().as_ref()],
+        bump = fixed_amounts.bump,
+    )]
+    pub fixed_amounts: Option<Account<'info, FixedAmounts>>,
+    /// Only required for agent spends when `agent_requires_registered_recipient` is set.
+    #[account(
+        seeds = [b"recipient_allow", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_allow.bump,
+    )]
+    pub recipient_allow: Option<Account<'info, RecipientAllow>>,
+    /// Only required while `allowlist_enabled` is on and the payee is not the
+    /// legacy single `allowed_recipient`.
+    #[account(
+        seeds = [b"allowed", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = allowed_recipient.bump,
+    )]
+    pub allowed_recipient: Option<Account<'info, AllowedRecipient>>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    /// CHECK: The vault owner, checked for existence only; only required when
+    /// `policy.require_owner_exists` is set.
+    #[account(address = vault.owner)]
+    pub owner: Option<UncheckedAccount<'info>>,
+    /// Only required when the caller is an agent added with `add_agent`.
+    #[account(
+        mut,
+        seeds = [b"agent", policy.key().as_ref(), caller.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Option<Account<'info, Agent>>,
+    /// CHECK: Must match `policy.notify_program`; only needed for notifications
+    /// (see `notify_spend`).
+    pub notify_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match `policy.registry_program`; only needed when a
+    /// registry is set (see `registry_allows`).
+    pub registry_program: Option<UncheckedAccount<'info>>,
+    /// CHECK: The payee's `RecipientLifetime` PDA, address-checked;
+    /// uninitialized until a tracker is closed. Required while
+    /// `per_recipient_lifetime_cap_lamports` is set.
+    #[account(
+        seeds = [b"recipient_lifetime", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub recipient_lifetime: Option<UncheckedAccount<'info>>,
+    /// Only required for an agent spend above `approval_threshold_lamports`.
+    #[account(
+        init,
+        payer = caller,
+        space = PendingSpend::SIZE,
+        seeds = [
+            b"pending",
+            policy.key().as_ref(),
+            policy.next_pending_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub pending_spend: Option<Account<'info, PendingSpend>>,
+}
+
+#[derive(Accounts)]
+pub struct SpendIntentMetered<'info> {
+    #[account(
+        init,
+        payer = caller,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Recipient of the SOL transfer. Validated by system_program CPI.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    /// CHECK: External counter account; must match `policy.meter_account`.
+    pub meter: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Must match `policy.hook_program`; only required when a hook is configured.
+    pub hook_program: Option<UncheckedAccount<'info>>,
+    /// Authority co-signature; only required for an agent's first spend when
+    /// `require_first_spend_approval` is set.
+    pub approver: Option<Signer<'info>>,
+    /// Only required when `policy.enforce_fixed_amounts` is set.
+    #[account(
+        seeds = [b"fixed_amounts", policy.key().as_ref()],
+        bump = fixed_amounts.bump,
+    )]
+    pub fixed_amounts: Option<Account<'info, FixedAmounts>>,
+    /// Only required for agent spends when `agent_requires_registered_recipient` is set.
+    #[account(
+        seeds = [b"recipient_allow", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_allow.bump,
+    )]
+    pub recipient_allow: Option<Account<'info, RecipientAllow>>,
+    /// Only required while `allowlist_enabled` is on and the payee is not the
+    /// legacy single `allowed_recipient`.
+    #[account(
+        seeds = [b"allowed", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = allowed_recipient.bump,
+    )]
+    pub allowed_recipient: Option<Account<'info, AllowedRecipient>>,
+    /// CHECK: The vault owner, checked for existence only; only required when
+    /// `policy.require_owner_exists` is set.
+    #[account(address = vault.owner)]
+    pub owner: Option<UncheckedAccount<'info>>,
+    /// Only required when the caller is an agent added with `add_agent`.
+    #[account(
+        mut,
+        seeds = [b"agent", policy.key().as_ref(), caller.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Option<Account<'info, Agent>>,
+    /// CHECK: Must match `policy.notify_program`; only needed for notifications
+    /// (see `notify_spend`).
+    pub notify_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueAndClaim<'info> {
+    #[account(
+        init,
+        payer = recipient,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        has_one = policy,
+        has_one = recipient,
+        seeds = [b"allowance", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = allowance.bump,
+    )]
+    pub allowance: Account<'info, Allowance>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Must match `policy.notify_program`; only needed for notifications
+    /// (see `notify_spend`).
+    pub notify_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SpendToTimelock<'info> {
+    #[account(
+        init,
+        payer = caller,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        init,
+        payer = caller,
+        space = TimelockedClaim::SIZE,
+        seeds = [
+            b"timelock",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub timelock: Account<'info, TimelockedClaim>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Payee of the claim; only its key is recorded.
+    pub recipient: UncheckedAccount<'info>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Must match `policy.notify_program`; only needed for notifications
+    /// (see `notify_spend`).
+    pub notify_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTimelock<'info> {
+    #[account(
+        init,
+        payer = recipient,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        has_one = policy,
+        has_one = recipient,
+        has_one = payer,
+        seeds = [
+            b"timelock",
+            policy.key().as_ref(),
+            timelock.sequence.to_le_bytes().as_ref(),
+        ],
+        bump = timelock.bump,
+    )]
+    pub timelock: Account<'info, TimelockedClaim>,
+    #[account(
+        mut,
+        has_one = vault,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    /// CHECK: Receives the claim's rent; must match `timelock.payer`.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelTimelock<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        close = payer,
+        has_one = policy,
+        has_one = payer,
+        seeds = [
+            b"timelock",
+            policy.key().as_ref(),
+            timelock.sequence.to_le_bytes().as_ref(),
+        ],
+        bump = timelock.bump,
+    )]
+    pub timelock: Account<'info, TimelockedClaim>,
+    #[account(
+        mut,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: Receives the claim's rent; must match `timelock.payer`.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveSpend<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        has_one = policy,
+        has_one = recipient,
+        has_one = payer,
+        seeds = [
+            b"pending",
+            policy.key().as_ref(),
+            pending_spend.id.to_le_bytes().as_ref(),
+        ],
+        bump = pending_spend.bump,
+    )]
+    pub pending_spend: Account<'info, PendingSpend>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Payee; must match `pending_spend.recipient`.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    /// CHECK: Receives the pending spend's rent; must match `pending_spend.payer`.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Must match `policy.notify_program`; only needed for notifications
+    /// (see `notify_spend`).
+    pub notify_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RejectSpend<'info> {
+    #[account(
+        mut,
+        close = payer,
+        has_one = policy,
+        has_one = payer,
+        seeds = [
+            b"pending",
+            policy.key().as_ref(),
+            pending_spend.id.to_le_bytes().as_ref(),
+        ],
+        bump = pending_spend.bump,
+    )]
+    pub pending_spend: Account<'info, PendingSpend>,
+    #[account(
+        mut,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    pub authority: Signer<'info>,
+    /// CHECK: Receives the pending spend's rent; must match `pending_spend.payer`.
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(amounts: Vec<u64>)]
+pub struct SpendBatch<'info> {
+    #[account(
+        init,
+        payer = caller,
+        space = BatchAuditEvent::space(amounts.len()),
+        seeds = [
+            b"batch",
+            policy.key().as_ref(),
+            policy.next_batch_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub batch_audit: Account<'info, BatchAuditEvent>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// Only required when the caller is an agent added with `add_agent`.
+    #[account(
+        mut,
+        seeds = [b"agent", policy.key().as_ref(), caller.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Option<Account<'info, Agent>>,
+}
+
+#[derive(Accounts)]
+pub struct SpendIntentSpl<'info> {
+    #[account(
+        init,
+        payer = caller,
+        space = AuditEvent::SIZE,
+        seeds = [
+            b"audit",
+            policy.key().as_ref(),
+            policy.next_sequence.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        has_one = policy,
+        has_one = mint,
+        seeds = [b"tokbudget", policy.key().as_ref(), mint.key().as_ref()],
+        bump = token_budget.bump,
+    )]
+    pub token_budget: Account<'info, TokenBudget>,
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Wallet being paid; its key is used (blocklist, allowlist, audit,
+    /// ATA), and it must sign when `require_recipient_signature` is set.
+    pub recipient: UncheckedAccount<'info>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub caller: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    /// Only required while `allowlist_enabled` is on and the payee is not the
+    /// legacy single `allowed_recipient`.
+    #[account(
+        seeds = [b"allowed", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = allowed_recipient.bump,
+    )]
+    pub allowed_recipient: Option<Account<'info, AllowedRecipient>>,
+    /// CHECK: Must match `policy.hook_program`; only required when a hook is configured.
+    pub hook_program: Option<UncheckedAccount<'info>>,
+    /// Authority co-signature; only required for an agent's first spend when
+    /// `require_first_spend_approval` is set.
+    pub approver: Option<Signer<'info>>,
+    /// Only required for agent spends when `agent_requires_registered_recipient` is set.
+    #[account(
+        seeds = [b"recipient_allow", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_allow.bump,
+    )]
+    pub recipient_allow: Option<Account<'info, RecipientAllow>>,
+    /// CHECK: The vault owner, checked for existence only; only required when
+    /// `policy.require_owner_exists` is set.
+    #[account(address = vault.owner)]
+    pub owner: Option<UncheckedAccount<'info>>,
+    /// CHECK: Must match `policy.registry_program`; only needed when a
+    /// registry is set (see `registry_allows`).
+    pub registry_program: Option<UncheckedAccount<'info>>,
+    /// Only required when the caller is an agent added with `add_agent`.
+    #[account(
+        seeds = [b"agent", policy.key().as_ref(), caller.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Option<Account<'info, Agent>>,
+    /// CHECK: Must match `policy.notify_program`; only needed for notifications
+    /// (see `notify_spend`).
+    pub notify_program: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct RecordRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        mut,
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// The refunder's per-recipient tracker, if it has one.
+    #[account(
+        mut,
+        has_one = policy,
+        seeds = [b"recipient", policy.key().as_ref(), refunder.key().as_ref()],
+        bump = recipient_spend.bump,
+    )]
+    pub recipient_spend: Option<Account<'info, RecipientSpend>>,
+    #[account(mut)]
+    pub refunder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    /// CHECK: Receives an emptied tracker's rent; only used with
+    /// `policy.auto_close_empty_tracker`.
+    #[account(mut, address = policy.authority)]
+    pub authority: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAuditEvent<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = policy,
+    )]
+    pub audit_event: Account<'info, AuditEvent>,
+    #[account(
+        mut,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseAuditEventsBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRecipientSpend<'info> {
+    #[account(
+        mut,
+        close = authority,
+        has_one = policy,
+        seeds = [b"recipient", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_spend.bump,
+    )]
+    pub recipient_spend: Account<'info, RecipientSpend>,
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = RecipientLifetime::SIZE,
+        seeds = [b"recipient_lifetime", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub recipient_lifetime: Account<'info, RecipientLifetime>,
+    pub policy: Account<'info, Policy>,
+    /// CHECK: Only used for PDA derivation.
+    pub recipient: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePolicy<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: The vault's Policy PDA, address-checked; must already be closed.
+    #[account(seeds = [b"policy", vault.key().as_ref()], bump)]
+    pub policy: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecoverMisdirected<'info> {
+    #[account(
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    /// CHECK: Identified by discriminator and checked to belong to `policy`.
+    #[account(mut, owner = crate::ID)]
+    pub target: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReassignPolicyVault<'info> {
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"policy", vault.key().as_ref()],
+        bump = policy.bump,
+        constraint = !policy.locked @ VaultError::PolicyLocked,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(
+        init,
+        payer = authority,
+        space = Policy::SIZE,
+        seeds = [b"policy", new_vault.key().as_ref()],
+        bump,
+    )]
+    pub new_policy: Account<'info, Policy>,
+    #[account(
+        seeds = [b"vault", vault.owner.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        seeds = [b"vault", new_vault.owner.as_ref()],
+        bump = new_vault.bump,
+        constraint = new_vault.owner == authority.key() @ VaultError::Unauthorized,
+    )]
+    pub new_vault: Account<'info, Vault>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Raw account so older, shorter layouts can be loaded; ownership is
+    /// enforced here and the type is identified by its discriminator.
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GetPoliciesSummary {}
+
+#[derive(Accounts)]
+pub struct ProjectEodSpend<'info> {
+    pub policy: Account<'info, Policy>,
+}
+
+#[derive(Accounts)]
+pub struct SecondsUntilSpendable<'info> {
+    pub policy: Account<'info, Policy>,
+}
+
+#[derive(Accounts)]
+pub struct RemainingBudget<'info> {
+    pub policy: Account<'info, Policy>,
+}
+
+#[derive(Accounts)]
+pub struct SpendableBalance<'info> {
+    #[account(has_one = vault)]
+    pub policy: Account<'info, Policy>,
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+pub struct SimulateSpend<'info> {
+    #[account(has_one = vault)]
+    pub policy: Account<'info, Policy>,
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Would-be payee; only read.
+    pub recipient: UncheckedAccount<'info>,
+    /// CHECK: The payee's `BlockedRecipient` PDA, address-checked and always
+    /// required so a block cannot be skipped; uninitialized unless blocked.
+    #[account(
+        seeds = [b"blocked", policy.key().as_ref(), recipient.key().as_ref()],
+        bump,
+    )]
+    pub blocked_recipient: UncheckedAccount<'info>,
+    /// CHECK: Would-be signer of the spend; only its key is used.
+    pub caller: UncheckedAccount<'info>,
+    /// Only needed when `policy.enforce_fixed_amounts` is set.
+    #[account(
+        seeds = [b"fixed_amounts", policy.key().as_ref()],
+        bump = fixed_amounts.bump,
+    )]
+    pub fixed_amounts: Option<Account<'info, FixedAmounts>>,
+    /// Only needed for agent spends when `agent_requires_registered_recipient` is set.
+    #[account(
+        seeds = [b"recipient_allow", policy.key().as_ref(), recipient.key().as_ref()],
+        bump = recipient_allow.bump,
+    )]
+    pub recipient_allow: Option<Account<'info, RecipientAllow>>,
+    /// CHECK: The vault owner, checked for existence only; only needed when
+    /// `policy.require_owner_exists` is set.
+    #[account(address = vault.owner)]
+    pub owner: Option<UncheckedAccount<'info>>,
+    /// Only needed when the caller is an agent added with `add_agent`.
+    #[account(
+        seeds = [b"agent", policy.key().as_ref(), caller.key().as_ref()],
+        bump = agent_entry.bump,
+    )]
+    pub agent_entry: Option<Account<'info, Agent>>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyMerkleProof<'info> {
+    pub policy: Account<'info, Policy>,
+}
+
+#[derive(Accounts)]
+pub struct GetPolicyFlags<'info> {
+    pub policy: Account<'info, Policy>,
+}
+
+#[derive(Accounts)]
+pub struct ExportAuditEvents<'info> {
+    #[account(
+        mut,
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DeriveAddresses<'info> {
+    pub policy: Option<Account<'info, Policy>>,
+}
+
+#[derive(Accounts)]
+pub struct CheckSolvency<'info> {
+    #[account(has_one = vault)]
+    pub policy: Account<'info, Policy>,
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+pub struct RecordPolicyVersion<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = VersionHistory::space(0),
+        seeds = [b"version_history", policy.key().as_ref()],
+        bump,
+    )]
+    pub version_history: Account<'info, VersionHistory>,
+    #[account(
+        seeds = [b"policy", policy.vault.as_ref()],
+        bump = policy.bump,
+    )]
+    pub policy: Account<'info, Policy>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// ──────────────── Calendar ────────────────
+
+/// Days since 1970-01-01 → proleptic Gregorian `(year, month 1..=12, day 1..=31)`.
+///
+/// Howard Hinnant's `civil_from_days`; exact for every month length and leap
+/// year, including negative day counts.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097); // [0, 146096]
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365]
+    let mp = (5 * doy + 2) / 153; // [0, 11], March-based
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Calendar-month key of a unix timestamp (UTC): `year * 12 + (month - 1)`.
+pub fn month_index(unix_ts: i64) -> i64 {
+    let (year, month, _) = civil_from_days(unix_ts.div_euclid(SECONDS_PER_DAY));
+    year * 12 + (month as i64 - 1)
+}
+
+// ──────────────── Price feed ────────────────
+
+/// Byte offsets in a Pyth (v2, push) price account.
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_TIMESTAMP_OFFSET: usize = 96;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+const PYTH_AGG_STATUS_OFFSET: usize = 224;
+const PYTH_STATUS_TRADING: u32 = 1;
+
+/// Aggregate `(price, expo)` of a Pyth price account — USD per SOL is
+/// `price * 10^expo` — or `None` if the data is not a price account, the
+/// feed is not trading, the price is not positive, or it was published more
+/// than `max_age_seconds` before `now`.
+pub fn pyth_price(data: &[u8], now: i64, max_age_seconds: u32) -> Option<(i64, i32)> {
+    let u32_at = |at: usize| Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let i64_at = |at: usize| Some(i64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?));
+
+    if u32_at(0)? != PYTH_MAGIC || u32_at(PYTH_AGG_STATUS_OFFSET)? != PYTH_STATUS_TRADING {
+        return None;
+    }
+    let expo = u32_at(PYTH_EXPO_OFFSET)? as i32;
+    let price = i64_at(PYTH_AGG_PRICE_OFFSET)?;
+    let published = i64_at(PYTH_TIMESTAMP_OFFSET)?;
+    let fresh = now.saturating_sub(published) <= max_age_seconds as i64;
+    (price > 0 && fresh).then_some((price, expo))
+}
+
+/// USD cents worth of `lamports` at `price * 10^expo` USD per SOL, rounded
+/// up so a budget is never under-counted.
+pub fn lamports_to_usd_cents(lamports: u64, price: i64, expo: i32) -> Option<u64> {
+    // cents = lamports * price * 10^expo * 100 / 10^9
+    let numerator = (lamports as u128).checked_mul(price as u128)?;
+    let exponent = expo - 7;
+    let cents = if exponent >= 0 {
+        numerator.checked_mul(10u128.checked_pow(exponent as u32)?)?
+    } else {
+        numerator.div_ceil(10u128.checked_pow(exponent.unsigned_abs())?)
+    };
+    u64::try_from(cents).ok()
+}
+
+// ──────────────── Merkle allowlist ────────────────
+
+/// Leaf of the recipient allowlist tree: `sha256(recipient)`.
+pub fn merkle_leaf(recipient: &Pubkey) -> [u8; 32] {
+    hashv(&[recipient.as_ref()]).to_bytes()
+}
+
+/// Verify `proof` for `recipient` against `root`. Pairs are hashed in sorted
+/// order (`sha256(min || max)`), so proofs carry no left/right flags.
+pub fn merkle_proof_valid(root: &[u8; 32], recipient: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let computed = proof.iter().fold(merkle_leaf(recipient), |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == *root
+}
+
+// ──────────────── Spend hook ────────────────
+
+/// Instruction discriminator a hook must handle: `sha256("global:on_spend")[..8]`,
+/// i.e. an Anchor instruction named `on_spend`.
+pub const SPEND_HOOK_DISCRIMINATOR: [u8; 8] = [79, 40, 42, 14, 43, 242, 211, 78];
+
+/// Borsh payload that follows `SPEND_HOOK_DISCRIMINATOR` in the hook CPI.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SpendHookArgs {
+    pub policy: Pubkey,
+    pub vault: Pubkey,
+    pub recipient: Pubkey,
+    pub caller: Pubkey,
+    pub amount: u64,
+}
+
+/// Offer a spend to the policy's hook program; `true` only on explicit approval.
+///
+/// Interface:
+/// - data: `SPEND_HOOK_DISCRIMINATOR` followed by borsh `SpendHookArgs`
+/// - accounts: `policy`, `vault`, `recipient`, `caller` (all read-only, none
+///   signing), then the spend's `remaining_accounts` with their original flags
+/// - approval: the hook sets return data to exactly `[1]`
+///
+/// Anything else — no/other return data, or the hook account missing or not
+/// matching `policy.hook_program` — is a denial. A hook that fails aborts the
+/// whole transaction, so hooks should reject via return data, not errors.
+fn spend_hook_approves<'info>(
+    hook_program: &Pubkey,
+    hook: Option<&UncheckedAccount<'info>>,
+    accounts: [AccountInfo<'info>; 4],
+    remaining: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<bool> {
+    let Some(hook) = hook.filter(|h| h.key() == *hook_program && h.executable) else {
+        return Ok(false);
+    };
+
+    let args = SpendHookArgs {
+        policy: accounts[0].key(),
+        vault: accounts[1].key(),
+        recipient: accounts[2].key(),
+        caller: accounts[3].key(),
+        amount,
+    };
+    let mut data = SPEND_HOOK_DISCRIMINATOR.to_vec();
+    args.serialize(&mut data)?;
+
+    let mut metas: Vec<AccountMeta> = accounts
+        .iter()
+        .map(|a| AccountMeta::new_readonly(a.key(), false))
+        .collect();
+    metas.extend(remaining.iter().map(|a| AccountMeta {
+        pubkey: a.key(),
+        is_signer: a.is_signer,
+        is_writable: a.is_writable,
+    }));
+
+    let mut infos = accounts.to_vec();
+    infos.extend_from_slice(remaining);
+    infos.push(hook.to_account_info());
+
+    invoke(
+        &Instruction {
+            program_id: *hook_program,
+            accounts: metas,
+            data,
+        },
+        &infos,
+    )?;
+
+    Ok(
+        matches!(get_return_data(), Some((program_id, ret)) if program_id == *hook_program && ret == [1]),
+    )
+}
+
+/// Offer an otherwise-allowed spend to the policy's hook, if one is configured.
+///
+/// `parties` is `[vault, recipient, caller]`.
+fn apply_spend_hook<'info>(
+    decision: (bool, u16),
+    policy: &Account<'info, Policy>,
+    hook: Option<&UncheckedAccount<'info>>,
+    parties: [AccountInfo<'info>; 3],
+    remaining: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<(bool, u16)> {
+    let Some(hook_program) = policy.hook_program else {
+        return Ok(decision);
+    };
+    if !decision.0 {
+        return Ok(decision);
+    }
+    let [vault, recipient, caller] = parties;
+    let approved = spend_hook_approves(
+        &hook_program,
+        hook,
+        [policy.to_account_info(), vault, recipient, caller],
+        remaining,
+        amount,
+    )?;
+    Ok(if approved {
+        (true, REASON_OK)
+    } else {
+        (false, REASON_HOOK_REJECTED)
+    })
+}
+
+// ──────────────── Spend notification ────────────────
+
+/// Instruction discriminator a notify program must handle:
+/// `sha256("global:on_spend_notify")[..8]`, i.e. an Anchor instruction named
+/// `on_spend_notify`.
+pub const SPEND_NOTIFY_DISCRIMINATOR: [u8; 8] = [6, 218, 238, 16, 209, 180, 141, 242];
+
+/// Borsh payload that follows `SPEND_NOTIFY_DISCRIMINATOR` in the notify CPI.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SpendNotifyArgs {
+    pub policy: Pubkey,
+    pub vault: Pubkey,
+    pub recipient: Pubkey,
+    pub caller: Pubkey,
+    pub amount: u64,
+    pub allowed: bool,
+    pub reason_code: u16,
+    pub sequence: u64,
+}
+
+/// Tell the policy's notify program about a settled spend that was denied or
+/// exceeded `notify_threshold_lamports`. Fire-and-forget: return data is
+/// ignored.
+///
+/// Interface:
+/// - data: `SPEND_NOTIFY_DISCRIMINATOR` followed by borsh `SpendNotifyArgs`
+/// - accounts: `policy`, `vault`, `recipient`, `caller` (all read-only, none
+///   signing)
+///
+/// Best-effort as far as the runtime allows: when the notify account is not
+/// passed, does not match `policy.notify_program` or is not executable, the
+/// spend settles without a notification. A notify program that fails still
+/// aborts the whole transaction (a CPI error cannot be caught), so it should
+/// never return an error.
+fn notify_spend<'info>(
+    policy: &Account<'info, Policy>,
+    notify: Option<&UncheckedAccount<'info>>,
+    parties: [AccountInfo<'info>; 3],
+    amount: u64,
+    (allowed, reason_code): (bool, u16),
+    sequence: u64,
+) -> Result<()> {
+    let Some(notify_program) = policy.notify_program else {
+        return Ok(());
+    };
+    if allowed && amount <= policy.notify_threshold_lamports {
+        return Ok(());
+    }
+    let Some(notify) = notify.filter(|n| n.key() == notify_program && n.executable) else {
+        return Ok(());
+    };
+
+    let [vault, recipient, caller] = parties;
+    let args = SpendNotifyArgs {
+        policy: policy.key(),
+        vault: vault.key(),
+        recipient: recipient.key(),
+        caller: caller.key(),
+        amount,
+        allowed,
+        reason_code,
+        sequence,
+    };
+    let mut data = SPEND_NOTIFY_DISCRIMINATOR.to_vec();
+    args.serialize(&mut data)?;
+
+    let infos = [policy.to_account_info(), vault, recipient, caller];
+    let metas = infos
+        .iter()
+        .map(|a| AccountMeta::new_readonly(a.key(), false))
+        .collect();
+    let mut infos = infos.to_vec();
+    infos.push(notify.to_account_info());
+
+    invoke(
+        &Instruction {
+            program_id: notify_program,
+            accounts: metas,
+            data,
+        },
+        &infos,
+    )?;
+    Ok(())
+}
+
+// ──────────────── Recipient registry ────────────────
+
+/// Instruction discriminator a registry program must handle:
+/// `sha256("global:is_allowed")[..8]`, i.e. an Anchor instruction named
+/// `is_allowed`.
+pub const REGISTRY_IS_ALLOWED_DISCRIMINATOR: [u8; 8] = [61, 67, 178, 249, 199, 119, 207, 237];
+
+/// Borsh payload that follows `REGISTRY_IS_ALLOWED_DISCRIMINATOR` in the
+/// registry CPI.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RegistryQueryArgs {
+    pub recipient: Pubkey,
+}
+
+/// Ask the policy's registry program whether `recipient` may be paid; `true`
+/// only on an explicit yes.
+///
+/// Interface:
+/// - data: `REGISTRY_IS_ALLOWED_DISCRIMINATOR` followed by borsh
+///   `RegistryQueryArgs`
+/// - accounts: `policy`, `recipient` (both read-only, none signing)
+/// - allowed: the registry sets return data to exactly `[1]`
+///
+/// Anything else — no/other return data, or the registry account missing, not
+/// matching `policy.registry_program` or not executable — is a denial. A
+/// registry that fails aborts the whole transaction, so it should answer via
+/// return data, not errors.
+fn registry_allows<'info>(
+    registry_program: &Pubkey,
+    registry: Option<&UncheckedAccount<'info>>,
+    accounts: [AccountInfo<'info>; 2],
+) -> Result<bool> {
+    let Some(registry) = registry.filter(|r| r.key() == *registry_program && r.executable) else {
+        return Ok(false);
+    };
+
+    let args = RegistryQueryArgs {
+        recipient: accounts[1].key(),
+    };
+    let mut data = REGISTRY_IS_ALLOWED_DISCRIMINATOR.to_vec();
+    args.serialize(&mut data)?;
+
+    let metas = accounts
+        .iter()
+        .map(|a| AccountMeta::new_readonly(a.key(), false))
+        .collect();
+    let mut infos = accounts.to_vec();
+    infos.push(registry.to_account_info());
+
+    invoke(
+        &Instruction {
+            program_id: *registry_program,
+            accounts: metas,
+            data,
+        },
+        &infos,
+    )?;
+
+    Ok(
+        matches!(get_return_data(), Some((program_id, ret)) if program_id == *registry_program && ret == [1]),
+    )
+}
+
+// ──────────────── Spend caps ────────────────
+
+/// Share of `budget` released after `elapsed` of a `period`-long window:
+/// `budget * elapsed / period`, 0 at the window start and all of it at the end.
+pub fn released_budget(budget: u64, elapsed: i64, period: i64) -> u64 {
+    if period <= 0 {
+        return budget;
+    }
+    let elapsed = elapsed.clamp(0, period) as u128;
+    (budget as u128 * elapsed / period as u128) as u64
+}
 
-    // ── advanced policy fields ──
-    pub paused: bool,                          // 1
-    pub allowlist_enabled: bool,               // 1
-    pub allowed_recipient: Option<Pubkey>,     // 1 + 32 = 33
-    pub per_recipient_daily_cap_lamports: u64, // 8
-    pub policy_version: u16,                   // 2
+/// Lamports for `amount` units of `10^budget_scale` lamports. Commitments
+/// paid out later (`TimelockedClaim`, `PendingSpend`, `Allowance`) keep the
+/// scale they were made under, so `set_budget_scale` cannot change them.
+pub fn units_to_lamports(amount: u64, budget_scale: u8) -> Result<u64> {
+    10u64
+        .checked_pow(budget_scale as u32)
+        .and_then(|unit| amount.checked_mul(unit))
+        .ok_or_else(|| error!(VaultError::ScaledAmountOverflow))
+}
 
-    pub bump: u8, // 1
+/// Whether adding `amount` to `spent` goes over `limit`. A sum that overflows
+/// `u64` is over any limit, so a budget near `u64::MAX` cannot let a spend
+/// through that the counters could then not record.
+pub fn exceeds_limit(spent: u64, amount: u64, limit: u64) -> bool {
+    spent.checked_add(amount).is_none_or(|total| total > limit)
 }
 
-// 8 discriminator + (fields) = 195
-// 32 + 32 + 33 + 8 + 8 + 8 + 4 + 8 + 8 + 1 + 1 + 33 + 8 + 2 + 1 = 187
-// 8 + 187 = 195
-impl Policy {
-    pub const SIZE: usize = 8 + 32 + 32 + 33 + 8 + 8 + 8 + 4 + 8 + 8 + 1 + 1 + 33 + 8 + 2 + 1;
+/// Which of the daily budget and per-recipient cap blocks a spend of `amount`,
+/// given each one's remaining headroom (`None` = no per-recipient cap).
+///
+/// When both bind, the one with less headroom is the binding constraint and is
+/// reported; ties go to the per-recipient cap as the more specific limit.
+pub fn binding_cap_reason(
+    amount: u64,
+    budget_headroom: u64,
+    recipient_headroom: Option<u64>,
+) -> Option<u16> {
+    let budget_binds = amount > budget_headroom;
+    match recipient_headroom {
+        Some(headroom) if amount > headroom && (!budget_binds || headroom <= budget_headroom) => {
+            Some(REASON_RECIPIENT_CAP_EXCEEDED)
+        }
+        _ if budget_binds => Some(REASON_BUDGET_EXCEEDED),
+        _ => None,
+    }
 }
 
-#[account]
-pub struct AuditEvent {
-    pub policy: Pubkey,     // 32
-    pub sequence: u64,      // 8
-    pub ts: i64,            // 8
-    pub recipient: Pubkey,  // 32
-    pub amount: u64,        // 8
-    pub allowed: bool,      // 1
-    pub reason_code: u16,   // 2
-    pub policy_version: u16 // 2
+// ──────────────── Spend settlement ────────────────
+
+/// `Policy::roll_windows`, first emitting `DailySummary` for the day being
+/// closed (when it saw any spend attempt) so its counters are not lost.
+fn roll_policy_windows(policy: &mut Account<Policy>, clock: &Clock) {
+    if policy.current_day(clock) != policy.day_index
+        && policy
+            .allowed_count_today
+            .saturating_add(policy.denied_count_today)
+            > 0
+    {
+        emit!(DailySummary {
+            policy: policy.key(),
+            day_index: policy.day_index,
+            total_spent: policy.spent_today_lamports,
+            allowed_count: policy.allowed_count_today,
+            denied_count: policy.denied_count_today,
+            distinct_recipients: policy.distinct_recipients_today,
+            ts: clock.unix_timestamp,
+        });
+    }
+    policy.roll_windows(clock);
 }
 
-// 8 + 32 + 8 + 8 + 32 + 8 + 1 + 2 + 2 = 101
-impl AuditEvent {
-    pub const SIZE: usize = 8 + 32 + 8 + 8 + 32 + 8 + 1 + 2 + 2;
+/// Authorize `caller` on a spend path: `(is_authority, is_agent)`, or
+/// `Unauthorized` for anyone else. Agents are the legacy `policy.agent` and any
+/// key whose `Agent` entry (added with `add_agent`) is passed as `agent_entry`;
+/// the entry's seeds bind it to this policy and caller. Every path that takes
+/// the entry also applies its daily sub-limit, including for the legacy agent
+/// when it has one.
+fn authorize_caller(
+    policy: &Policy,
+    caller: &Pubkey,
+    agent_entry: Option<&Agent>,
+) -> Result<(bool, bool)> {
+    let is_authority = *caller == policy.authority;
+    let is_agent = policy.agent == Some(*caller) || agent_entry.is_some();
+    require!(is_authority || is_agent, VaultError::Unauthorized);
+    Ok((is_authority, is_agent))
 }
 
-#[account]
-pub struct RecipientSpend {
-    pub policy: Pubkey,            // 32
-    pub recipient: Pubkey,         // 32
-    pub spent_today_lamports: u64, // 8
-    pub day_index: i64,            // 8
-    pub bump: u8,                  // 1
+/// Body of `spend_intent`, shared with `deposit_and_spend`,
+/// `spend_intent_tagged` (which pass a region tag and its cap, if any) and
+/// `spend_intent_idempotent` (which passes its receipt's rent as
+/// `extra_rent`). Returns the recorded decision.
+fn run_spend_intent<'info>(
+    accounts: &mut SpendIntent<'info>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    amount: u64,
+    region_code: u16,
+    mut region_budget: Option<&mut RegionBudget>,
+    extra_rent: u64,
+) -> Result<(bool, u16)> {
+    let policy = &mut accounts.policy;
+    let caller_key = accounts.caller.key();
+
+    // ── Authorization: caller must be authority or agent ──
+    let (is_authority, is_agent) =
+        authorize_caller(policy, &caller_key, accounts.agent_entry.as_deref())?;
+
+    // ── First-spend checkpoint: authority co-signs until one spend goes through ──
+    if policy.needs_first_spend_approval() && !is_authority {
+        let approver = accounts
+            .approver
+            .as_ref()
+            .ok_or(VaultError::FirstSpendApprovalRequired)?;
+        require_keys_eq!(
+            approver.key(),
+            policy.authority,
+            VaultError::FirstSpendApprovalRequired
+        );
+    }
+
+    // ── Pull-confirm: recipient co-signs to acknowledge the transfer ──
+    require!(
+        !policy.require_recipient_signature || accounts.recipient.is_signer,
+        VaultError::RecipientSignatureRequired
+    );
+
+    let clock = Clock::get()?;
+    roll_policy_windows(policy, &clock);
+
+    if let Some(agent_entry) = accounts.agent_entry.as_mut() {
+        agent_entry.roll_day(policy.day_index);
+    }
+    let agent_over_budget = accounts
+        .agent_entry
+        .as_ref()
+        .is_some_and(|entry| entry.exceeds_daily_budget(amount));
+    let region_over_cap = region_budget
+        .as_deref_mut()
+        .is_some_and(|region| region.exceeds_daily_cap(policy.day_index, amount));
+    let usd_cents = usd_cents_for(policy, remaining_accounts, clock.unix_timestamp, amount)?;
+
+    // Determine if the intent is allowed.
+    let decision = spend_intent_decision(
+        policy,
+        &policy.key(),
+        &caller_key,
+        is_agent,
+        &accounts.vault.to_account_info(),
+        &accounts.recipient,
+        accounts.fixed_amounts.as_deref(),
+        accounts.recipient_allow.as_deref(),
+        accounts.owner.as_deref(),
+        *accounts.blocked_recipient.owner == crate::ID,
+        true,
+        policy.daily_budget_lamports,
+        agent_over_budget,
+        region_over_cap,
+        usd_cents,
+        &clock,
+        amount,
+    )?;
+
+    let decision = apply_spend_hook(
+        decision,
+        policy,
+        accounts.hook_program.as_ref(),
+        [
+            accounts.vault.to_account_info(),
+            accounts.recipient.to_account_info(),
+            accounts.caller.to_account_info(),
+        ],
+        remaining_accounts,
+        amount,
+    )?;
+    let held_for_approval = decision.0 && policy.needs_approval(is_authority, amount);
+    let decision = if held_for_approval {
+        (false, REASON_APPROVAL_REQUIRED)
+    } else {
+        decision
+    };
+
+    // Rent the caller paid for this instruction's AuditEvent (and receipt).
+    let rent_paid = Rent::get()?
+        .minimum_balance(AuditEvent::SIZE)
+        .saturating_add(extra_rent);
+
+    finalize_spend(
+        policy,
+        &mut accounts.audit_event,
+        &accounts.vault,
+        &accounts.recipient,
+        &accounts.caller.to_account_info(),
+        is_agent,
+        amount,
+        decision,
+        rent_paid,
+        &clock,
+        region_code,
+        accounts.notify_program.as_ref(),
+    )?;
+
+    settle_pending_spend(
+        &mut accounts.policy,
+        accounts.pending_spend.as_mut(),
+        held_for_approval,
+        accounts.recipient.key(),
+        &accounts.caller.to_account_info(),
+        amount,
+        accounts.audit_event.sequence,
+        &clock,
+    )?;
+
+    if decision.0 {
+        if let Some(agent_entry) = accounts.agent_entry.as_mut() {
+            agent_entry.record(amount);
+        }
+        if let Some(cents) = usd_cents {
+            let policy = &mut accounts.policy;
+            policy.spent_today_usd_cents = policy.spent_today_usd_cents.saturating_add(cents);
+        }
+        if let Some(region) = region_budget {
+            region.spent_today_lamports = region.spent_today_lamports.saturating_add(amount);
+        }
+    }
+    Ok(decision)
 }
 
-// 8 + 32 + 32 + 8 + 8 + 1 = 89
-impl RecipientSpend {
-    pub const SIZE: usize = 8 + 32 + 32 + 8 + 8 + 1;
+/// Queue a spend held for approval: fill the caller's `PendingSpend`, take
+/// its id from `policy.next_pending_id` and hold its lamports in
+/// `policy.pending_lamports`. A `PendingSpend` passed for a
+/// spend that was not held is closed again (rent back to the caller).
+#[allow(clippy::too_many_arguments)]
+fn settle_pending_spend<'info>(
+    policy: &mut Account<'info, Policy>,
+    pending_spend: Option<&mut Account<'info, PendingSpend>>,
+    held_for_approval: bool,
+    recipient: Pubkey,
+    caller: &AccountInfo<'info>,
+    amount: u64,
+    sequence: u64,
+    clock: &Clock,
+) -> Result<()> {
+    if !held_for_approval {
+        if let Some(pending) = pending_spend {
+            pending.close(caller.clone())?;
+        }
+        return Ok(());
+    }
+    let pending = pending_spend.ok_or(VaultError::PendingSpendRequired)?;
+    pending.policy = policy.key();
+    pending.recipient = recipient;
+    pending.payer = caller.key();
+    pending.amount = amount;
+    pending.requested_ts = clock.unix_timestamp;
+    pending.expires_ts = clock
+        .unix_timestamp
+        .saturating_add(i64::from(policy.approval_ttl_seconds));
+    pending.id = policy.next_pending_id;
+    pending.sequence = sequence;
+    pending.budget_scale = policy.budget_scale;
+    pending.bump = Pubkey::find_program_address(
+        &[b"pending", policy.key().as_ref(), &pending.id.to_le_bytes()],
+        &crate::ID,
+    )
+    .1;
+    policy.next_pending_id = policy.next_pending_id.saturating_add(1);
+    policy.pending_lamports = policy.pending_lamports.saturating_add(pending.lamports()?);
+    Ok(())
 }
 
-// ──────────────── Instruction Contexts ────────────────
+/// USD value in cents of an `amount`-unit spend at the policy's price feed,
+/// read from the feed account among `remaining_accounts`. `None` without a
+/// feed, or when the feed is missing, not trading or older than
+/// `max_price_age_seconds`.
+fn usd_cents_for(
+    policy: &Policy,
+    remaining_accounts: &[AccountInfo],
+    now: i64,
+    amount: u64,
+) -> Result<Option<u64>> {
+    let Some(price_feed) = policy.price_feed else {
+        return Ok(None);
+    };
+    remaining_accounts
+        .iter()
+        .find(|info| info.key() == price_feed)
+        .and_then(|info| {
+            let data = info.try_borrow_data().ok()?;
+            pyth_price(&data, now, policy.max_price_age_seconds)
+        })
+        .map(|(price, expo)| {
+            lamports_to_usd_cents(policy.scaled(amount)?, price, expo)
+                .ok_or_else(|| error!(VaultError::ArithmeticOverflow))
+        })
+        .transpose()
+}
 
-#[derive(Accounts)]
-pub struct InitializeVault<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = Vault::SIZE,
-        seeds = [b"vault", owner.key().as_ref()],
-        bump,
-    )]
-    pub vault: Account<'info, Vault>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
+/// The `spend_intent` decision chain, before the spend hook and the approval
+/// threshold: the first failing check's reason, or `(true, REASON_OK)`.
+/// Shared with `spend_intent_metered` (which passes its metered `daily_budget`
+/// and its allowlist result as `recipient_allowed`; `spend_intent` has no
+/// allowlist and passes `true`) and `simulate_spend`; `policy` must already be
+/// rolled to `clock`.
+#[allow(clippy::too_many_arguments)]
+fn spend_intent_decision(
+    policy: &Policy,
+    policy_key: &Pubkey,
+    caller: &Pubkey,
+    is_agent: bool,
+    vault: &AccountInfo,
+    recipient: &AccountInfo,
+    fixed_amounts: Option<&FixedAmounts>,
+    recipient_allow: Option<&RecipientAllow>,
+    owner: Option<&AccountInfo>,
+    recipient_blocked: bool,
+    recipient_allowed: bool,
+    daily_budget: u64,
+    agent_over_budget: bool,
+    region_over_cap: bool,
+    usd_cents: Option<u64>,
+    clock: &Clock,
+    amount: u64,
+) -> Result<(bool, u16)> {
+    let lamports = policy.scaled(amount)?;
+    let reserve_breached = policy.breaches_agent_reserve(caller, vault, lamports)?;
+    let reserve_protected = policy.breaches_min_reserve(vault, lamports);
+    let owner_gone = policy.owner_gone(owner)?;
+
+    Ok(if recipient_blocked {
+        (false, REASON_RECIPIENT_BLOCKED)
+    } else if policy.in_denial_backoff(clock) {
+        (false, REASON_BACKOFF)
+    } else if amount == 0 {
+        (false, REASON_INVALID_AMOUNT)
+    } else if policy.exceeds_spend_count() {
+        (false, REASON_COUNT_LIMIT)
+    } else if !policy.amount_allowed(fixed_amounts, amount) {
+        (false, REASON_AMOUNT_NOT_ALLOWED)
+    } else if policy.is_invalid_recipient(policy_key, recipient.key) {
+        (false, REASON_INVALID_RECIPIENT)
+    } else if policy.blocks_program_recipient(recipient) {
+        (false, REASON_RECIPIENT_IS_PROGRAM)
+    } else if !policy.recipient_registered_for(caller, recipient_allow) {
+        (false, REASON_RECIPIENT_NOT_REGISTERED)
+    } else if reserve_breached {
+        (false, REASON_AGENT_RESERVE_VIOLATION)
+    } else if reserve_protected {
+        (false, REASON_RESERVE_PROTECTED)
+    } else if owner_gone {
+        (false, REASON_OWNER_GONE)
+    } else if policy.paused {
+        (false, REASON_PAUSED)
+    } else if policy.in_maintenance(clock) {
+        (false, REASON_MAINTENANCE)
+    } else if policy.seconds_until_window_opens(clock.unix_timestamp) > 0 {
+        (false, REASON_OUTSIDE_WINDOW)
+    } else if !recipient_allowed {
+        (false, REASON_RECIPIENT_NOT_ALLOWED)
+    } else if exceeds_limit(policy.spent_today(clock), amount, daily_budget) {
+        (false, REASON_BUDGET_EXCEEDED)
+    } else if policy.price_feed.is_some() && usd_cents.is_none() {
+        (false, REASON_STALE_PRICE)
+    } else if usd_cents.is_some_and(|cents| {
+        exceeds_limit(
+            policy.spent_today_usd_cents,
+            cents,
+            policy.daily_budget_usd_cents,
+        )
+    }) {
+        (false, REASON_BUDGET_EXCEEDED)
+    } else if policy.exceeds_release_schedule(clock, daily_budget, amount) {
+        (false, REASON_RELEASE_SCHEDULE)
+    } else if policy.exceeds_weekly_budget(amount) {
+        (false, REASON_WEEKLY_EXCEEDED)
+    } else if policy.exceeds_monthly_budget(amount) {
+        (false, REASON_MONTHLY_BUDGET_EXCEEDED)
+    } else if policy.exceeds_agent_weekly_budget(is_agent, amount) {
+        (false, REASON_AGENT_WEEKLY_BUDGET)
+    } else if agent_over_budget {
+        (false, REASON_AGENT_BUDGET_EXCEEDED)
+    } else if region_over_cap {
+        (false, REASON_REGION_CAP)
+    } else if policy.in_cooldown(clock) {
+        (false, REASON_COOLDOWN)
+    } else if policy.slot_already_spent(clock) {
+        (false, REASON_SLOT_ALREADY_SPENT)
+    } else {
+        (true, REASON_OK)
+    })
 }
 
-#[derive(Accounts)]
-pub struct InitializePolicy<'info> {
-    #[account(
-        init,
-        payer = owner,
-        space = Policy::SIZE,
-        seeds = [b"policy", vault.key().as_ref()],
-        bump,
-    )]
-    pub policy: Account<'info, Policy>,
-    #[account(
-        has_one = owner,
-        seeds = [b"vault", owner.key().as_ref()],
-        bump = vault.bump,
-    )]
-    pub vault: Account<'info, Vault>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    pub system_program: Program<'info, System>,
+/// Move `amount` lamports from `funder` into the vault and emit `Deposited`.
+fn deposit_into_vault<'info>(
+    system_program: &Program<'info, System>,
+    funder: &Signer<'info>,
+    vault: &Account<'info, Vault>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, VaultError::InvalidDepositAmount);
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: funder.to_account_info(),
+                to: vault.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+    emit!(Deposited {
+        vault: vault.key(),
+        funder: funder.key(),
+        amount,
+        new_balance: vault.to_account_info().lamports(),
+        ts: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct SetPolicy<'info> {
-    #[account(
-        mut,
-        seeds = [b"policy", vault.key().as_ref()],
-        bump = policy.bump,
-    )]
-    pub policy: Account<'info, Policy>,
-    #[account(
-        seeds = [b"vault", vault.owner.as_ref()],
-        bump = vault.bump,
-    )]
-    pub vault: Account<'info, Vault>,
-    pub authority: Signer<'info>,
+/// Shared tail of every lamport spend path: `record_spend`, then — when
+/// allowed — move the lamports from the vault to the recipient.
+#[allow(clippy::too_many_arguments)]
+fn finalize_spend<'info>(
+    policy: &mut Account<'info, Policy>,
+    audit: &mut Account<'info, AuditEvent>,
+    vault: &Account<'info, Vault>,
+    recipient: &AccountInfo<'info>,
+    caller: &AccountInfo<'info>,
+    is_agent: bool,
+    amount: u64,
+    decision: (bool, u16),
+    rent_paid: u64,
+    clock: &Clock,
+    region_code: u16,
+    notify: Option<&UncheckedAccount<'info>>,
+) -> Result<()> {
+    record_spend(
+        policy,
+        audit,
+        vault,
+        recipient,
+        caller,
+        is_agent,
+        amount,
+        decision,
+        rent_paid,
+        clock,
+        None,
+        region_code,
+        notify,
+    )?;
+    if decision.0 {
+        let lamports = policy.scaled(amount)?;
+        pay_from_vault(&vault.to_account_info(), recipient, lamports)?;
+    }
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct SpendIntent<'info> {
-    #[account(
-        init,
-        payer = caller,
-        space = AuditEvent::SIZE,
-        seeds = [
-            b"audit",
-            policy.key().as_ref(),
-            policy.next_sequence.to_le_bytes().as_ref(),
-        ],
-        bump,
-    )]
-    pub audit_event: Account<'info, AuditEvent>,
-    #[account(
-        mut,
-        seeds = [b"policy", vault.key().as_ref()],
-        bump = policy.bump,
-    )]
-    pub policy: Account<'info, Policy>,
-    #[account(
-        mut,
-        seeds = [b"vault", vault.owner.as_ref()],
-        bump = vault.bump,
-    )]
-    pub vault: Account<'info, Vault>,
-    /// CHECK: Recipient of the SOL transfer. Validated by system_program CPI.
-    #[account(mut)]
-    pub recipient: UncheckedAccount<'info>,
-    #[account(mut)]
-    pub caller: Signer<'info>,
-    pub system_program: Program<'info, System>,
+/// Move `amount` lamports from the vault PDA to `to`.
+///
+/// Direct lamport manipulation: `SystemProgram::transfer` cannot be used
+/// because the vault PDA carries account data.
+fn pay_from_vault(vault: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    **vault.try_borrow_mut_lamports()? = vault
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(VaultError::InsufficientVaultBalance)?;
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct SpendIntentV2<'info> {
-    #[account(
-        init,
-        payer = caller,
-        space = AuditEvent::SIZE,
-        seeds = [
-            b"audit",
-            policy.key().as_ref(),
-            policy.next_sequence.to_le_bytes().as_ref(),
-        ],
-        bump,
-    )]
-    pub audit_event: Account<'info, AuditEvent>,
+/// Write the AuditEvent PDA, advance the sequence and — when allowed — count
+/// the spend against the policy's windows. Emits `SpendRecorded` and calls
+/// `notify_spend` either way, so every spend path notifies. Moves no lamports.
+///
+/// Token spends (`mint` set) are budgeted by their `TokenBudget`, so they skip
+/// the lamport counters but still stamp the shared cooldown.
+///
+/// `caller` paid for the AuditEvent; with `sequence_on_allowed_only` a denied
+/// attempt's AuditEvent is closed back to it. `is_agent` is the spend path's
+/// own authorization result (legacy `agent` or an `Agent` entry).
+#[allow(clippy::too_many_arguments)]
+fn record_spend<'info>(
+    policy: &mut Account<'info, Policy>,
+    audit: &mut Account<'info, AuditEvent>,
+    vault: &Account<'info, Vault>,
+    recipient: &AccountInfo<'info>,
+    caller: &AccountInfo<'info>,
+    is_agent: bool,
+    amount: u64,
+    (allowed, reason_code): (bool, u16),
+    rent_paid: u64,
+    clock: &Clock,
+    mint: Option<Pubkey>,
+    region_code: u16,
+    notify: Option<&UncheckedAccount<'info>>,
+) -> Result<()> {
+    audit.mint = mint;
+    audit.region_code = region_code;
+    let sequence = write_audit_event(
+        policy,
+        audit,
+        recipient.key(),
+        caller,
+        is_agent,
+        amount,
+        (allowed, reason_code),
+        rent_paid,
+        clock,
+    )?;
 
-    #[account(
-        init_if_needed,
-        payer = caller,
-        space = RecipientSpend::SIZE,
-        seeds = [
-            b"recipient",
-            policy.key().as_ref(),
-            recipient.key().as_ref(),
-        ],
-        bump,
-    )]
-    pub recipient_spend: Account<'info, RecipientSpend>,
+    // Update counters only when allowed.
+    if allowed {
+        policy.allowed_count_today = policy.allowed_count_today.saturating_add(1);
+        count_allowed_spend(
+            policy,
+            &caller.key(),
+            is_agent,
+            amount,
+            clock,
+            mint.is_none(),
+        )?;
+    } else {
+        policy.denied_count_today = policy.denied_count_today.saturating_add(1);
+        // The backoff throttles the policy's own spenders: a denial during
+        // the backoff does not restart it, a hold is not a failed attempt, and
+        // other callers (an allowance recipient's claim) cannot start it.
+        let is_spender = is_agent || caller.key() == policy.authority;
+        if is_spender && reason_code != REASON_APPROVAL_REQUIRED && reason_code != REASON_BACKOFF {
+            policy.consecutive_denials = policy.consecutive_denials.saturating_add(1);
+            policy.last_denial_ts = clock.unix_timestamp;
+        }
+    }
+
+    // Token amounts are not comparable with `notify_threshold_lamports`, so
+    // token spends only notify when denied.
+    if mint.is_none() || !allowed {
+        notify_spend(
+            policy,
+            notify,
+            [vault.to_account_info(), recipient.clone(), caller.clone()],
+            amount,
+            (allowed, reason_code),
+            sequence,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Write the AuditEvent PDA (its `mint` and `region_code` are left to the
+/// caller), advance the sequence and emit `SpendRecorded`, chained to the
+/// previous event. Returns the event's sequence. Counts nothing: besides
+/// `record_spend`, timelock settlement uses it directly, since a claim's
+/// budget was counted when it was committed.
+///
+/// `caller` paid for the AuditEvent; with `sequence_on_allowed_only` a denied
+/// outcome's AuditEvent is closed back to it.
+#[allow(clippy::too_many_arguments)]
+fn write_audit_event<'info>(
+    policy: &mut Account<'info, Policy>,
+    audit: &mut Account<'info, AuditEvent>,
+    recipient: Pubkey,
+    caller: &AccountInfo<'info>,
+    is_agent: bool,
+    amount: u64,
+    (allowed, reason_code): (bool, u16),
+    rent_paid: u64,
+    clock: &Clock,
+) -> Result<u64> {
+    audit.policy = policy.key();
+    audit.sequence = policy.next_sequence;
+    audit.ts = clock.unix_timestamp;
+    audit.recipient = recipient;
+    audit.amount = amount;
+    audit.allowed = allowed;
+    audit.reason_code = reason_code;
+    audit.policy_version = policy.policy_version;
+    audit.environment_tag = policy.environment_tag;
+    audit.caller = caller.key();
+    audit.caller_is_agent = is_agent;
+
+    // Advance sequence counter (denials count separately when configured).
+    let keep_audit = allowed || !policy.sequence_on_allowed_only;
+    let sequence = if keep_audit {
+        policy.next_sequence = policy
+            .next_sequence
+            .checked_add(1)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        audit.sequence
+    } else {
+        let denial = policy.denial_sequence;
+        policy.denial_sequence = policy.denial_sequence.saturating_add(1);
+        denial
+    };
+
+    // Emit Anchor event for off-chain indexers, chained to the previous one.
+    let event = SpendRecorded {
+        vault: policy.vault,
+        policy: policy.key(),
+        policy_version: policy.policy_version,
+        sequence,
+        recipient,
+        amount,
+        allowed,
+        reason_code,
+        ts: clock.unix_timestamp,
+        rent_paid: if keep_audit {
+            rent_paid
+        } else {
+            rent_paid.saturating_sub(audit.to_account_info().lamports())
+        },
+        prev_sequence_hash: policy.last_event_hash,
+        mint: audit.mint,
+        region_code: audit.region_code,
+        caller: caller.key(),
+        out_of_sequence: !keep_audit,
+    };
+    let mut data = Vec::new();
+    event.serialize(&mut data)?;
+    policy.last_event_hash = hashv(&[&data]).to_bytes();
+    emit!(event);
+
+    // Denied attempt outside the payment sequence: refund its AuditEvent so the
+    // same sequence PDA is free for the next attempt.
+    if !keep_audit {
+        audit.close(caller.clone())?;
+    }
+    Ok(sequence)
+}
+
+/// Count an allowed spend of `amount` by `caller`: stamps the cooldown and
+/// clears the denial streak; lamport spends also add to the daily, weekly,
+/// rolling and monthly counters (and the agents' weekly one when `is_agent`).
+fn count_allowed_spend(
+    policy: &mut Account<Policy>,
+    caller: &Pubkey,
+    is_agent: bool,
+    amount: u64,
+    clock: &Clock,
+    lamports: bool,
+) -> Result<()> {
+    if let Some(gap_seconds) = policy.missed_heartbeat(clock.unix_timestamp) {
+        emit!(SpendHeartbeatMissed {
+            policy: policy.key(),
+            caller: *caller,
+            last_spend_ts: policy.last_spend_ts,
+            gap_seconds,
+            ts: clock.unix_timestamp,
+        });
+    }
+    policy.last_spend_ts = clock.unix_timestamp;
+    policy.last_spend_slot = clock.slot;
+    policy.has_spent_once = true;
+    policy.consecutive_denials = 0;
+    if lamports {
+        policy.spent_today_lamports = policy
+            .spent_today_lamports
+            .checked_add(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        policy.spent_this_week_lamports = policy.spent_this_week_lamports.saturating_add(amount);
+        policy.record_rolling_spend(clock.unix_timestamp, amount);
+        policy.spent_this_month_lamports = policy.spent_this_month_lamports.saturating_add(amount);
+        if is_agent {
+            policy.agent_spent_this_week_lamports =
+                policy.agent_spent_this_week_lamports.saturating_add(amount);
+        }
+    }
+    Ok(())
+}
+
+// ──────────────── Return types ────────────────
+
+/// Packed per-policy view returned by `get_policies_summary` (19 bytes each).
+/// Entries follow the order of the passed accounts, so no key is repeated here.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PolicySummary {
+    pub daily_budget_lamports: u64,
+    pub remaining_today_lamports: u64,
+    pub paused: bool,
+    pub policy_version: u16,
+}
+
+/// Outcome returned by `simulate_spend`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SpendSimulation {
+    pub allowed: bool,
+    pub reason_code: u16,
+}
+
+/// PDA addresses returned by `derive_addresses`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DerivedAddresses {
+    pub vault: Pubkey,
+    pub policy: Pubkey,
+    pub fixed_amounts: Pubkey,
+    /// `None` unless the policy account was passed.
+    pub next_audit_event: Option<Pubkey>,
+}
+
+/// Daily budget status returned by `remaining_budget`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct DailyBudgetStatus {
+    pub remaining_today: u64,
+    pub seconds_until_cooldown_clears: i64,
+    pub paused: bool,
+}
+
+// ──────────────── Events ────────────────
+
+#[event]
+pub struct SpendRecorded {
+    pub vault: Pubkey,
+    pub policy: Pubkey,
+    pub policy_version: u16,
+    pub sequence: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub allowed: bool,
+    pub reason_code: u16,
+    pub ts: i64,
+    /// Lamports the caller paid in rent for accounts created by this spend.
+    pub rent_paid: u64,
+    /// `sha256` of the borsh body of this policy's previous `SpendRecorded`
+    /// (all zeroes for the first), so the stream can be checked for gaps and
+    /// reordering. The latest hash is kept in `Policy::last_event_hash`.
+    pub prev_sequence_hash: [u8; 32],
+    /// Token mint for `spend_intent_spl`; `None` for SOL spends.
+    pub mint: Option<Pubkey>,
+    /// Jurisdiction code from `spend_intent_tagged`; 0 = untagged.
+    pub region_code: u16,
+    /// Signer that submitted the spend.
+    pub caller: Pubkey,
+    /// Denied attempt kept out of the payment sequence
+    /// (`sequence_on_allowed_only`): `sequence` is then its `denial_sequence`
+    /// number, so payment sequences never repeat.
+    pub out_of_sequence: bool,
+}
+
+#[event]
+pub struct BudgetChanged {
+    pub policy: Pubkey,
+    pub old: u64,
+    pub new: u64,
+    pub reason: [u8; 32],
+    pub by: Pubkey,
+    pub ts: i64,
+}
+
+/// Guardian paused the policy via `freeze`.
+#[event]
+pub struct PolicyFrozen {
+    pub policy: Pubkey,
+    pub guardian: Pubkey,
+    pub policy_version: u16,
+    pub ts: i64,
+}
+
+/// Authority zeroed today's counters via `reset_spend_counters`; `recipient`
+/// is set when a `RecipientSpend` tracker was reset too.
+#[event]
+pub struct CountersReset {
+    pub policy: Pubkey,
+    pub authority: Pubkey,
+    pub recipient: Option<Pubkey>,
+    pub day_index: i64,
+    pub ts: i64,
+}
+
+/// New limits after `set_policy` / `set_policy_advanced`, keyed by the
+/// policy version they produced.
+#[event]
+pub struct PolicyUpdated {
+    pub vault: Pubkey,
+    pub policy: Pubkey,
+    pub policy_version: u16,
+    pub daily_budget_lamports: u64,
+    pub cooldown_seconds: u32,
+    pub agent: Option<Pubkey>,
+    pub paused: bool,
+    pub allowlist_enabled: bool,
+    pub allowed_recipient: Option<Pubkey>,
+    pub per_recipient_daily_cap_lamports: u64,
+    pub ts: i64,
+}
+
+/// Rollup of a finished day, emitted by the first call that rolls past it.
+/// `distinct_recipients` counts payees paid through `spend_intent_v2` while a
+/// per-recipient limit is on (the only path that tracks recipients per day).
+#[event]
+pub struct DailySummary {
+    pub policy: Pubkey,
+    pub day_index: i64,
+    pub total_spent: u64,
+    pub allowed_count: u32,
+    pub denied_count: u32,
+    pub distinct_recipients: u32,
+    pub ts: i64,
+}
+
+/// Full copy of an AuditEvent, emitted by `export_audit_events`.
+#[event]
+pub struct AuditExported {
+    pub audit_event: Pubkey,
+    pub policy: Pubkey,
+    pub sequence: u64,
+    pub ts: i64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub allowed: bool,
+    pub reason_code: u16,
+    pub policy_version: u16,
+    pub environment_tag: u8,
+    pub mint: Option<Pubkey>,
+    pub region_code: u16,
+    pub caller: Pubkey,
+    pub caller_is_agent: bool,
+}
+
+#[event]
+pub struct TimelockClaimed {
+    pub policy: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub ts: i64,
+}
 
-    #[account(
-        mut,
-        seeds = [b"policy", vault.key().as_ref()],
-        bump = policy.bump,
-    )]
-    pub policy: Account<'info, Policy>,
-    #[account(
-        mut,
-        seeds = [b"vault", vault.owner.as_ref()],
-        bump = vault.bump,
-    )]
-    pub vault: Account<'info, Vault>,
-    /// CHECK: Recipient of the SOL transfer. Validated by system_program CPI.
-    #[account(mut)]
-    pub recipient: UncheckedAccount<'info>,
-    #[account(mut)]
-    pub caller: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct PendingSpendRejected {
+    pub policy: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub id: u64,
+    pub ts: i64,
 }
 
-#[derive(Accounts)]
-pub struct CloseAuditEvent<'info> {
-    #[account(
-        mut,
-        close = authority,
-        has_one = policy,
-    )]
-    pub audit_event: Account<'info, AuditEvent>,
-    #[account(
-        seeds = [b"policy", policy.vault.as_ref()],
-        bump = policy.bump,
-    )]
-    pub policy: Account<'info, Policy>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
+#[event]
+pub struct BatchSpendRecorded {
+    pub policy: Pubkey,
+    pub batch_id: u64,
+    pub caller: Pubkey,
+    pub total: u64,
+    pub count: u8,
+    pub ts: i64,
 }
 
-#[derive(Accounts)]
-pub struct CloseRecipientSpend<'info> {
-    #[account(
-        mut,
-        close = authority,
-        has_one = policy,
-        seeds = [b"recipient", policy.key().as_ref(), recipient.key().as_ref()],
-        bump = recipient_spend.bump,
-    )]
-    pub recipient_spend: Account<'info, RecipientSpend>,
-    pub policy: Account<'info, Policy>,
-    /// CHECK: Only used for PDA derivation.
-    pub recipient: UncheckedAccount<'info>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
+#[event]
+pub struct TimelockCancelled {
+    pub policy: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub sequence: u64,
+    pub ts: i64,
 }
 
-// ──────────────── Events ────────────────
+#[event]
+pub struct AgentRotated {
+    pub policy: Pubkey,
+    pub old_agent: Option<Pubkey>,
+    pub new_agent: Option<Pubkey>,
+    pub policy_version: u16,
+    pub ts: i64,
+}
 
 #[event]
-pub struct SpendRecorded {
+pub struct Deposited {
+    pub vault: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    /// Vault lamports after the deposit.
+    pub new_balance: u64,
+    pub ts: i64,
+}
+
+/// Owner withdrawal via `withdraw`; never a policy-governed spend.
+#[event]
+pub struct Withdrawn {
     pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    /// Vault lamports after the withdrawal.
+    pub new_balance: u64,
+    pub ts: i64,
+}
+
+/// First allowed spend after a silence longer than `max_spend_gap_seconds`.
+#[event]
+pub struct SpendHeartbeatMissed {
     pub policy: Pubkey,
-    pub policy_version: u16,
-    pub sequence: u64,
-    pub recipient: Pubkey,
+    pub caller: Pubkey,
+    pub last_spend_ts: i64,
+    pub gap_seconds: i64,
+    pub ts: i64,
+}
+
+/// Outcome of a `fund_caller` top-up; denied attempts move nothing.
+#[event]
+pub struct CallerFunded {
+    pub policy: Pubkey,
+    pub caller: Pubkey,
     pub amount: u64,
     pub allowed: bool,
     pub reason_code: u16,
+    pub ops_funding_spent_today: u64,
+    pub ts: i64,
+}
+
+#[event]
+pub struct RefundRecorded {
+    pub policy: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub ts: i64,
+}
+
+#[event]
+pub struct MisdirectedRecovered {
+    pub policy: Pubkey,
+    pub target: Pubkey,
+    pub amount: u64,
+    pub authority: Pubkey,
+    pub ts: i64,
+}
+
+#[event]
+pub struct AuthorityTransferProposed {
+    pub policy: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub policy_version: u16,
+    pub ts: i64,
+}
+
+#[event]
+pub struct AuthorityTransferAccepted {
+    pub policy: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub policy_version: u16,
+    pub ts: i64,
+}
+
+#[event]
+pub struct PolicyReassigned {
+    pub old_policy: Pubkey,
+    pub new_policy: Pubkey,
+    pub old_vault: Pubkey,
+    pub new_vault: Pubkey,
+    pub authority: Pubkey,
+    pub policy_version: u16,
+    pub ts: i64,
+}
+
+#[event]
+pub struct PolicyLocked {
+    pub policy: Pubkey,
+    pub authority: Pubkey,
+    pub policy_version: u16,
+    pub ts: i64,
+}
+
+/// `paused` went from false to true, via `set_policy_advanced` (`by` = the
+/// authority) or `freeze` (`by` = the guardian).
+#[event]
+pub struct PolicyPaused {
+    pub policy: Pubkey,
+    pub by: Pubkey,
+    pub ts: i64,
+}
+
+/// `paused` went from true to false via `set_policy_advanced` or `unfreeze`.
+#[event]
+pub struct PolicyUnpaused {
+    pub policy: Pubkey,
+    pub by: Pubkey,
     pub ts: i64,
 }
 
 // ──────────────── Errors ────────────────
 
+/// Anchor numbers variants from 6000 in declaration order, so new variants
+/// are only ever appended to keep client-visible codes stable.
 #[error_code]
 pub enum VaultError {
     #[msg("Unauthorized: signer is not the policy authority or agent")]
     Unauthorized,
+    #[msg("Account is not a PolicyVault account that supports migration")]
+    UnknownAccountType,
+    #[msg("Too many accounts passed for a single call")]
+    TooManyAccounts,
+    #[msg("Account is not a Policy owned by this program")]
+    InvalidPolicyAccount,
+    #[msg("Unknown time unit (expected 0 = seconds or 1 = slots)")]
+    InvalidTimeUnit,
+    #[msg("Maintenance window start must not be after its end")]
+    InvalidMaintenanceWindow,
+    #[msg("Spend hook cannot be this program")]
+    InvalidHookProgram,
+    #[msg("Target is not a Policy, AuditEvent or RecipientSpend of this policy")]
+    InvalidRecoveryTarget,
+    #[msg("Target holds no lamports above its rent-exempt minimum")]
+    NothingToRecover,
+    #[msg("No meter account is configured for this policy")]
+    MeterNotConfigured,
+    #[msg("Meter account does not match policy.meter_account")]
+    MeterMismatch,
+    #[msg("Meter offset is out of bounds for the meter account")]
+    InvalidMeterOffset,
+    #[msg("First spend requires the authority as an additional signer")]
+    FirstSpendApprovalRequired,
+    #[msg("Too many fixed amounts (max MAX_FIXED_AMOUNTS)")]
+    TooManyFixedAmounts,
+    #[msg("RecipientSpend account is required while a per-recipient limit is on")]
+    RecipientSpendRequired,
+    #[msg("Basis points must be at most 10000")]
+    InvalidBps,
+    #[msg("Recipient must co-sign this spend")]
+    RecipientSignatureRequired,
+    #[msg("Refund amount must be greater than zero")]
+    InvalidRefundAmount,
+    #[msg("Recipient cannot be the vault or its owner")]
+    InvalidRecipient,
+    #[msg("Commitment account is not an Allowance or TimelockedClaim of this policy")]
+    InvalidCommitmentAccount,
+    #[msg("Unlock time must be in the future")]
+    InvalidUnlockTs,
+    #[msg("Timelocked claim has not reached its unlock time")]
+    TimelockNotMatured,
+    #[msg("Timelocked claim has already unlocked")]
+    TimelockMatured,
+    #[msg("Vault owner account is required while require_owner_exists is on")]
+    OwnerAccountRequired,
+    #[msg("Account is not an AuditEvent of this policy")]
+    InvalidAuditEvent,
+    #[msg("Budget scale must be at most MAX_BUDGET_SCALE")]
+    InvalidBudgetScale,
+    #[msg("Amount overflows u64 lamports at the policy's budget scale")]
+    ScaledAmountOverflow,
+    #[msg("Policy is locked; its configuration can no longer change")]
+    PolicyLocked,
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+    #[msg("Deposit amount must be greater than zero")]
+    InvalidDepositAmount,
+    #[msg("Vault balance is too low for this transfer")]
+    InsufficientVaultBalance,
+    #[msg("Arithmetic overflow in a spend counter or balance")]
+    ArithmeticOverflow,
+    #[msg("Budget mode must be 0 (calendar) or 1 (rolling)")]
+    InvalidBudgetMode,
+    #[msg("Notify program cannot be this program")]
+    InvalidNotifyProgram,
+    #[msg("Withdraw amount must be greater than zero")]
+    InvalidWithdrawAmount,
+    #[msg("Withdrawal would leave the vault below its rent, reserve or committed lamports")]
+    WithdrawBreachesReserve,
+    #[msg("PendingSpend account is required for a spend above the approval threshold")]
+    PendingSpendRequired,
+    #[msg("Pending spend has expired and can no longer be approved")]
+    PendingSpendExpired,
+    #[msg("Approval TTL must be greater than zero while an approval threshold is set")]
+    InvalidApprovalTtl,
+    #[msg("Spend window bounds must be below 86400 and differ unless both are 0")]
+    InvalidSpendWindow,
+    #[msg("Price feed cannot be this program")]
+    InvalidPriceFeed,
+    #[msg("Close the vault's policy first")]
+    PolicyStillOpen,
+    #[msg("Vault still holds lamports above its rent-exempt minimum; withdraw them first")]
+    VaultNotEmpty,
+    #[msg("A batch must hold between 1 and MAX_BATCH_RECIPIENTS payments")]
+    InvalidBatchSize,
+    #[msg("remaining_accounts must be one (recipient, BlockedRecipient PDA) pair per amount")]
+    InvalidBatchAccounts,
+    #[msg("Batch spends are unavailable while the policy needs per-spend accounts")]
+    BatchUnsupported,
+    #[msg("Batch denied by policy; see the logged reason code")]
+    BatchDenied,
+    #[msg("Unknown cooldown scope (expected 0 = global or 1 = per recipient, seconds mode only)")]
+    InvalidCooldownScope,
+    #[msg("RecipientSpend tracker belongs to a different policy or recipient")]
+    RecipientSpendMismatch,
+    #[msg("Registry program cannot be this program")]
+    InvalidRegistryProgram,
+    #[msg("A close batch must hold between 1 and MAX_AUDIT_CLOSE_BATCH audit events")]
+    InvalidAuditCloseBatch,
+    #[msg("RecipientLifetime account is required while a per-recipient lifetime cap is set")]
+    RecipientLifetimeRequired,
+    #[msg("Old agent entry is missing or is not the current agent's entry address")]
+    OldAgentEntryMismatch,
+    #[msg("Day offset must be less than one day (86400 seconds) either way")]
+    InvalidDayOffset,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An all-zero `Policy`: every limit off, every counter empty.
+    fn blank_policy() -> Policy {
+        let zeroed = vec![0u8; Policy::SIZE];
+        Policy::try_deserialize_unchecked(&mut &zeroed[..]).unwrap()
+    }
+
+    #[test]
+    fn civil_from_days_epoch_and_negative() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    }
+
+    #[test]
+    fn civil_from_days_leap_years() {
+        // 2000 is a leap year (divisible by 400), 2100 is not, 2024 is.
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(19_416), (2023, 2, 28));
+        assert_eq!(civil_from_days(19_417), (2023, 3, 1));
+        assert_eq!(civil_from_days(47_540), (2100, 2, 28));
+        assert_eq!(civil_from_days(47_541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn month_index_changes_exactly_at_month_boundaries() {
+        let feb_1_2024 = 1_706_745_600;
+        assert_eq!(month_index(feb_1_2024) - month_index(feb_1_2024 - 1), 1);
+        // 29 days later (leap February) is still February...
+        let mar_1_2024 = feb_1_2024 + 29 * SECONDS_PER_DAY;
+        assert_eq!(month_index(mar_1_2024 - 1), month_index(feb_1_2024));
+        // ...and the next second is March.
+        assert_eq!(month_index(mar_1_2024), month_index(feb_1_2024) + 1);
+        // December → January carries into the next year.
+        assert_eq!(month_index(0) - month_index(-1), 1);
+    }
+
+    #[test]
+    fn binding_cap_reason_single_violation() {
+        assert_eq!(binding_cap_reason(5, 10, None), None);
+        assert_eq!(binding_cap_reason(5, 10, Some(10)), None);
+        assert_eq!(
+            binding_cap_reason(11, 10, None),
+            Some(REASON_BUDGET_EXCEEDED)
+        );
+        assert_eq!(
+            binding_cap_reason(11, 10, Some(20)),
+            Some(REASON_BUDGET_EXCEEDED)
+        );
+        assert_eq!(
+            binding_cap_reason(11, 20, Some(10)),
+            Some(REASON_RECIPIENT_CAP_EXCEEDED)
+        );
+    }
+
+    #[test]
+    fn binding_cap_reason_both_violated_reports_most_restrictive() {
+        // Recipient cap has less headroom left → it is the binding constraint.
+        assert_eq!(
+            binding_cap_reason(50, 30, Some(10)),
+            Some(REASON_RECIPIENT_CAP_EXCEEDED)
+        );
+        // Daily budget has less headroom left.
+        assert_eq!(
+            binding_cap_reason(50, 10, Some(30)),
+            Some(REASON_BUDGET_EXCEEDED)
+        );
+        // Tie → per-recipient cap.
+        assert_eq!(
+            binding_cap_reason(50, 20, Some(20)),
+            Some(REASON_RECIPIENT_CAP_EXCEEDED)
+        );
+        // Exhausted budget vs exhausted cap.
+        assert_eq!(
+            binding_cap_reason(1, 0, Some(0)),
+            Some(REASON_RECIPIENT_CAP_EXCEEDED)
+        );
+    }
+
+    #[test]
+    fn released_budget_window_boundaries() {
+        let day = SECONDS_PER_DAY;
+        assert_eq!(released_budget(86_400, 0, day), 0);
+        assert_eq!(released_budget(86_400, 1, day), 1);
+        assert_eq!(released_budget(86_400, day / 2, day), 43_200);
+        assert_eq!(released_budget(86_400, day - 1, day), 86_399);
+        assert_eq!(released_budget(86_400, day, day), 86_400);
+        // Out-of-range elapsed clamps to the window.
+        assert_eq!(released_budget(86_400, -5, day), 0);
+        assert_eq!(released_budget(86_400, day + 5, day), 86_400);
+    }
+
+    #[test]
+    fn released_budget_does_not_overflow() {
+        assert_eq!(
+            released_budget(u64::MAX, SECONDS_PER_DAY, SECONDS_PER_DAY),
+            u64::MAX
+        );
+        assert_eq!(
+            released_budget(u64::MAX, SECONDS_PER_DAY / 2, SECONDS_PER_DAY),
+            u64::MAX / 2
+        );
+    }
+
+    #[test]
+    fn amount_fits_unit_only_checks_nonzero_units() {
+        let mut allow = RecipientAllow {
+            policy: Pubkey::default(),
+            recipient: Pubkey::default(),
+            registered_by: Pubkey::default(),
+            bump: 0,
+            unit_lamports: 0,
+        };
+        assert!(RecipientAllow::amount_fits_unit(None, 7));
+        assert!(RecipientAllow::amount_fits_unit(Some(&allow), 7));
+        allow.unit_lamports = 100;
+        assert!(RecipientAllow::amount_fits_unit(Some(&allow), 300));
+        assert!(!RecipientAllow::amount_fits_unit(Some(&allow), 350));
+    }
+
+    #[test]
+    fn seconds_until_spendable_takes_longest_wait() {
+        let mut policy = blank_policy();
+        let clock = Clock {
+            unix_timestamp: 1_000,
+            ..Clock::default()
+        };
+        assert_eq!(policy.seconds_until_spendable(&clock), 0);
+
+        policy.cooldown_seconds = 60;
+        policy.last_spend_ts = 980;
+        assert_eq!(policy.seconds_until_spendable(&clock), 40);
+
+        policy.maintenance_start_ts = 900;
+        policy.maintenance_end_ts = 1_100;
+        assert_eq!(policy.seconds_until_spendable(&clock), 100);
+
+        policy.paused = true;
+        assert_eq!(policy.seconds_until_spendable(&clock), i64::MAX);
+    }
+
+    #[test]
+    fn recipient_cap_headroom_exclusive_at_cap() {
+        let mut policy = blank_policy();
+        assert_eq!(policy.recipient_cap_headroom(0), None);
+
+        policy.per_recipient_daily_cap_lamports = 100;
+        assert_eq!(policy.recipient_cap_headroom(40), Some(60));
+        // Inclusive: a spend of exactly 60 reaches the cap and passes.
+        assert_eq!(binding_cap_reason(60, u64::MAX, Some(60)), None);
+
+        policy.recipient_cap_exclusive = true;
+        assert_eq!(policy.recipient_cap_headroom(40), Some(59));
+        assert_eq!(policy.recipient_cap_headroom(100), Some(0));
+        assert_eq!(
+            binding_cap_reason(60, u64::MAX, policy.recipient_cap_headroom(40)),
+            Some(REASON_RECIPIENT_CAP_EXCEEDED)
+        );
+        assert_eq!(
+            binding_cap_reason(59, u64::MAX, policy.recipient_cap_headroom(40)),
+            None
+        );
+    }
+
+    #[test]
+    fn config_digest_ignores_runtime_state() {
+        let mut policy = blank_policy();
+        policy.daily_budget_lamports = 1_000;
+        let digest = policy.config_digest().unwrap();
+
+        policy.spent_today_lamports = 500;
+        policy.next_sequence = 7;
+        policy.last_event_hash = [1; 32];
+        assert_eq!(policy.config_digest().unwrap(), digest);
+
+        policy.daily_budget_lamports = 2_000;
+        assert_ne!(policy.config_digest().unwrap(), digest);
+    }
+
+    #[test]
+    fn merkle_proof_valid_for_members_only() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let pair = |x: [u8; 32], y: [u8; 32]| {
+            let (lo, hi) = if x <= y { (x, y) } else { (y, x) };
+            hashv(&[&lo, &hi]).to_bytes()
+        };
+        let root = pair(merkle_leaf(&a), merkle_leaf(&b));
+
+        assert!(merkle_proof_valid(&root, &a, &[merkle_leaf(&b)]));
+        assert!(merkle_proof_valid(&root, &b, &[merkle_leaf(&a)]));
+        assert!(!merkle_proof_valid(&root, &c, &[merkle_leaf(&a)]));
+        assert!(!merkle_proof_valid(&root, &a, &[]));
+    }
+
+    #[test]
+    fn denial_backoff_doubles_per_denial_up_to_cap() {
+        let mut policy = blank_policy();
+        let clock = Clock {
+            unix_timestamp: 1_000,
+            ..Clock::default()
+        };
+        policy.last_denial_ts = 1_000;
+        policy.consecutive_denials = 3;
+        assert!(!policy.in_denial_backoff(&clock));
+
+        policy.denial_backoff_base_seconds = 5;
+        assert_eq!(policy.denial_backoff_remaining(&clock), 40);
+        policy.consecutive_denials = 4;
+        assert_eq!(policy.denial_backoff_remaining(&clock), 80);
+        policy.consecutive_denials = 50;
+        assert_eq!(policy.denial_backoff_remaining(&clock), 5 << 10);
+        assert_eq!(policy.seconds_until_spendable(&clock), 5 << 10);
+
+        policy.consecutive_denials = 0;
+        assert!(!policy.in_denial_backoff(&clock));
+    }
+
+    #[test]
+    fn missed_heartbeat_only_past_threshold() {
+        let mut policy = blank_policy();
+        policy.last_spend_ts = 1_000;
+        assert_eq!(policy.missed_heartbeat(5_000), None);
+
+        policy.max_spend_gap_seconds = 60;
+        assert_eq!(policy.missed_heartbeat(1_060), None);
+        assert_eq!(policy.missed_heartbeat(1_061), Some(61));
+
+        // No heartbeat before the first spend.
+        policy.last_spend_ts = 0;
+        assert_eq!(policy.missed_heartbeat(1_061), None);
+    }
+
+    #[test]
+    fn rolling_window_sums_last_day_and_folds_evictions() {
+        let mut policy = blank_policy();
+        let day = SECONDS_PER_DAY;
+        policy.record_rolling_spend(1_000, 10);
+        policy.record_rolling_spend(1_000 + day / 2, 20);
+        assert_eq!(policy.rolling_spent(1_000 + day / 2), 30);
+        // The first spend leaves the window exactly 24 hours later.
+        assert_eq!(policy.rolling_spent(1_000 + day), 20);
+
+        // Filling the ring overwrites in-window entries without losing them.
+        let now = 1_000 + day;
+        for _ in 0..ROLLING_SPEND_SLOTS {
+            policy.record_rolling_spend(now, 1);
+        }
+        assert!(policy.rolling_spent(now) >= ROLLING_SPEND_SLOTS as u64 + 20);
+    }
+
+    #[test]
+    fn rolling_refund_credits_newest_in_window_spends() {
+        let mut policy = blank_policy();
+        let day = SECONDS_PER_DAY;
+        policy.record_rolling_spend(1_000, 10);
+        policy.record_rolling_spend(1_000 + day / 2, 20);
+        policy.refund_rolling_spend(1_000 + day / 2, 25);
+        assert_eq!(policy.rolling_spent(1_000 + day / 2), 5);
+        assert_eq!(policy.rolling_spends[0].amount, 5);
+        assert_eq!(policy.rolling_spends[1].amount, 0);
+
+        // Entries outside the window are left alone; excess is dropped.
+        policy.record_rolling_spend(1_000 + day, 7);
+        policy.refund_rolling_spend(1_000 + day, 100);
+        assert_eq!(policy.rolling_spent(1_000 + day), 0);
+        assert_eq!(policy.rolling_spends[0].amount, 5);
+    }
+
+    #[test]
+    fn invalid_recipient_rejects_vault_policy_and_default_key() {
+        let mut policy = blank_policy();
+        policy.vault = Pubkey::new_unique();
+        let policy_key = Pubkey::new_unique();
+
+        assert!(policy.is_invalid_recipient(&policy_key, &policy.vault));
+        assert!(policy.is_invalid_recipient(&policy_key, &policy_key));
+        assert!(policy.is_invalid_recipient(&policy_key, &Pubkey::default()));
+        assert!(!policy.is_invalid_recipient(&policy_key, &Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn spend_window_handles_wrap_around() {
+        let mut policy = blank_policy();
+        let day = 20_000 * SECONDS_PER_DAY;
+        assert_eq!(policy.seconds_until_window_opens(day + 3_600), 0);
+
+        // 09:00–17:00
+        policy.allowed_start_seconds = 9 * 3_600;
+        policy.allowed_end_seconds = 17 * 3_600;
+        assert_eq!(policy.seconds_until_window_opens(day + 9 * 3_600), 0);
+        assert_eq!(policy.seconds_until_window_opens(day + 8 * 3_600), 3_600);
+        assert_eq!(
+            policy.seconds_until_window_opens(day + 17 * 3_600),
+            16 * 3_600
+        );
+
+        // 22:00–06:00 spans midnight.
+        policy.allowed_start_seconds = 22 * 3_600;
+        policy.allowed_end_seconds = 6 * 3_600;
+        assert_eq!(policy.seconds_until_window_opens(day + 23 * 3_600), 0);
+        assert_eq!(policy.seconds_until_window_opens(day + 5 * 3_600), 0);
+        assert_eq!(
+            policy.seconds_until_window_opens(day + 6 * 3_600),
+            16 * 3_600
+        );
+        assert_eq!(
+            policy.seconds_until_window_opens(day + 12 * 3_600),
+            10 * 3_600
+        );
+    }
+
+    #[test]
+    fn pyth_price_parses_fresh_trading_feed_and_converts_to_cents() {
+        let mut data = vec![0u8; 240];
+        data[0..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[PYTH_EXPO_OFFSET..PYTH_EXPO_OFFSET + 4].copy_from_slice(&(-8i32).to_le_bytes());
+        data[PYTH_TIMESTAMP_OFFSET..PYTH_TIMESTAMP_OFFSET + 8]
+            .copy_from_slice(&1_000i64.to_le_bytes());
+        data[PYTH_AGG_PRICE_OFFSET..PYTH_AGG_PRICE_OFFSET + 8]
+            .copy_from_slice(&15_000_000_000i64.to_le_bytes()); // $150.00
+        data[PYTH_AGG_STATUS_OFFSET..PYTH_AGG_STATUS_OFFSET + 4]
+            .copy_from_slice(&PYTH_STATUS_TRADING.to_le_bytes());
+
+        assert_eq!(pyth_price(&data, 1_060, 60), Some((15_000_000_000, -8)));
+        assert_eq!(pyth_price(&data, 1_061, 60), None);
+        assert_eq!(pyth_price(&data[..200], 1_000, 60), None);
+
+        // 1 SOL = 15000 cents; a single lamport still costs a cent.
+        assert_eq!(
+            lamports_to_usd_cents(1_000_000_000, 15_000_000_000, -8),
+            Some(15_000)
+        );
+        assert_eq!(lamports_to_usd_cents(1, 15_000_000_000, -8), Some(1));
+        assert_eq!(lamports_to_usd_cents(0, 15_000_000_000, -8), Some(0));
+    }
+
+    #[test]
+    fn audit_closed_mark_advances_over_filled_gaps() {
+        let mut policy = blank_policy();
+        policy.mark_audit_closed(0);
+        assert_eq!(policy.audit_closed_through, 1);
+
+        // 2 and 3 close ahead of the gap at 1.
+        policy.mark_audit_closed(3);
+        policy.mark_audit_closed(2);
+        assert_eq!(policy.audit_closed_through, 1);
+        assert_eq!(policy.audit_closed_bitmap, 0b11);
+
+        policy.mark_audit_closed(1);
+        assert_eq!(policy.audit_closed_through, 4);
+        assert_eq!(policy.audit_closed_bitmap, 0);
+
+        // Too far ahead to track.
+        policy.mark_audit_closed(4 + 65);
+        assert_eq!(policy.audit_closed_bitmap, 0);
+    }
+
+    #[test]
+    fn recipient_scoped_cooldown_ignores_policy_wide_spend() {
+        let mut policy = blank_policy();
+        let clock = Clock {
+            unix_timestamp: 1_000,
+            ..Clock::default()
+        };
+        policy.cooldown_seconds = 60;
+        policy.last_spend_ts = 980;
+        assert!(policy.in_scoped_cooldown(0, &clock));
+
+        policy.cooldown_scope = COOLDOWN_SCOPE_RECIPIENT;
+        assert!(policy.tracks_recipients());
+        assert!(!policy.in_scoped_cooldown(0, &clock));
+        assert!(!policy.in_scoped_cooldown(900, &clock));
+        assert!(policy.in_scoped_cooldown(990, &clock));
+    }
+
+    #[test]
+    fn budget_near_u64_max_denies_on_overflow() {
+        assert!(!exceeds_limit(u64::MAX - 2, 2, u64::MAX));
+        assert!(exceeds_limit(u64::MAX - 1, 2, u64::MAX));
+
+        let mut policy = blank_policy();
+        policy.weekly_budget_lamports = u64::MAX;
+        policy.spent_this_week_lamports = u64::MAX - 1;
+        assert!(!policy.exceeds_weekly_budget(1));
+        assert!(policy.exceeds_weekly_budget(2));
+    }
+
+    #[test]
+    fn current_day_follows_negative_utc_offset() {
+        let mut policy = blank_policy();
+        // UTC-5: the day starts at 05:00 UTC.
+        policy.epoch_anchor_ts = 5 * 3_600;
+        let at = |unix_timestamp| Clock {
+            unix_timestamp,
+            ..Clock::default()
+        };
+        let day = 20_000 * SECONDS_PER_DAY;
+        assert_eq!(policy.current_day(&at(day + 5 * 3_600 - 1)), 19_999);
+        assert_eq!(policy.current_day(&at(day + 5 * 3_600)), 20_000);
+        // Before the anchor the index goes negative instead of truncating to 0.
+        assert_eq!(policy.current_day(&at(0)), -1);
+        assert_eq!(policy.current_day(&at(5 * 3_600 - 1)), -1);
+        assert_eq!(policy.current_day(&at(5 * 3_600)), 0);
+    }
+
+    #[test]
+    fn recipient_lifetime_cap_counts_carried_total() {
+        let mut policy = blank_policy();
+        assert!(!policy.exceeds_recipient_lifetime_cap(u64::MAX, 1));
+        assert!(!policy.tracks_recipients());
+
+        policy.per_recipient_lifetime_cap_lamports = 1_000;
+        assert!(policy.tracks_recipients());
+        assert!(!policy.exceeds_recipient_lifetime_cap(600, 400));
+        assert!(policy.exceeds_recipient_lifetime_cap(600, 401));
+        assert!(policy.exceeds_recipient_lifetime_cap(u64::MAX, 1));
+    }
+
+    #[test]
+    fn reason_code_str_names_every_code() {
+        let names: Vec<_> = (REASON_OK..=REASON_APPROVAL_UNAVAILABLE)
+            .map(reason_code_str)
+            .collect();
+        assert!(names.iter().all(|name| *name != "UNKNOWN"));
+        let mut unique = names.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), names.len());
+        assert_eq!(reason_code_str(REASON_BUDGET_EXCEEDED), "BUDGET_EXCEEDED");
+        assert_eq!(reason_code_str(0), "UNKNOWN");
+        assert_eq!(reason_code_str(REASON_APPROVAL_UNAVAILABLE + 1), "UNKNOWN");
+    }
+
+    #[test]
+    fn release_schedule_ignores_stale_day_spend() {
+        let mut policy = blank_policy();
+        policy.linear_release = true;
+        policy.spent_today_lamports = 1_000;
+        // Half of day 1: 500 of the 1_000 budget released.
+        let clock = Clock {
+            unix_timestamp: SECONDS_PER_DAY + SECONDS_PER_DAY / 2,
+            ..Clock::default()
+        };
+        policy.day_index = 1;
+        assert!(policy.exceeds_release_schedule(&clock, 1_000, 1));
+        // Yesterday's spend no longer counts against today's release.
+        policy.day_index = 0;
+        assert!(!policy.exceeds_release_schedule(&clock, 1_000, 500));
+        assert!(policy.exceeds_release_schedule(&clock, 1_000, 501));
+    }
+
+    #[test]
+    fn day_offset_shifts_day_week_and_month_but_not_spend_window() {
+        let mut policy = blank_policy();
+        // UTC-5: local midnight is 05:00 UTC.
+        let offset = -5 * 3_600;
+        policy.day_offset_seconds = offset as i32;
+        let at = |unix_timestamp| Clock {
+            unix_timestamp,
+            ..Clock::default()
+        };
+
+        let day = 20_000 * SECONDS_PER_DAY;
+        assert_eq!(policy.current_day(&at(day - offset - 1)), 19_999);
+        assert_eq!(policy.current_day(&at(day - offset)), 20_000);
+
+        // 2024-03-01T00:00Z is still February in UTC-5.
+        let mar_1_2024 = 1_709_251_200;
+        policy.roll_windows(&at(mar_1_2024));
+        assert_eq!(policy.month_index, month_index(mar_1_2024 - 1));
+        policy.roll_windows(&at(mar_1_2024 - offset));
+        assert_eq!(policy.month_index, month_index(mar_1_2024));
+
+        // Weeks turn over at the same local midnight.
+        let week = 2_800 * SECONDS_PER_WEEK;
+        policy.roll_windows(&at(week - offset - 1));
+        assert_eq!(policy.week_index, 2_799);
+        assert_eq!(policy.agent_week_index, 2_799);
+        policy.roll_windows(&at(week - offset));
+        assert_eq!(policy.week_index, 2_800);
+        assert_eq!(policy.agent_week_index, 2_800);
+
+        // A 09:00–17:00 window stays in UTC hours.
+        policy.allowed_start_seconds = 9 * 3_600;
+        policy.allowed_end_seconds = 17 * 3_600;
+        assert_eq!(policy.seconds_until_window_opens(day + 9 * 3_600), 0);
+        assert_eq!(policy.seconds_until_window_opens(day + 8 * 3_600), 3_600);
+        assert!(policy.seconds_until_window_opens(day + 17 * 3_600) > 0);
+    }
+
+    #[test]
+    fn day_offset_boundaries_round_down() {
+        let mut policy = blank_policy();
+        let at = |unix_timestamp| Clock {
+            unix_timestamp,
+            ..Clock::default()
+        };
+        // A negative offset before the epoch falls into day -1, not day 0.
+        policy.day_offset_seconds = -3_600;
+        assert_eq!(policy.current_day(&at(0)), -1);
+        assert_eq!(policy.current_day(&at(3_599)), -1);
+        assert_eq!(policy.current_day(&at(3_600)), 0);
+        // UTC+3: the day starts at 21:00 UTC the evening before.
+        policy.day_offset_seconds = 3 * 3_600;
+        let day = 20_000 * SECONDS_PER_DAY;
+        assert_eq!(policy.current_day(&at(day - 3 * 3_600 - 1)), 19_999);
+        assert_eq!(policy.current_day(&at(day - 3 * 3_600)), 20_000);
+        // Combined with an epoch anchor, both shifts apply.
+        policy.epoch_anchor_ts = 3 * 3_600;
+        assert_eq!(policy.current_day(&at(day - 1)), 19_999);
+        assert_eq!(policy.current_day(&at(day)), 20_000);
+    }
 }
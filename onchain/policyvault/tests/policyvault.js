@@ -58,6 +58,89 @@ describe("policyvault", () => {
     );
   }
 
+  // Helper: derive the lifetime total PDA kept across tracker closures.
+  function recipientLifetimePda(recipientPk) {
+    return PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("recipient_lifetime"),
+        policyPda.toBuffer(),
+        recipientPk.toBuffer(),
+      ],
+      program.programId
+    );
+  }
+
+  // Helper: accounts for authority-only setters on the main policy.
+  function setterAccounts() {
+    return {
+      policy: policyPda,
+      vault: vaultPda,
+      authority: owner.publicKey,
+    };
+  }
+
+  // Helper: vault + policy under a new funded owner, for tests that would
+  // otherwise change the shared policy irreversibly.
+  async function freshPolicy() {
+    const newOwner = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      newOwner.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+
+    const [newVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), newOwner.publicKey.toBuffer()],
+      program.programId
+    );
+    const [newPolicy] = PublicKey.findProgramAddressSync(
+      [Buffer.from("policy"), newVault.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeVault()
+      .accounts({
+        vault: newVault,
+        owner: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .initializePolicy(DAILY_BUDGET, COOLDOWN_SECS, null)
+      .accounts({
+        policy: newPolicy,
+        vault: newVault,
+        owner: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    return { newOwner, newVault, newPolicy };
+  }
+
+  // Helper: submit spend_intent_v2 from the owner and return the audit event.
+  async function spendV2(amount, recipientPk, extraAccounts = {}) {
+    const seq = nextSeq;
+    const [auditPdaKey] = auditPda(seq);
+    const [recipientSpendKey] = recipientSpendPda(recipientPk);
+    await program.methods
+      .spendIntentV2(amount)
+      .accounts({
+        auditEvent: auditPdaKey,
+        recipientSpend: recipientSpendKey,
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: recipientPk,
+        caller: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+        ...extraAccounts,
+      })
+      .rpc();
+    nextSeq++;
+    return program.account.auditEvent.fetch(auditPdaKey);
+  }
+
   it("A) initialize_vault", async () => {
     const vaultInfo = await provider.connection.getAccountInfo(vaultPda);
     if (!vaultInfo) {
@@ -121,6 +204,30 @@ describe("policyvault", () => {
     console.log("  fund vault tx:", tx);
   });
 
+  it("A.1) deposit — funds the vault and emits Deposited", async () => {
+    const before = await provider.connection.getBalance(vaultPda);
+    let listener;
+    const event = new Promise((resolve) => {
+      listener = program.addEventListener("deposited", resolve);
+    });
+    await program.methods
+      .deposit(new anchor.BN(1_000_000))
+      .accounts({
+        vault: vaultPda,
+        funder: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    const ev = await event;
+    await program.removeEventListener(listener);
+
+    const after = await provider.connection.getBalance(vaultPda);
+    assert.strictEqual(after, before + 1_000_000);
+    assert.ok(ev.vault.equals(vaultPda));
+    assert.ok(ev.amount.eqn(1_000_000));
+    assert.ok(ev.newBalance.eqn(after));
+  });
+
   it("D.1) spend_intent — allowed, SOL transferred to recipient", async () => {
     const seq = nextSeq;
     firstAuditSeq = seq;
@@ -368,7 +475,8 @@ describe("policyvault", () => {
         true, // paused
         false,
         null,
-        perRecipientCap
+        perRecipientCap,
+        0
       )
       .accounts({
         policy: policyPda,
@@ -429,7 +537,8 @@ describe("policyvault", () => {
         false, // unpaused
         true, // allowlist enabled
         otherRecipient.publicKey,
-        perRecipientCap
+        perRecipientCap,
+        0
       )
       .accounts({
         policy: policyPda,
@@ -489,7 +598,8 @@ describe("policyvault", () => {
         false,
         true,
         recipient.publicKey,
-        perRecipientCap
+        perRecipientCap,
+        0
       )
       .accounts({
         policy: policyPda,
@@ -607,6 +717,13 @@ describe("policyvault", () => {
     const auditInfo = await provider.connection.getAccountInfo(auditPdaKey);
     assert.strictEqual(auditInfo, null);
 
+    // ...and its sequence is recorded as reclaimed.
+    const { auditClosedThrough, auditClosedBitmap } =
+      await program.account.policy.fetch(policyPda);
+    assert.ok(
+      auditClosedThrough.gtn(firstAuditSeq) || !auditClosedBitmap.isZero()
+    );
+
     // Authority should have received rent back (minus tx fee)
     const authorityBalAfter = await provider.connection.getBalance(
       owner.publicKey
@@ -660,9 +777,11 @@ describe("policyvault", () => {
         .closeRecipientSpend()
         .accounts({
           recipientSpend: recipientSpendKey,
+          recipientLifetime: recipientLifetimePda(recipient.publicKey)[0],
           policy: policyPda,
           recipient: recipient.publicKey,
           authority: rando.publicKey,
+          systemProgram: SystemProgram.programId,
         })
         .signers([rando])
         .rpc();
@@ -693,9 +812,11 @@ describe("policyvault", () => {
       .closeRecipientSpend()
       .accounts({
         recipientSpend: recipientSpendKey,
+        recipientLifetime: recipientLifetimePda(recipient.publicKey)[0],
         policy: policyPda,
         recipient: recipient.publicKey,
         authority: owner.publicKey,
+        systemProgram: SystemProgram.programId,
       })
       .rpc();
     console.log("  close_recipient_spend tx:", tx);
@@ -703,9 +824,4719 @@ describe("policyvault", () => {
     const rsInfo = await provider.connection.getAccountInfo(recipientSpendKey);
     assert.strictEqual(rsInfo, null);
 
+    // Lifetime total survives the tracker.
+    const lifetime = await program.account.recipientLifetime.fetch(
+      recipientLifetimePda(recipient.publicKey)[0]
+    );
+    assert.ok(lifetime.lifetimeSpentLamports.eq(rsBefore.lifetimeSpentLamports));
+
     const authorityBalAfter = await provider.connection.getBalance(
       owner.publicKey
     );
     assert.ok(authorityBalAfter > authorityBalBefore - 10_000);
   });
+
+  it("C.3) set_recipient_count_limit — second payment to a recipient denied", async () => {
+    // Open the allowlist and drop the amount cap so only the count limit applies.
+    await program.methods
+      .setPolicyAdvanced(
+        new anchor.BN(50_000_000),
+        0,
+        null,
+        false,
+        false,
+        null,
+        new anchor.BN(0),
+        0
+      )
+      .accounts({
+        policy: policyPda,
+        vault: vaultPda,
+        authority: owner.publicKey,
+      })
+      .rpc();
+    policyVersion += 1;
+
+    await program.methods
+      .setRecipientCountLimit(1)
+      .accounts({
+        policy: policyPda,
+        vault: vaultPda,
+        authority: owner.publicKey,
+      })
+      .rpc();
+    policyVersion += 1;
+
+    const policy = await program.account.policy.fetch(policyPda);
+    assert.strictEqual(policy.perRecipientMaxCount, 1);
+    assert.strictEqual(policy.policyVersion, policyVersion);
+
+    const payee = anchor.web3.Keypair.generate();
+    const amount = new anchor.BN(1_000_000);
+
+    const first = await spendV2(amount, payee.publicKey);
+    assert.strictEqual(first.allowed, true);
+    assert.strictEqual(first.reasonCode, 1);
+
+    const second = await spendV2(amount, payee.publicKey);
+    assert.strictEqual(second.allowed, false);
+    assert.strictEqual(second.reasonCode, 8); // REASON_RECIPIENT_COUNT_EXCEEDED
+
+    const [recipientSpendKey] = recipientSpendPda(payee.publicKey);
+    const rs = await program.account.recipientSpend.fetch(recipientSpendKey);
+    assert.strictEqual(rs.spendCountToday, 1);
+    assert.ok(rs.spentTodayLamports.eq(amount));
+
+    // Restore unlimited so later tests are unaffected.
+    await program.methods
+      .setRecipientCountLimit(0)
+      .accounts({
+        policy: policyPda,
+        vault: vaultPda,
+        authority: owner.publicKey,
+      })
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.4) set_time_unit — cooldown measured in slots", async () => {
+    const setCooldown = async (cooldown) => {
+      await program.methods
+        .setPolicy(new anchor.BN(50_000_000), cooldown, null)
+        .accounts({
+          policy: policyPda,
+          vault: vaultPda,
+          authority: owner.publicKey,
+        })
+        .rpc();
+      policyVersion += 1;
+    };
+    const setTimeUnit = async (unit) => {
+      await program.methods
+        .setTimeUnit(unit)
+        .accounts({
+          policy: policyPda,
+          vault: vaultPda,
+          authority: owner.publicKey,
+        })
+        .rpc();
+      policyVersion += 1;
+    };
+
+    await setTimeUnit(1); // TIME_UNIT_SLOTS
+    await setCooldown(0);
+
+    const payee = anchor.web3.Keypair.generate();
+    const amount = new anchor.BN(1_000_000);
+
+    const first = await spendV2(amount, payee.publicKey);
+    assert.strictEqual(first.allowed, true);
+
+    const policy = await program.account.policy.fetch(policyPda);
+    assert.strictEqual(policy.timeUnit, 1);
+    assert.ok(policy.lastSpendSlot.gt(new anchor.BN(0)));
+
+    // A million-slot cooldown (~4.6 days) cannot have elapsed.
+    await setCooldown(1_000_000);
+    const second = await spendV2(amount, payee.publicKey);
+    assert.strictEqual(second.allowed, false);
+    assert.strictEqual(second.reasonCode, 3); // REASON_COOLDOWN
+
+    // Restore second-based timing without a cooldown.
+    await setCooldown(0);
+    await setTimeUnit(0);
+  });
+
+  it("C.5) set_maintenance_window — spends denied inside the window", async () => {
+    const slot = await provider.connection.getSlot();
+    const now = await provider.connection.getBlockTime(slot);
+
+    await program.methods
+      .setMaintenanceWindow(new anchor.BN(now - 60), new anchor.BN(now + 3600))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const audit = await spendV2(new anchor.BN(1_000_000), payee.publicKey);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 9); // REASON_MAINTENANCE
+
+    // Inverted windows are rejected outright.
+    try {
+      await program.methods
+        .setMaintenanceWindow(new anchor.BN(now + 10), new anchor.BN(now))
+        .accounts(setterAccounts())
+        .rpc();
+      assert.fail("Should have thrown InvalidMaintenanceWindow");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidMaintenanceWindow"),
+        `Expected InvalidMaintenanceWindow error, got: ${err}`
+      );
+    }
+
+    // Clearing the window re-enables spending.
+    await program.methods
+      .setMaintenanceWindow(new anchor.BN(0), new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const after = await spendV2(new anchor.BN(1_000_000), payee.publicKey);
+    assert.strictEqual(after.allowed, true);
+  });
+
+  it("C.6) set_spend_hook — missing or unknown hook denies (fail closed)", async () => {
+    // A random key is not an executable program, so it can never approve.
+    const fakeHook = anchor.web3.Keypair.generate().publicKey;
+    await program.methods
+      .setSpendHook(fakeHook)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const audit = await spendV2(new anchor.BN(1_000_000), payee.publicKey);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 10); // REASON_HOOK_REJECTED
+
+    // The program cannot be its own hook.
+    try {
+      await program.methods
+        .setSpendHook(program.programId)
+        .accounts(setterAccounts())
+        .rpc();
+      assert.fail("Should have thrown InvalidHookProgram");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidHookProgram"),
+        `Expected InvalidHookProgram error, got: ${err}`
+      );
+    }
+
+    await program.methods.setSpendHook(null).accounts(setterAccounts()).rpc();
+    policyVersion += 1;
+  });
+
+  it("E.5) recover_misdirected — sweeps excess lamports from the policy PDA", async () => {
+    const extra = 2_000_000;
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: owner.publicKey,
+          toPubkey: policyPda,
+          lamports: extra,
+        })
+      )
+    );
+    const policyBalBefore = await provider.connection.getBalance(policyPda);
+
+    await program.methods
+      .recoverMisdirected()
+      .accounts({
+        policy: policyPda,
+        target: policyPda,
+        authority: owner.publicKey,
+      })
+      .rpc();
+
+    const policyInfo = await provider.connection.getAccountInfo(policyPda);
+    const rentMin =
+      await provider.connection.getMinimumBalanceForRentExemption(
+        policyInfo.data.length
+      );
+    assert.strictEqual(policyInfo.lamports, rentMin);
+    assert.ok(policyBalBefore - policyInfo.lamports >= extra);
+
+    // The vault's balance is spendable funds and cannot be swept this way.
+    try {
+      await program.methods
+        .recoverMisdirected()
+        .accounts({
+          policy: policyPda,
+          target: vaultPda,
+          authority: owner.publicKey,
+        })
+        .rpc();
+      assert.fail("Should have thrown InvalidRecoveryTarget");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidRecoveryTarget"),
+        `Expected InvalidRecoveryTarget error, got: ${err}`
+      );
+    }
+  });
+
+  it("C.7) set_meter — metered budget derived from an external counter", async () => {
+    // Any account holding a u64 works as a meter; reuse the last audit's `amount`
+    // field (8 discriminator + 32 policy + 8 sequence + 8 ts + 32 recipient = 88).
+    const [meterKey] = auditPda(nextSeq - 1);
+    await program.methods
+      .setMeter(meterKey, 88, new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const metered = (meter) =>
+      program.methods
+        .spendIntentMetered(new anchor.BN(1_000))
+        .accounts({
+          auditEvent: auditPda(nextSeq)[0],
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee.publicKey,
+          meter,
+          caller: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    // Zero lamports per unit → zero budget, whatever the counter says.
+    const [auditKey] = auditPda(nextSeq);
+    await metered(meterKey);
+    nextSeq++;
+    const audit = await program.account.auditEvent.fetch(auditKey);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 2); // REASON_BUDGET_EXCEEDED
+
+    // A different meter account is rejected.
+    try {
+      await metered(vaultPda);
+      assert.fail("Should have thrown MeterMismatch");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("MeterMismatch"),
+        `Expected MeterMismatch error, got: ${err}`
+      );
+    }
+
+    await program.methods
+      .setMeter(null, 0, new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.8) set_first_spend_approval — agent's first spend needs authority co-sign", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const agentKp = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      agentKp.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+
+    await program.methods
+      .setPolicy(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        agentKp.publicKey
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setFirstSpendApproval(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const agentSpend = (approver) =>
+      program.methods
+        .spendIntent(new anchor.BN(1_000_000))
+        .accounts({
+          auditEvent: auditPda(nextSeq)[0],
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee.publicKey,
+          caller: agentKp.publicKey,
+          systemProgram: SystemProgram.programId,
+          approver,
+        })
+        .signers([agentKp])
+        .rpc();
+
+    try {
+      await agentSpend(null);
+      assert.fail("Should have thrown FirstSpendApprovalRequired");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("FirstSpendApprovalRequired"),
+        `Expected FirstSpendApprovalRequired error, got: ${err}`
+      );
+    }
+
+    // Provider wallet (the authority) co-signs the first spend.
+    await agentSpend(owner.publicKey);
+    nextSeq++;
+    let policy = await program.account.policy.fetch(policyPda);
+    assert.strictEqual(policy.hasSpentOnce, true);
+
+    // Afterwards the agent spends on its own.
+    await agentSpend(null);
+    nextSeq++;
+
+    await program.methods
+      .setFirstSpendApproval(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.9) set_one_spend_per_slot — second spend in the same slot denied", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        before.agent
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setOneSpendPerSlot(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    // Two spends in one transaction always share a slot.
+    const payee = anchor.web3.Keypair.generate();
+    const [firstKey] = auditPda(nextSeq);
+    const [secondKey] = auditPda(nextSeq + 1);
+    const ix = (auditEvent) =>
+      program.methods
+        .spendIntent(new anchor.BN(1_000_000))
+        .accounts({
+          auditEvent,
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee.publicKey,
+          caller: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+    const tx = new anchor.web3.Transaction().add(
+      await ix(firstKey),
+      await ix(secondKey)
+    );
+    await provider.sendAndConfirm(tx);
+    nextSeq += 2;
+
+    const first = await program.account.auditEvent.fetch(firstKey);
+    const second = await program.account.auditEvent.fetch(secondKey);
+    assert.strictEqual(first.allowed, true);
+    assert.strictEqual(second.allowed, false);
+    assert.strictEqual(second.reasonCode, 11); // REASON_SLOT_ALREADY_SPENT
+
+    await program.methods
+      .setOneSpendPerSlot(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.10) set_environment_tag — audit events filterable by tag", async () => {
+    const TAG = 7;
+    await program.methods
+      .setEnvironmentTag(TAG)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const audit = await spendV2(new anchor.BN(1_000_000), payee.publicKey);
+    assert.strictEqual(audit.environmentTag, TAG);
+
+    const tagged = await program.account.auditEvent.all([
+      { memcmp: { offset: 8, bytes: policyPda.toBase58() } },
+      { memcmp: { offset: 101, bytes: anchor.utils.bytes.bs58.encode([TAG]) } },
+    ]);
+    assert.strictEqual(tagged.length, 1);
+    assert.strictEqual(tagged[0].account.sequence.toNumber(), nextSeq - 1);
+
+    await program.methods
+      .setEnvironmentTag(0)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.11) set_fixed_amounts — only whitelisted amounts may be spent", async () => {
+    const [fixedAmountsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("fixed_amounts"), policyPda.toBuffer()],
+      program.programId
+    );
+    const FEE = new anchor.BN(1_000_000);
+    await program.methods
+      .setFixedAmounts([FEE])
+      .accounts({
+        fixedAmounts: fixedAmountsPda,
+        ...setterAccounts(),
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const extra = { fixedAmounts: fixedAmountsPda };
+    let audit = await spendV2(FEE.addn(1), payee.publicKey, extra);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 12); // REASON_AMOUNT_NOT_ALLOWED
+
+    audit = await spendV2(FEE, payee.publicKey, extra);
+    assert.notStrictEqual(audit.reasonCode, 12);
+
+    // Omitting the whitelist account fails closed.
+    audit = await spendV2(FEE, payee.publicKey);
+    assert.strictEqual(audit.reasonCode, 12);
+
+    await program.methods
+      .setFixedAmounts([])
+      .accounts({
+        fixedAmounts: fixedAmountsPda,
+        ...setterAccounts(),
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("D.6) spend_intent_v2 — recipient tracker optional without per-recipient limits", async () => {
+    const payee = anchor.web3.Keypair.generate();
+    const [recipientSpendKey] = recipientSpendPda(payee.publicKey);
+
+    // No per-recipient limit is on: the tracker can be omitted and is not created.
+    const audit = await spendV2(new anchor.BN(1_000_000), payee.publicKey, {
+      recipientSpend: null,
+    });
+    assert.notStrictEqual(audit.reasonCode, 7);
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(recipientSpendKey),
+      null
+    );
+
+    // With a count limit on, it is required again.
+    await program.methods
+      .setRecipientCountLimit(1)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    try {
+      await spendV2(new anchor.BN(1_000_000), payee.publicKey, {
+        recipientSpend: null,
+      });
+      assert.fail("Should have thrown RecipientSpendRequired");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("RecipientSpendRequired"),
+        `Expected RecipientSpendRequired error, got: ${err}`
+      );
+    }
+    await program.methods
+      .setRecipientCountLimit(0)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.12) set_daily_budget_with_reason — emits BudgetChanged", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const reason = Buffer.alloc(32);
+    reason.write("quarterly-review");
+
+    let listener;
+    const event = new Promise((resolve) => {
+      listener = program.addEventListener("budgetChanged", resolve);
+    });
+    const bumped = before.dailyBudgetLamports.addn(1);
+    await program.methods
+      .setDailyBudgetWithReason(bumped, [...reason])
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    const ev = await event;
+    await program.removeEventListener(listener);
+
+    assert.ok(ev.old.eq(before.dailyBudgetLamports));
+    assert.ok(ev.new.eq(bumped));
+    assert.deepStrictEqual(Buffer.from(ev.reason), reason);
+    assert.ok(ev.by.equals(owner.publicKey));
+
+    await program.methods
+      .setDailyBudgetWithReason(before.dailyBudgetLamports, [...reason])
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.13) set_per_recipient_cap_bps — cap scales with the daily budget", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    // Fresh budget headroom of 10M; 10% → 1M lamports per recipient.
+    const budget = before.spentTodayLamports.add(new anchor.BN(10_000_000));
+    await program.methods
+      .setPolicy(budget, 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPerRecipientCapBps(1_000)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const capLamports = budget.muln(1_000).divn(10_000);
+    const over = await spendV2(capLamports.addn(1), payee.publicKey);
+    assert.strictEqual(over.allowed, false);
+    assert.strictEqual(over.reasonCode, 7); // REASON_RECIPIENT_CAP_EXCEEDED
+
+    try {
+      await program.methods
+        .setPerRecipientCapBps(10_001)
+        .accounts(setterAccounts())
+        .rpc();
+      assert.fail("Should have thrown InvalidBps");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidBps"),
+        `Expected InvalidBps error, got: ${err}`
+      );
+    }
+
+    await program.methods
+      .setPerRecipientCapBps(0)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("D.7) accrue_and_claim — recipient pulls a streaming allowance", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        before.agent
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      payee.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const [allowancePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("allowance"), policyPda.toBuffer(), payee.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const CEILING = new anchor.BN(2_000_000);
+    await program.methods
+      .setAllowance(new anchor.BN(1_000_000), CEILING)
+      .accounts({
+        allowance: allowancePda,
+        ...setterAccounts(),
+        recipient: payee.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await new Promise((r) => setTimeout(r, 2_000));
+
+    const [auditKey] = auditPda(nextSeq);
+    await program.methods
+      .accrueAndClaim()
+      .accounts({
+        auditEvent: auditKey,
+        allowance: allowancePda,
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: payee.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([payee])
+      .rpc();
+    nextSeq++;
+
+    const audit = await program.account.auditEvent.fetch(auditKey);
+    assert.strictEqual(audit.allowed, true);
+    assert.ok(audit.amount.gtn(0) && audit.amount.lte(CEILING));
+    let allowance = await program.account.allowance.fetch(allowancePda);
+    assert.strictEqual(allowance.accrued.toNumber(), 0);
+
+    // Frozen: the claim is denied but accrual continues.
+    await program.methods
+      .setAllowanceFrozen(true)
+      .accounts({
+        allowance: allowancePda,
+        ...setterAccounts(),
+      })
+      .rpc();
+    await new Promise((r) => setTimeout(r, 1_000));
+    const [frozenAuditKey] = auditPda(nextSeq);
+    await program.methods
+      .accrueAndClaim()
+      .accounts({
+        auditEvent: frozenAuditKey,
+        allowance: allowancePda,
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: payee.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([payee])
+      .rpc();
+    nextSeq++;
+    const frozenAudit = await program.account.auditEvent.fetch(frozenAuditKey);
+    assert.strictEqual(frozenAudit.allowed, false);
+    assert.strictEqual(frozenAudit.reasonCode, 15); // REASON_ALLOWANCE_FROZEN
+    allowance = await program.account.allowance.fetch(allowancePda);
+    assert.ok(allowance.accrued.gtn(0));
+
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("D.7b) accrue_and_claim — a claim over the weekly budget is denied", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    await program.methods
+      .deposit(new anchor.BN(5_000_000))
+      .accounts({
+        vault: newVault,
+        funder: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    const payee = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      payee.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const [allowancePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("allowance"), newPolicy.toBuffer(), payee.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .setAllowance(new anchor.BN(1_000_000), new anchor.BN(2_000_000))
+      .accounts({
+        allowance: allowancePda,
+        ...setterFresh,
+        recipient: payee.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .setWeeklyBudget(new anchor.BN(1))
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await new Promise((r) => setTimeout(r, 2_000));
+
+    const claim = async () => {
+      const { nextSequence } = await program.account.policy.fetch(newPolicy);
+      const [auditEvent] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          nextSequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      await program.methods
+        .accrueAndClaim()
+        .accounts({
+          auditEvent,
+          allowance: allowancePda,
+          policy: newPolicy,
+          vault: newVault,
+          recipient: payee.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payee])
+        .rpc();
+      return program.account.auditEvent.fetch(auditEvent);
+    };
+
+    const denied = await claim();
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 22); // REASON_WEEKLY_EXCEEDED
+    let allowance = await program.account.allowance.fetch(allowancePda);
+    assert.ok(allowance.accrued.gtn(0));
+    let policy = await program.account.policy.fetch(newPolicy);
+    assert.ok(policy.spentThisWeekLamports.eqn(0));
+
+    await program.methods
+      .setWeeklyBudget(new anchor.BN(0))
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    const allowed = await claim();
+    assert.strictEqual(allowed.allowed, true);
+    allowance = await program.account.allowance.fetch(allowancePda);
+    assert.strictEqual(allowance.accrued.toNumber(), 0);
+  });
+
+  it("C.16) set_agent_requires_registered_recipient — agent limited to authority-registered payees", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const agentKp = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      agentKp.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    await program.methods
+      .setPolicy(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        agentKp.publicKey
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setAgentRequiresRegisteredRecipient(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const [recipientAllowPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("recipient_allow"),
+        policyPda.toBuffer(),
+        payee.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const register = (caller, signers) =>
+      program.methods
+        .registerRecipient()
+        .accounts({
+          recipientAllow: recipientAllowPda,
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee.publicKey,
+          caller,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(signers)
+        .rpc();
+    const agentSpend = async () => {
+      const [auditKey] = auditPda(nextSeq);
+      await program.methods
+        .spendIntent(new anchor.BN(1_000_000))
+        .accounts({
+          auditEvent: auditKey,
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee.publicKey,
+          caller: agentKp.publicKey,
+          systemProgram: SystemProgram.programId,
+          recipientAllow: recipientAllowPda,
+        })
+        .signers([agentKp])
+        .rpc();
+      nextSeq++;
+      return program.account.auditEvent.fetch(auditKey);
+    };
+
+    // A payee the agent registered itself does not count.
+    await register(agentKp.publicKey, [agentKp]);
+    let audit = await agentSpend();
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 13); // REASON_RECIPIENT_NOT_REGISTERED
+
+    // Once the authority registers it, the agent may pay it.
+    await register(owner.publicKey, []);
+    const allow = await program.account.recipientAllow.fetch(recipientAllowPda);
+    assert.ok(allow.registeredBy.equals(owner.publicKey));
+    audit = await agentSpend();
+    assert.notStrictEqual(audit.reasonCode, 13);
+
+    await program.methods
+      .setAgentRequiresRegisteredRecipient(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.17) set_monthly_budget — spends beyond the month's budget denied", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        before.agent
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    // Leave exactly 1M lamports in this month's budget.
+    const monthly = before.spentThisMonthLamports.add(new anchor.BN(1_000_000));
+    await program.methods
+      .setMonthlyBudget(monthly)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const audit = await spendV2(new anchor.BN(1_000_001), payee.publicKey);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 14); // REASON_MONTHLY_BUDGET_EXCEEDED
+
+    await program.methods
+      .setMonthlyBudget(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.19) set_require_recipient_signature — recipient must co-sign", async () => {
+    await program.methods
+      .setRequireRecipientSignature(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    try {
+      await spendV2(new anchor.BN(1_000_000), payee.publicKey);
+      assert.fail("Should have thrown RecipientSignatureRequired");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("RecipientSignatureRequired"),
+        `Expected RecipientSignatureRequired error, got: ${err}`
+      );
+    }
+
+    // Mark the recipient as a signer and have it sign.
+    const [auditKey] = auditPda(nextSeq);
+    const ix = await program.methods
+      .spendIntentV2(new anchor.BN(1_000_000))
+      .accounts({
+        auditEvent: auditKey,
+        recipientSpend: recipientSpendPda(payee.publicKey)[0],
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: payee.publicKey,
+        caller: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .instruction();
+    ix.keys.find((k) => k.pubkey.equals(payee.publicKey)).isSigner = true;
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(ix), [
+      payee,
+    ]);
+    nextSeq++;
+    const audit = await program.account.auditEvent.fetch(auditKey);
+    assert.ok(audit.recipient.equals(payee.publicKey));
+
+    await program.methods
+      .setRequireRecipientSignature(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.20) set_epoch_anchor — moving the anchor starts a new daily window", async () => {
+    const summary = async () =>
+      (
+        await program.methods
+          .getPoliciesSummary()
+          .remainingAccounts([
+            { pubkey: policyPda, isWritable: false, isSigner: false },
+          ])
+          .view()
+      )[0];
+    const before = await program.account.policy.fetch(policyPda);
+
+    // Anchoring to 10s ago puts "now" in window 0, not the stored day index.
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .setEpochAnchor(new anchor.BN(now - 10))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    let s = await summary();
+    assert.ok(s.remainingTodayLamports.eq(before.dailyBudgetLamports));
+
+    await program.methods
+      .setEpochAnchor(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    s = await summary();
+    assert.ok(
+      s.remainingTodayLamports.eq(
+        before.dailyBudgetLamports.sub(before.spentTodayLamports)
+      )
+    );
+  });
+
+  it("K) record_refund — returned lamports credit policy and recipient counters", async () => {
+    await program.methods
+      .setRecipientCountLimit(10)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      payee.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const [recipientSpendKey] = recipientSpendPda(payee.publicKey);
+
+    const paid = new anchor.BN(1_000_000);
+    const audit = await spendV2(paid, payee.publicKey);
+    assert.strictEqual(audit.allowed, true);
+
+    const policyBefore = await program.account.policy.fetch(policyPda);
+    const refund = new anchor.BN(400_000);
+    await program.methods
+      .recordRefund(refund)
+      .accounts({
+        policy: policyPda,
+        vault: vaultPda,
+        recipientSpend: recipientSpendKey,
+        refunder: payee.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([payee])
+      .rpc();
+
+    const policyAfter = await program.account.policy.fetch(policyPda);
+    assert.ok(
+      policyAfter.spentTodayLamports.eq(
+        policyBefore.spentTodayLamports.sub(refund)
+      )
+    );
+    const rs = await program.account.recipientSpend.fetch(recipientSpendKey);
+    assert.ok(rs.spentTodayLamports.eq(paid.sub(refund)));
+
+    await program.methods
+      .setRecipientCountLimit(0)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.22) set_agent_reserve — agents cannot dip into the reserve", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const agentKp = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      agentKp.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    await program.methods
+      .setPolicy(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        agentKp.publicKey
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    // Reserve the whole vault balance.
+    const vaultBal = await provider.connection.getBalance(vaultPda);
+    await program.methods
+      .setAgentReserve(new anchor.BN(vaultBal))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const [auditKey] = auditPda(nextSeq);
+    await program.methods
+      .spendIntent(new anchor.BN(1_000_000))
+      .accounts({
+        auditEvent: auditKey,
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: payee.publicKey,
+        caller: agentKp.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([agentKp])
+      .rpc();
+    nextSeq++;
+    let audit = await program.account.auditEvent.fetch(auditKey);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 16); // REASON_AGENT_RESERVE_VIOLATION
+
+    // The authority is not bound by the reserve.
+    audit = await spendV2(new anchor.BN(1_000_000), payee.publicKey);
+    assert.notStrictEqual(audit.reasonCode, 16);
+
+    await program.methods
+      .setAgentReserve(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.23) set_agent_weekly_budget — agent capped per week, authority not", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const agentKp = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      agentKp.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    await program.methods
+      .setPolicy(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        agentKp.publicKey
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    // Room for one more 1M agent spend this week, not two.
+    await program.methods
+      .setAgentWeeklyBudget(
+        before.agentSpentThisWeekLamports.add(new anchor.BN(1_500_000))
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const agentSpend = async () => {
+      const [auditKey] = auditPda(nextSeq);
+      await program.methods
+        .spendIntent(new anchor.BN(1_000_000))
+        .accounts({
+          auditEvent: auditKey,
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee.publicKey,
+          caller: agentKp.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([agentKp])
+        .rpc();
+      nextSeq++;
+      return program.account.auditEvent.fetch(auditKey);
+    };
+
+    let audit = await agentSpend();
+    assert.strictEqual(audit.allowed, true);
+    audit = await agentSpend();
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 17); // REASON_AGENT_WEEKLY_BUDGET
+
+    // Authority spends neither count toward nor are limited by it.
+    audit = await spendV2(new anchor.BN(1_000_000), payee.publicKey);
+    assert.strictEqual(audit.allowed, true);
+
+    await program.methods
+      .setAgentWeeklyBudget(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.24) set_sequence_on_allowed_only — denials keep the sequence", async () => {
+    await program.methods
+      .setSequenceOnAllowedOnly(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const before = await program.account.policy.fetch(policyPda);
+    const [auditKey] = auditPda(nextSeq);
+    const recorded = [];
+    const listener = program.addEventListener("spendRecorded", (ev) =>
+      recorded.push(ev)
+    );
+    await program.methods
+      .spendIntentV2(new anchor.BN(0)) // denied: INVALID_AMOUNT
+      .accounts({
+        auditEvent: auditKey,
+        recipientSpend: recipientSpendPda(payee.publicKey)[0],
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: payee.publicKey,
+        caller: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await new Promise((r) => setTimeout(r, 1_000));
+    await program.removeEventListener(listener);
+
+    let policy = await program.account.policy.fetch(policyPda);
+    assert.ok(policy.nextSequence.eq(before.nextSequence));
+    assert.ok(policy.denialSequence.eq(before.denialSequence.addn(1)));
+    assert.strictEqual(await provider.connection.getAccountInfo(auditKey), null);
+    // The event carries the denial number, not the next payment's sequence.
+    assert.strictEqual(recorded.length, 1);
+    assert.strictEqual(recorded[0].outOfSequence, true);
+    assert.ok(recorded[0].sequence.eq(before.denialSequence));
+
+    // Turn it off again; the next attempt reuses the same sequence PDA.
+    await program.methods
+      .setSequenceOnAllowedOnly(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    const audit = await spendV2(new anchor.BN(0), payee.publicKey);
+    assert.ok(audit.sequence.eq(before.nextSequence));
+    policy = await program.account.policy.fetch(policyPda);
+    assert.ok(policy.nextSequence.eq(before.nextSequence.addn(1)));
+  });
+
+  it("C.25) set_linear_release — the full budget is never released mid-window", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const amount = new anchor.BN(1_000_000);
+    await program.methods
+      .setPolicy(before.spentTodayLamports.add(amount), 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setLinearRelease(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    // Within the daily budget, but spending all of it needs the window's end.
+    const payee = anchor.web3.Keypair.generate();
+    const audit = await spendV2(amount, payee.publicKey);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 18); // REASON_RELEASE_SCHEDULE
+
+    await program.methods
+      .setLinearRelease(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.26) rotate_agent_secure — old agent locked out, AgentRotated emitted", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const oldAgent = anchor.web3.Keypair.generate();
+    const newAgent = anchor.web3.Keypair.generate();
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, oldAgent.publicKey)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    const [oldAgentEntry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("agent"), policyPda.toBuffer(), oldAgent.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .addAgent(new anchor.BN(1_000_000))
+      .accounts({
+        agentEntry: oldAgentEntry,
+        policy: policyPda,
+        vault: vaultPda,
+        agent: oldAgent.publicKey,
+        authority: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    let listener;
+    const event = new Promise((resolve) => {
+      listener = program.addEventListener("agentRotated", resolve);
+    });
+    await program.methods
+      .rotateAgentSecure(newAgent.publicKey)
+      .accounts({ ...setterAccounts(), oldAgentEntry })
+      .rpc();
+    policyVersion += 1;
+    const ev = await event;
+    await program.removeEventListener(listener);
+
+    assert.ok(ev.oldAgent.equals(oldAgent.publicKey));
+    assert.ok(ev.newAgent.equals(newAgent.publicKey));
+    assert.strictEqual(ev.policyVersion, policyVersion);
+    const policy = await program.account.policy.fetch(policyPda);
+    assert.ok(policy.agent.equals(newAgent.publicKey));
+    // The old key's add_agent entry is gone with it.
+    assert.strictEqual(await program.account.agent.fetchNullable(oldAgentEntry), null);
+
+    // The entry address is required even when no entry lives there.
+    try {
+      await program.methods
+        .rotateAgentSecure(before.agent)
+        .accounts(setterAccounts())
+        .rpc();
+      assert.fail("Should have thrown OldAgentEntryMismatch");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("OldAgentEntryMismatch"),
+        `Expected OldAgentEntryMismatch error, got: ${err}`
+      );
+    }
+    const [newAgentEntry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("agent"), policyPda.toBuffer(), newAgent.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .rotateAgentSecure(before.agent)
+      .accounts({ ...setterAccounts(), oldAgentEntry: newAgentEntry })
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("D.9) DailySummary — emitted for the closed day on rollover", async () => {
+    const payee = anchor.web3.Keypair.generate();
+    // Make sure the current day has at least one attempt to summarize.
+    await spendV2(new anchor.BN(0), payee.publicKey);
+    const before = await program.account.policy.fetch(policyPda);
+
+    // Anchoring to 10s ago moves "now" into window 0, rolling the day over.
+    const now = Math.floor(Date.now() / 1000);
+    await program.methods
+      .setEpochAnchor(new anchor.BN(now - 10))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    let listener;
+    const event = new Promise((resolve) => {
+      listener = program.addEventListener("dailySummary", resolve);
+    });
+    await spendV2(new anchor.BN(0), payee.publicKey);
+    const ev = await event;
+    await program.removeEventListener(listener);
+
+    assert.ok(ev.dayIndex.eq(before.dayIndex));
+    assert.ok(ev.totalSpent.eq(before.spentTodayLamports));
+    assert.strictEqual(ev.allowedCount, before.allowedCountToday);
+    assert.strictEqual(ev.deniedCount, before.deniedCountToday);
+    const policy = await program.account.policy.fetch(policyPda);
+    assert.strictEqual(policy.allowedCountToday, 0);
+    assert.strictEqual(policy.deniedCountToday, 1);
+
+    await program.methods
+      .setEpochAnchor(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("D.5) spend_to_timelock — claimable only after unlock, cancellable before", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.spentTodayLamports.add(new anchor.BN(10_000_000)), 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      payee.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const amount = new anchor.BN(1_000_000);
+
+    const timelockPda = (seq) => {
+      const seqBuf = Buffer.alloc(8);
+      seqBuf.writeBigUInt64LE(BigInt(seq));
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("timelock"), policyPda.toBuffer(), seqBuf],
+        program.programId
+      )[0];
+    };
+    const lock = async (unlockTs) => {
+      const seq = nextSeq;
+      await program.methods
+        .spendToTimelock(amount, new anchor.BN(unlockTs))
+        .accounts({
+          auditEvent: auditPda(seq)[0],
+          timelock: timelockPda(seq),
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee.publicKey,
+          caller: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      nextSeq++;
+      return timelockPda(seq);
+    };
+    const claim = async (timelock) => {
+      const [auditPdaKey] = auditPda(nextSeq);
+      await program.methods
+        .claimTimelock()
+        .accounts({
+          auditEvent: auditPdaKey,
+          timelock,
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee.publicKey,
+          payer: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([payee])
+        .rpc();
+      nextSeq++;
+      return program.account.auditEvent.fetch(auditPdaKey);
+    };
+
+    const now = Math.floor(Date.now() / 1000);
+    const matured = await lock(now + 3);
+    const cancelled = await lock(now + 3_600);
+    let policy = await program.account.policy.fetch(policyPda);
+    assert.ok(policy.spentTodayLamports.eq(before.spentTodayLamports.add(amount.muln(2))));
+    assert.ok(
+      policy.timelockedLamports.eq(before.timelockedLamports.add(amount.muln(2)))
+    );
+
+    try {
+      await claim(matured);
+      assert.fail("Should have thrown TimelockNotMatured");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("TimelockNotMatured"),
+        `Expected TimelockNotMatured, got: ${err}`
+      );
+    }
+
+    const [cancelAudit] = auditPda(nextSeq);
+    await program.methods
+      .cancelTimelock()
+      .accounts({
+        auditEvent: cancelAudit,
+        timelock: cancelled,
+        policy: policyPda,
+        authority: owner.publicKey,
+        payer: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    nextSeq++;
+    assert.strictEqual(await provider.connection.getAccountInfo(cancelled), null);
+    const cancelEvent = await program.account.auditEvent.fetch(cancelAudit);
+    assert.strictEqual(cancelEvent.reasonCode, 36); // REASON_TIMELOCK_CANCELLED
+    assert.ok(cancelEvent.amount.eq(amount));
+
+    // The escrow is held back from ordinary spends...
+    policy = await program.account.policy.fetch(policyPda);
+    assert.ok(policy.timelockedLamports.eq(before.timelockedLamports.add(amount)));
+    const spendable = await program.methods
+      .spendableBalance()
+      .accounts({ policy: policyPda, vault: vaultPda })
+      .view();
+    const balance = await provider.connection.getBalance(vaultPda);
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(41); // Vault::SIZE
+    const floor = Math.max(rent, policy.minReserveLamports.toNumber());
+    assert.strictEqual(
+      spendable.toNumber(),
+      balance - policy.timelockedLamports.toNumber() - floor
+    );
+
+    // ...and a paused policy denies the claim but keeps it open.
+    await new Promise((r) => setTimeout(r, 4_000));
+    const pause = (paused) =>
+      program.methods
+        .setPolicyAdvanced(
+          policy.dailyBudgetLamports,
+          policy.cooldownSeconds,
+          policy.agent,
+          paused,
+          policy.allowlistEnabled,
+          policy.allowedRecipient,
+          policy.perRecipientDailyCapLamports,
+          policy.dayOffsetSeconds
+        )
+        .accounts(setterAccounts())
+        .rpc();
+    await pause(true);
+    policyVersion += 1;
+    const paused = await claim(matured);
+    assert.strictEqual(paused.allowed, false);
+    assert.strictEqual(paused.reasonCode, 5); // REASON_PAUSED
+    assert.notStrictEqual(await provider.connection.getAccountInfo(matured), null);
+    await pause(false);
+    policyVersion += 1;
+
+    const payeeBefore = await provider.connection.getBalance(payee.publicKey);
+    const claimed = await claim(matured);
+    assert.strictEqual(claimed.allowed, true);
+    assert.ok(claimed.recipient.equals(payee.publicKey));
+    const payeeAfter = await provider.connection.getBalance(payee.publicKey);
+    // The payee signs, so its balance also pays the fee and the audit's rent.
+    assert.ok(payeeAfter - payeeBefore > amount.toNumber() - 2_000_000);
+    assert.strictEqual(await provider.connection.getAccountInfo(matured), null);
+    policy = await program.account.policy.fetch(policyPda);
+    assert.ok(policy.timelockedLamports.eq(before.timelockedLamports));
+
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.27) set_require_owner_exists — owner account must be passed and funded", async () => {
+    await program.methods
+      .setRequireOwnerExists(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    try {
+      await spendV2(new anchor.BN(0), payee.publicKey);
+      assert.fail("Should have thrown OwnerAccountRequired");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("OwnerAccountRequired"),
+        `Expected OwnerAccountRequired, got: ${err}`
+      );
+    }
+
+    // With the (funded) owner passed, the attempt goes through to a decision.
+    const audit = await spendV2(new anchor.BN(0), payee.publicKey, {
+      owner: owner.publicKey,
+    });
+    assert.notStrictEqual(audit.reasonCode, 19); // REASON_OWNER_GONE
+
+    await program.methods
+      .setRequireOwnerExists(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.28) set_auto_close_empty_tracker — full refund closes the tracker", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.spentTodayLamports.add(new anchor.BN(10_000_000)), 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setRecipientCountLimit(10)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setAutoCloseEmptyTracker(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      payee.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const [recipientSpendKey] = recipientSpendPda(payee.publicKey);
+    const paid = new anchor.BN(1_000_000);
+    const audit = await spendV2(paid, payee.publicKey);
+    assert.strictEqual(audit.allowed, true);
+
+    await program.methods
+      .recordRefund(paid)
+      .accounts({
+        policy: policyPda,
+        vault: vaultPda,
+        recipientSpend: recipientSpendKey,
+        refunder: payee.publicKey,
+        systemProgram: SystemProgram.programId,
+        authority: owner.publicKey,
+      })
+      .signers([payee])
+      .rpc();
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(recipientSpendKey),
+      null
+    );
+
+    await program.methods
+      .setAutoCloseEmptyTracker(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setRecipientCountLimit(0)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.29) set_recipient_min_interval — denied attempts restart the interval", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.spentTodayLamports.add(new anchor.BN(10_000_000)), 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setRecipientMinInterval(3_600, true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    // A denied probe still stamps the tracker...
+    let audit = await spendV2(new anchor.BN(0), payee.publicKey);
+    assert.strictEqual(audit.reasonCode, 4); // REASON_INVALID_AMOUNT
+    const rs = await program.account.recipientSpend.fetch(
+      recipientSpendPda(payee.publicKey)[0]
+    );
+    assert.ok(rs.lastSpendTs.gtn(0));
+
+    // ...so a valid spend right after is throttled.
+    audit = await spendV2(new anchor.BN(1_000_000), payee.publicKey);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 20); // REASON_RECIPIENT_COOLDOWN
+
+    await program.methods
+      .setRecipientMinInterval(0, false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.30) set_budget_scale — amounts are transferred in 10^scale lamports", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.spentTodayLamports.add(new anchor.BN(10_000_000)), 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setBudgetScale(3)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const audit = await spendV2(new anchor.BN(1_000), payee.publicKey);
+    assert.strictEqual(audit.allowed, true);
+    assert.ok(audit.amount.eq(new anchor.BN(1_000))); // recorded in policy units
+    assert.strictEqual(
+      await provider.connection.getBalance(payee.publicKey),
+      1_000_000
+    );
+
+    // A timelock committed at scale 3 still pays 10^3 lamports per unit
+    // after the scale changes.
+    const claimant = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      claimant.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const seqBuf = Buffer.alloc(8);
+    seqBuf.writeBigUInt64LE(BigInt(nextSeq));
+    const [timelock] = PublicKey.findProgramAddressSync(
+      [Buffer.from("timelock"), policyPda.toBuffer(), seqBuf],
+      program.programId
+    );
+    await program.methods
+      .spendToTimelock(
+        new anchor.BN(1),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 2)
+      )
+      .accounts({
+        auditEvent: auditPda(nextSeq)[0],
+        timelock,
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: claimant.publicKey,
+        caller: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    nextSeq++;
+    assert.strictEqual(
+      (await program.account.timelockedClaim.fetch(timelock)).budgetScale,
+      3
+    );
+    await program.methods
+      .setBudgetScale(0)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await new Promise((r) => setTimeout(r, 3_000));
+    const vaultBefore = await provider.connection.getBalance(vaultPda);
+    const [claimAudit] = auditPda(nextSeq);
+    await program.methods
+      .claimTimelock()
+      .accounts({
+        auditEvent: claimAudit,
+        timelock,
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: claimant.publicKey,
+        payer: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([claimant])
+      .rpc();
+    nextSeq++;
+    assert.strictEqual((await program.account.auditEvent.fetch(claimAudit)).allowed, true);
+    assert.strictEqual(
+      vaultBefore - (await provider.connection.getBalance(vaultPda)),
+      1_000
+    );
+    const after = await program.account.policy.fetch(policyPda);
+    assert.ok(after.timelockedLamports.eq(before.timelockedLamports));
+
+    try {
+      await program.methods
+        .setBudgetScale(20)
+        .accounts(setterAccounts())
+        .rpc();
+      assert.fail("Should have thrown InvalidBudgetScale");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidBudgetScale"),
+        `Expected InvalidBudgetScale, got: ${err}`
+      );
+    }
+
+    await program.methods
+      .setBudgetScale(0)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.31) add/remove_allowed_recipient — multi-recipient allowlist", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const payeeA = anchor.web3.Keypair.generate().publicKey;
+    const payeeB = anchor.web3.Keypair.generate().publicKey;
+    const allowedPda = (pk) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("allowed"), policyPda.toBuffer(), pk.toBuffer()],
+        program.programId
+      )[0];
+
+    await program.methods
+      .setPolicyAdvanced(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        before.agent,
+        false,
+        true, // allowlist enabled, no legacy single recipient
+        null,
+        before.perRecipientDailyCapLamports,
+        before.dayOffsetSeconds
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    for (const payee of [payeeA, payeeB]) {
+      await program.methods
+        .addAllowedRecipient()
+        .accounts({
+          allowedRecipient: allowedPda(payee),
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee,
+          authority: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    for (const payee of [payeeA, payeeB]) {
+      const audit = await spendV2(new anchor.BN(1_000_000), payee, {
+        allowedRecipient: allowedPda(payee),
+      });
+      assert.strictEqual(audit.allowed, true);
+    }
+
+    await program.methods
+      .removeAllowedRecipient()
+      .accounts({
+        allowedRecipient: allowedPda(payeeB),
+        policy: policyPda,
+        recipient: payeeB,
+        authority: owner.publicKey,
+      })
+      .rpc();
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(allowedPda(payeeB)),
+      null
+    );
+
+    // Without an entry (and no legacy recipient) the payee is not allowed.
+    const denied = await spendV2(new anchor.BN(1_000_000), payeeB);
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 6); // REASON_RECIPIENT_NOT_ALLOWED
+
+    await program.methods
+      .setPolicyAdvanced(
+        before.dailyBudgetLamports,
+        before.cooldownSeconds,
+        before.agent,
+        before.paused,
+        before.allowlistEnabled,
+        before.allowedRecipient,
+        before.perRecipientDailyCapLamports,
+        before.dayOffsetSeconds
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.33) lock_policy — configuration becomes permanently immutable", async () => {
+    // Lock a throwaway policy so the shared one stays configurable.
+    const { newOwner, newPolicy, newVault } = await freshPolicy();
+    const accounts = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    await program.methods.lockPolicy().accounts(accounts).signers([newOwner]).rpc();
+
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.locked, true);
+    assert.strictEqual(policy.policyVersion, 2);
+
+    for (const call of [
+      () => program.methods.setPolicy(new anchor.BN(1), 0, null),
+      () => program.methods.lockPolicy(),
+    ]) {
+      try {
+        await call().accounts(accounts).signers([newOwner]).rpc();
+        assert.fail("Should have thrown PolicyLocked");
+      } catch (err) {
+        assert.ok(
+          err.toString().includes("PolicyLocked"),
+          `Expected PolicyLocked, got: ${err}`
+        );
+      }
+    }
+  });
+
+  it("D.8) spend_intent_spl — token transfer under the mint's TokenBudget", async () => {
+    const { Keypair, Transaction, TransactionInstruction, SYSVAR_RENT_PUBKEY } =
+      anchor.web3;
+    const TOKEN_PROGRAM_ID = new PublicKey(
+      "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+    );
+    const ATA_PROGRAM_ID = new PublicKey(
+      "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"
+    );
+    const ata = (walletPk, mintPk) =>
+      PublicKey.findProgramAddressSync(
+        [walletPk.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), mintPk.toBuffer()],
+        ATA_PROGRAM_ID
+      )[0];
+    const createAta = (walletPk, mintPk) =>
+      new TransactionInstruction({
+        programId: ATA_PROGRAM_ID,
+        keys: [
+          { pubkey: owner.publicKey, isSigner: true, isWritable: true },
+          { pubkey: ata(walletPk, mintPk), isSigner: false, isWritable: true },
+          { pubkey: walletPk, isSigner: false, isWritable: false },
+          { pubkey: mintPk, isSigner: false, isWritable: false },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        ],
+        data: Buffer.from([1]), // CreateIdempotent
+      });
+
+    // Mint with 6 decimals; 1_000_000 base units minted to the vault's ATA.
+    const mint = Keypair.generate();
+    const payee = Keypair.generate().publicKey;
+    const mintRent = await provider.connection.getMinimumBalanceForRentExemption(82);
+    const mintTo = Buffer.alloc(9);
+    mintTo.writeUInt8(7, 0); // MintTo
+    mintTo.writeBigUInt64LE(1_000_000n, 1);
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: owner.publicKey,
+          newAccountPubkey: mint.publicKey,
+          lamports: mintRent,
+          space: 82,
+          programId: TOKEN_PROGRAM_ID,
+        }),
+        new TransactionInstruction({
+          programId: TOKEN_PROGRAM_ID,
+          keys: [
+            { pubkey: mint.publicKey, isSigner: false, isWritable: true },
+            { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
+          ],
+          // InitializeMint: decimals, mint authority, no freeze authority
+          data: Buffer.concat([
+            Buffer.from([0, 6]),
+            owner.publicKey.toBuffer(),
+            Buffer.from([0]),
+            Buffer.alloc(32),
+          ]),
+        }),
+        createAta(vaultPda, mint.publicKey),
+        createAta(payee, mint.publicKey),
+        new TransactionInstruction({
+          programId: TOKEN_PROGRAM_ID,
+          keys: [
+            { pubkey: mint.publicKey, isSigner: false, isWritable: true },
+            {
+              pubkey: ata(vaultPda, mint.publicKey),
+              isSigner: false,
+              isWritable: true,
+            },
+            { pubkey: owner.publicKey, isSigner: true, isWritable: false },
+          ],
+          data: mintTo,
+        })
+      ),
+      [mint]
+    );
+
+    const [tokenBudget] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tokbudget"), policyPda.toBuffer(), mint.publicKey.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .setTokenBudget(new anchor.BN(600_000))
+      .accounts({
+        tokenBudget,
+        policy: policyPda,
+        vault: vaultPda,
+        mint: mint.publicKey,
+        authority: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const spendSpl = async (amount) => {
+      const [auditPdaKey] = auditPda(nextSeq);
+      await program.methods
+        .spendIntentSpl(new anchor.BN(amount))
+        .accounts({
+          auditEvent: auditPdaKey,
+          tokenBudget,
+          policy: policyPda,
+          vault: vaultPda,
+          mint: mint.publicKey,
+          vaultTokenAccount: ata(vaultPda, mint.publicKey),
+          recipient: payee,
+          recipientTokenAccount: ata(payee, mint.publicKey),
+          caller: owner.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      nextSeq++;
+      return program.account.auditEvent.fetch(auditPdaKey);
+    };
+
+    const allowed = await spendSpl(400_000);
+    assert.strictEqual(allowed.allowed, true);
+    assert.ok(allowed.mint.equals(mint.publicKey));
+    const payeeBalance = await provider.connection.getTokenAccountBalance(
+      ata(payee, mint.publicKey)
+    );
+    assert.strictEqual(payeeBalance.value.amount, "400000");
+
+    // 400k + 300k exceeds the 600k token budget; lamport budget is untouched.
+    const denied = await spendSpl(300_000);
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 2); // REASON_BUDGET_EXCEEDED
+    const after = await program.account.policy.fetch(policyPda);
+    assert.ok(after.spentTodayLamports.eq(before.spentTodayLamports));
+
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.35) propose/accept_authority_transfer — two-step handover", async () => {
+    const { newOwner, newPolicy, newVault } = await freshPolicy();
+    const first = anchor.web3.Keypair.generate();
+    const second = anchor.web3.Keypair.generate();
+    const propose = (pk) =>
+      program.methods
+        .proposeAuthorityTransfer(pk)
+        .accounts({
+          policy: newPolicy,
+          vault: newVault,
+          authority: newOwner.publicKey,
+        })
+        .signers([newOwner])
+        .rpc();
+    const accept = (kp) =>
+      program.methods
+        .acceptAuthorityTransfer()
+        .accounts({
+          policy: newPolicy,
+          vault: newVault,
+          newAuthority: kp.publicKey,
+        })
+        .signers([kp])
+        .rpc();
+
+    await propose(first.publicKey);
+    await propose(second.publicKey); // overwrites the first proposal
+    let policy = await program.account.policy.fetch(newPolicy);
+    assert.ok(policy.authority.equals(newOwner.publicKey));
+    assert.ok(policy.pendingAuthority.equals(second.publicKey));
+
+    try {
+      await accept(first);
+      assert.fail("Should have thrown Unauthorized");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("Unauthorized"),
+        `Expected Unauthorized, got: ${err}`
+      );
+    }
+
+    await accept(second);
+    policy = await program.account.policy.fetch(newPolicy);
+    assert.ok(policy.authority.equals(second.publicKey));
+    assert.strictEqual(policy.pendingAuthority, null);
+  });
+
+  it("C.37) set_recipient_unit — v2 amounts must be whole units", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.spentTodayLamports.add(new anchor.BN(10_000_000)), 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const [recipientAllow] = PublicKey.findProgramAddressSync(
+      [Buffer.from("recipient_allow"), policyPda.toBuffer(), payee.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .registerRecipient()
+      .accounts({
+        recipientAllow,
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: payee,
+        caller: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods
+      .setRecipientUnit(new anchor.BN(100_000))
+      .accounts({
+        recipientAllow,
+        policy: policyPda,
+        vault: vaultPda,
+        authority: owner.publicKey,
+      })
+      .rpc();
+
+    const denied = await spendV2(new anchor.BN(1_050_000), payee, { recipientAllow });
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 21); // REASON_BAD_RECIPIENT_UNIT
+
+    const allowed = await spendV2(new anchor.BN(1_000_000), payee, { recipientAllow });
+    assert.strictEqual(allowed.allowed, true);
+
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.38) set_weekly_budget — policy-wide weekly window", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.spentTodayLamports.add(new anchor.BN(10_000_000)), 0, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setWeeklyBudget(before.spentThisWeekLamports.add(new anchor.BN(1_500_000)))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const allowed = await spendV2(new anchor.BN(1_000_000), payee);
+    assert.strictEqual(allowed.allowed, true);
+    const denied = await spendV2(new anchor.BN(1_000_000), payee);
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 22); // REASON_WEEKLY_EXCEEDED
+
+    await program.methods
+      .setWeeklyBudget(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.39) set_block_program_recipients — loader-owned payees denied", async () => {
+    // A loader-owned account stands in for program data.
+    const loaderOwned = anchor.web3.Keypair.generate();
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: owner.publicKey,
+          newAccountPubkey: loaderOwned.publicKey,
+          lamports: await provider.connection.getMinimumBalanceForRentExemption(0),
+          space: 0,
+          programId: new PublicKey("BPFLoader2111111111111111111111111111111111"),
+        })
+      ),
+      [loaderOwned]
+    );
+
+    await program.methods
+      .setBlockProgramRecipients(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const denied = await spendV2(new anchor.BN(1_000_000), loaderOwned.publicKey);
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 23); // REASON_RECIPIENT_IS_PROGRAM
+
+    await program.methods
+      .setBlockProgramRecipients(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("E.5) reassign_policy_vault — policy moves to the authority's vault", async () => {
+    // Hand a fresh policy to a new authority, who then moves it to their vault.
+    const { newOwner, newPolicy, newVault } = await freshPolicy();
+    const successor = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      successor.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    await program.methods
+      .proposeAuthorityTransfer(successor.publicKey)
+      .accounts({ policy: newPolicy, vault: newVault, authority: newOwner.publicKey })
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .acceptAuthorityTransfer()
+      .accounts({
+        policy: newPolicy,
+        vault: newVault,
+        newAuthority: successor.publicKey,
+      })
+      .signers([successor])
+      .rpc();
+
+    const [successorVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), successor.publicKey.toBuffer()],
+      program.programId
+    );
+    const [movedPolicy] = PublicKey.findProgramAddressSync(
+      [Buffer.from("policy"), successorVault.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeVault()
+      .accounts({
+        vault: successorVault,
+        owner: successor.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([successor])
+      .rpc();
+
+    const old = await program.account.policy.fetch(newPolicy);
+    await program.methods
+      .reassignPolicyVault()
+      .accounts({
+        policy: newPolicy,
+        newPolicy: movedPolicy,
+        vault: newVault,
+        newVault: successorVault,
+        authority: successor.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([successor])
+      .rpc();
+
+    const moved = await program.account.policy.fetch(movedPolicy);
+    assert.ok(moved.vault.equals(successorVault));
+    assert.ok(moved.authority.equals(successor.publicKey));
+    assert.ok(moved.dailyBudgetLamports.eq(old.dailyBudgetLamports));
+    assert.strictEqual(moved.policyVersion, old.policyVersion + 1);
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(newPolicy),
+      null
+    );
+  });
+
+  it("C.40) set_min_reserve — no spend may dip below the floor", async () => {
+    const vaultBalance = await provider.connection.getBalance(vaultPda);
+    await program.methods
+      .setMinReserve(new anchor.BN(vaultBalance))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    // Authority spends are held to the floor too.
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const denied = await spendV2(new anchor.BN(1_000_000), payee);
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 24); // REASON_RESERVE_PROTECTED
+    assert.strictEqual(await provider.connection.getBalance(payee), 0);
+
+    await program.methods
+      .setMinReserve(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.41) set_recipient_cap_exclusive — spend exactly at the cap", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    const cap = new anchor.BN(2_000_000);
+    await program.methods
+      .setPolicyAdvanced(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        before.agent,
+        false,
+        false,
+        null,
+        cap,
+        before.dayOffsetSeconds
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setRecipientCapExclusive(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const denied = await spendV2(cap, payee);
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 7); // REASON_RECIPIENT_CAP_EXCEEDED
+
+    await program.methods
+      .setRecipientCapExclusive(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    const allowed = await spendV2(cap, payee);
+    assert.strictEqual(allowed.allowed, true);
+
+    await program.methods
+      .setPolicyAdvanced(
+        before.dailyBudgetLamports,
+        before.cooldownSeconds,
+        before.agent,
+        before.paused,
+        before.allowlistEnabled,
+        before.allowedRecipient,
+        before.perRecipientDailyCapLamports,
+        before.dayOffsetSeconds
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.42) add_agent — extra agent spends under its own sub-limit", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicyAdvanced(
+        before.spentTodayLamports.add(new anchor.BN(10_000_000)),
+        0,
+        before.agent,
+        false,
+        false,
+        null,
+        before.perRecipientDailyCapLamports,
+        before.dayOffsetSeconds
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const extraAgent = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      extraAgent.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const [agentEntry] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("agent"),
+        policyPda.toBuffer(),
+        extraAgent.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    await program.methods
+      .addAgent(new anchor.BN(1_000_000))
+      .accounts({
+        agentEntry,
+        policy: policyPda,
+        vault: vaultPda,
+        agent: extraAgent.publicKey,
+        authority: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const agentSpend = async (amount, accounts = {}) => {
+      const [auditPdaKey] = auditPda(nextSeq);
+      await program.methods
+        .spendIntent(amount)
+        .accounts({
+          auditEvent: auditPdaKey,
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee,
+          caller: extraAgent.publicKey,
+          systemProgram: SystemProgram.programId,
+          agentEntry,
+          ...accounts,
+        })
+        .signers([extraAgent])
+        .rpc();
+      nextSeq++;
+      return program.account.auditEvent.fetch(auditPdaKey);
+    };
+
+    const allowed = await agentSpend(new anchor.BN(600_000));
+    assert.strictEqual(allowed.allowed, true);
+    const over = await agentSpend(new anchor.BN(600_000));
+    assert.strictEqual(over.allowed, false);
+    assert.strictEqual(over.reasonCode, 25); // REASON_AGENT_BUDGET_EXCEEDED
+    assert.strictEqual(allowed.callerIsAgent, true);
+    // Paying itself does not make the agent look like a claiming recipient.
+    const selfPay = await agentSpend(new anchor.BN(0), {
+      recipient: extraAgent.publicKey,
+    });
+    assert.strictEqual(selfPay.callerIsAgent, true);
+
+    await program.methods
+      .removeAgent()
+      .accounts({
+        agentEntry,
+        policy: policyPda,
+        agent: extraAgent.publicKey,
+        authority: owner.publicKey,
+      })
+      .rpc();
+    try {
+      await agentSpend(new anchor.BN(100_000), { agentEntry: null });
+      assert.fail("Should have thrown Unauthorized");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("Unauthorized"),
+        `Expected Unauthorized error, got: ${err}`
+      );
+    }
+
+    await program.methods
+      .setPolicyAdvanced(
+        before.dailyBudgetLamports,
+        before.cooldownSeconds,
+        before.agent,
+        before.paused,
+        before.allowlistEnabled,
+        before.allowedRecipient,
+        before.perRecipientDailyCapLamports,
+        before.dayOffsetSeconds
+      )
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("D.9) deposit_and_spend — deposit stays when the spend is denied", async () => {
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const vaultBefore = await provider.connection.getBalance(vaultPda);
+    const deposit = new anchor.BN(3_000_000);
+    const [auditPdaKey] = auditPda(nextSeq);
+    await program.methods
+      .depositAndSpend(deposit, new anchor.BN(0))
+      .accounts({
+        spend: {
+          auditEvent: auditPdaKey,
+          policy: policyPda,
+          vault: vaultPda,
+          recipient: payee,
+          caller: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        },
+        funder: owner.publicKey,
+      })
+      .rpc();
+    nextSeq++;
+
+    const audit = await program.account.auditEvent.fetch(auditPdaKey);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 4); // REASON_INVALID_AMOUNT
+    assert.strictEqual(
+      await provider.connection.getBalance(vaultPda),
+      vaultBefore + deposit.toNumber()
+    );
+    assert.strictEqual(await provider.connection.getBalance(payee), 0);
+  });
+
+  it("C.1) set_policy — emits PolicyUpdated with the new version", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+
+    let listener;
+    const event = new Promise((resolve) => {
+      listener = program.addEventListener("policyUpdated", resolve);
+    });
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    const ev = await event;
+    await program.removeEventListener(listener);
+
+    assert.ok(ev.vault.equals(vaultPda));
+    assert.ok(ev.policy.equals(policyPda));
+    assert.strictEqual(ev.policyVersion, before.policyVersion + 1);
+    assert.ok(ev.dailyBudgetLamports.eq(before.dailyBudgetLamports));
+    assert.strictEqual(ev.cooldownSeconds, before.cooldownSeconds);
+    assert.strictEqual(ev.paused, before.paused);
+    assert.strictEqual(ev.allowlistEnabled, before.allowlistEnabled);
+    assert.ok(
+      ev.perRecipientDailyCapLamports.eq(before.perRecipientDailyCapLamports)
+    );
+  });
+
+  it("C.44) set_denial_backoff — a denial backs off; attempts during the backoff do not extend it", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const spendAccounts = async () => {
+      const { nextSequence } = await program.account.policy.fetch(newPolicy);
+      const [auditEvent] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          nextSequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      return {
+        auditEvent,
+        policy: newPolicy,
+        vault: newVault,
+        recipient: payee,
+        caller: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      };
+    };
+    const attempt = async (amount) => {
+      const accounts = await spendAccounts();
+      await program.methods
+        .spendIntent(new anchor.BN(amount))
+        .accounts(accounts)
+        .signers([newOwner])
+        .rpc();
+      return program.account.auditEvent.fetch(accounts.auditEvent);
+    };
+    const setBackoff = (base) =>
+      program.methods
+        .setDenialBackoff(base)
+        .accounts({
+          policy: newPolicy,
+          vault: newVault,
+          authority: newOwner.publicKey,
+        })
+        .signers([newOwner])
+        .rpc();
+
+    await setBackoff(60);
+    const first = await attempt(0);
+    assert.strictEqual(first.reasonCode, 4); // REASON_INVALID_AMOUNT
+    let policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.consecutiveDenials, 1);
+    const firstDenialTs = policy.lastDenialTs;
+    for (let i = 0; i < 3; i++) {
+      const backedOff = await attempt(1_000);
+      assert.strictEqual(backedOff.allowed, false);
+      assert.strictEqual(backedOff.reasonCode, 26); // REASON_BACKOFF
+    }
+    // The backed-off attempts neither double the wait nor restart it.
+    policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.consecutiveDenials, 1);
+    assert.ok(policy.lastDenialTs.eq(firstDenialTs));
+    const wait = await program.methods
+      .secondsUntilSpendable()
+      .accounts({ policy: newPolicy })
+      .view();
+    assert.ok(wait.gtn(0) && wait.lten(60 * 2));
+
+    // With the backoff off, an allowed spend resets the streak.
+    await setBackoff(0);
+    await program.methods
+      .depositAndSpend(new anchor.BN(2_000_000), new anchor.BN(1_000))
+      .accounts({ spend: await spendAccounts(), funder: newOwner.publicKey })
+      .signers([newOwner])
+      .rpc();
+    policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.consecutiveDenials, 0);
+  });
+
+  it("D.10) spend_intent_tagged — region tag recorded, RegionBudget caps it", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const regionPda = (code) => {
+      const codeBytes = Buffer.alloc(2);
+      codeBytes.writeUInt16LE(code);
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("region"), newPolicy.toBuffer(), codeBytes],
+        program.programId
+      )[0];
+    };
+    const tagged = async (amount, code) => {
+      const { nextSequence } = await program.account.policy.fetch(newPolicy);
+      const [auditEvent] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          nextSequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      await program.methods
+        .spendIntentTagged(new anchor.BN(amount), code)
+        .accounts({
+          spend: {
+            auditEvent,
+            policy: newPolicy,
+            vault: newVault,
+            recipient: payee,
+            caller: newOwner.publicKey,
+            systemProgram: SystemProgram.programId,
+          },
+          regionBudget: regionPda(code),
+        })
+        .signers([newOwner])
+        .rpc();
+      return program.account.auditEvent.fetch(auditEvent);
+    };
+
+    await program.methods
+      .setRegionBudget(840, new anchor.BN(1_000_000))
+      .accounts({
+        regionBudget: regionPda(840),
+        policy: newPolicy,
+        vault: newVault,
+        authority: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    const capped = await tagged(2_000_000, 840);
+    assert.strictEqual(capped.allowed, false);
+    assert.strictEqual(capped.reasonCode, 27); // REASON_REGION_CAP
+    assert.strictEqual(capped.regionCode, 840);
+
+    // A region without a RegionBudget is tagged but uncapped.
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: newOwner.publicKey,
+          toPubkey: newVault,
+          lamports: 2_000_000,
+        })
+      ),
+      [newOwner]
+    );
+    const uncapped = await tagged(2_000_000, 276);
+    assert.strictEqual(uncapped.allowed, true);
+    assert.strictEqual(uncapped.regionCode, 276);
+  });
+
+  it("C.46) set_max_spend_gap — SpendHeartbeatMissed after a silence", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    await program.methods
+      .setPolicy(DAILY_BUDGET, 0, null)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .setMaxSpendGap(1)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const spend = async () => {
+      const { nextSequence } = await program.account.policy.fetch(newPolicy);
+      const [auditEvent] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          nextSequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      await program.methods
+        .depositAndSpend(new anchor.BN(1_000_000), new anchor.BN(1_000_000))
+        .accounts({
+          spend: {
+            auditEvent,
+            policy: newPolicy,
+            vault: newVault,
+            recipient: payee,
+            caller: newOwner.publicKey,
+            systemProgram: SystemProgram.programId,
+          },
+          funder: newOwner.publicKey,
+        })
+        .signers([newOwner])
+        .rpc();
+    };
+
+    await spend();
+    const { lastSpendTs } = await program.account.policy.fetch(newPolicy);
+    await new Promise((r) => setTimeout(r, 2_500));
+
+    let listener;
+    const event = new Promise((resolve) => {
+      listener = program.addEventListener("spendHeartbeatMissed", resolve);
+    });
+    await spend();
+    const ev = await event;
+    await program.removeEventListener(listener);
+
+    assert.ok(ev.policy.equals(newPolicy));
+    assert.ok(ev.lastSpendTs.eq(lastSpendTs));
+    assert.ok(ev.gapSeconds.gtn(1));
+  });
+
+  it("C.47) set_budget_mode — rolling 24-hour window", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    try {
+      await program.methods
+        .setBudgetMode(2)
+        .accounts(setterFresh)
+        .signers([newOwner])
+        .rpc();
+      assert.fail("Should have thrown InvalidBudgetMode");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidBudgetMode"),
+        `Expected InvalidBudgetMode error, got: ${err}`
+      );
+    }
+    await program.methods
+      .setBudgetMode(1)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+
+    const { nextSequence } = await program.account.policy.fetch(newPolicy);
+    const [auditEvent] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("audit"),
+        newPolicy.toBuffer(),
+        nextSequence.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const amount = new anchor.BN(1_000_000);
+    await program.methods
+      .depositAndSpend(amount, amount)
+      .accounts({
+        spend: {
+          auditEvent,
+          policy: newPolicy,
+          vault: newVault,
+          recipient: anchor.web3.Keypair.generate().publicKey,
+          caller: newOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        },
+        funder: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.budgetMode, 1);
+    assert.ok(policy.rollingSpends[0].amount.eq(amount));
+    assert.strictEqual(policy.rollingHead, 1);
+    const status = await program.methods
+      .remainingBudget()
+      .accounts({ policy: newPolicy })
+      .view();
+    assert.ok(status.remainingToday.eq(DAILY_BUDGET.sub(amount)));
+  });
+
+  it("D.11) fund_caller — top-ups bounded by the ops budget and audited", async () => {
+    await program.methods
+      .setOpsBudget(new anchor.BN(1_000_000))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const topUp = async (amount) => {
+      let listener;
+      const event = new Promise((resolve) => {
+        listener = program.addEventListener("callerFunded", resolve);
+      });
+      const [auditEvent] = auditPda(nextSeq);
+      await program.methods
+        .fundCaller(new anchor.BN(amount))
+        .accounts({
+          auditEvent,
+          policy: policyPda,
+          vault: vaultPda,
+          caller: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      nextSeq++;
+      const ev = await event;
+      await program.removeEventListener(listener);
+      return { ev, audit: await program.account.auditEvent.fetch(auditEvent) };
+    };
+
+    const vaultBefore = await provider.connection.getBalance(vaultPda);
+    const funded = await topUp(600_000);
+    assert.strictEqual(funded.ev.allowed, true);
+    assert.ok(funded.ev.opsFundingSpentToday.eqn(600_000));
+    assert.strictEqual(
+      await provider.connection.getBalance(vaultPda),
+      vaultBefore - 600_000
+    );
+    assert.strictEqual(funded.audit.allowed, true);
+    assert.ok(funded.audit.recipient.equals(owner.publicKey));
+    assert.ok(funded.audit.amount.eqn(600_000));
+
+    const denied = await topUp(600_000);
+    assert.strictEqual(denied.ev.allowed, false);
+    assert.strictEqual(denied.ev.reasonCode, 28); // REASON_OPS_BUDGET_EXCEEDED
+    assert.strictEqual(denied.audit.allowed, false);
+    assert.strictEqual(denied.audit.reasonCode, 28);
+
+    await program.methods
+      .setOpsBudget(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("D.11b) fund_caller — an agent cannot top up from the authority reserve", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const agent = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      agent.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    await program.methods
+      .setPolicy(DAILY_BUDGET, 0, agent.publicKey)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .setOpsBudget(new anchor.BN(10_000_000))
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(2_000_000))
+      .accounts({
+        vault: newVault,
+        funder: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .setAgentReserve(new anchor.BN(1_500_000))
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+
+    const topUp = async (caller, amount) => {
+      const { nextSequence } = await program.account.policy.fetch(newPolicy);
+      const [auditEvent] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          nextSequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      await program.methods
+        .fundCaller(new anchor.BN(amount))
+        .accounts({
+          auditEvent,
+          policy: newPolicy,
+          vault: newVault,
+          caller: caller.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([caller])
+        .rpc();
+      return program.account.auditEvent.fetch(auditEvent);
+    };
+
+    const agentDenied = await topUp(agent, 1_000_000);
+    assert.strictEqual(agentDenied.allowed, false);
+    assert.strictEqual(agentDenied.reasonCode, 16); // REASON_AGENT_RESERVE_VIOLATION
+    assert.strictEqual(agentDenied.callerIsAgent, true);
+    const agentAllowed = await topUp(agent, 100_000);
+    assert.strictEqual(agentAllowed.allowed, true);
+    const ownerAllowed = await topUp(newOwner, 1_000_000);
+    assert.strictEqual(ownerAllowed.allowed, true);
+    assert.strictEqual(ownerAllowed.callerIsAgent, false);
+  });
+
+  it("C.49) set_notify_program — spends settle without an executable notify program", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    try {
+      await program.methods
+        .setNotifyProgram(program.programId, new anchor.BN(0))
+        .accounts(setterFresh)
+        .signers([newOwner])
+        .rpc();
+      assert.fail("Should have thrown InvalidNotifyProgram");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidNotifyProgram"),
+        `Expected InvalidNotifyProgram error, got: ${err}`
+      );
+    }
+    const notifyProgram = anchor.web3.Keypair.generate().publicKey;
+    await program.methods
+      .setNotifyProgram(notifyProgram, new anchor.BN(500_000))
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.ok(policy.notifyProgram.equals(notifyProgram));
+    assert.ok(policy.notifyThresholdLamports.eqn(500_000));
+
+    const [auditEvent] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("audit"),
+        newPolicy.toBuffer(),
+        policy.nextSequence.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const amount = new anchor.BN(1_000_000);
+    await program.methods
+      .depositAndSpend(amount, amount)
+      .accounts({
+        spend: {
+          auditEvent,
+          policy: newPolicy,
+          vault: newVault,
+          recipient: anchor.web3.Keypair.generate().publicKey,
+          caller: newOwner.publicKey,
+          notifyProgram,
+          systemProgram: SystemProgram.programId,
+        },
+        funder: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    const audit = await program.account.auditEvent.fetch(auditEvent);
+    assert.strictEqual(audit.allowed, true);
+  });
+
+  it("A.2) withdraw — owner pulls funds outside the spend policy", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    await program.methods
+      .deposit(new anchor.BN(2_000_000))
+      .accounts({
+        vault: newVault,
+        funder: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    const { nextSequence } = await program.account.policy.fetch(newPolicy);
+    const destination = anchor.web3.Keypair.generate().publicKey;
+    const withdrawAccounts = {
+      vault: newVault,
+      policy: newPolicy,
+      owner: newOwner.publicKey,
+      destination,
+    };
+
+    let listener;
+    const event = new Promise((resolve) => {
+      listener = program.addEventListener("withdrawn", resolve);
+    });
+    await program.methods
+      .withdraw(new anchor.BN(1_000_000))
+      .accounts(withdrawAccounts)
+      .signers([newOwner])
+      .rpc();
+    const ev = await event;
+    await program.removeEventListener(listener);
+
+    assert.strictEqual(
+      await provider.connection.getBalance(destination),
+      1_000_000
+    );
+    assert.ok(ev.destination.equals(destination));
+    assert.ok(ev.newBalance.eqn(await provider.connection.getBalance(newVault)));
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.ok(policy.nextSequence.eq(nextSequence));
+    assert.ok(policy.spentTodayLamports.eqn(0));
+
+    const vaultBalance = await provider.connection.getBalance(newVault);
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(vaultBalance))
+        .accounts(withdrawAccounts)
+        .signers([newOwner])
+        .rpc();
+      assert.fail("Should have thrown WithdrawBreachesReserve");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("WithdrawBreachesReserve"),
+        `Expected WithdrawBreachesReserve error, got: ${err}`
+      );
+    }
+
+    // Lamports committed to a timelocked claim stay in the vault too.
+    const seqBuf = Buffer.alloc(8);
+    seqBuf.writeBigUInt64LE(BigInt(nextSequence.toNumber()));
+    const [timelock] = PublicKey.findProgramAddressSync(
+      [Buffer.from("timelock"), newPolicy.toBuffer(), seqBuf],
+      program.programId
+    );
+    const [auditEvent] = PublicKey.findProgramAddressSync(
+      [Buffer.from("audit"), newPolicy.toBuffer(), seqBuf],
+      program.programId
+    );
+    await program.methods
+      .spendToTimelock(
+        new anchor.BN(400_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+      )
+      .accounts({
+        auditEvent,
+        timelock,
+        policy: newPolicy,
+        vault: newVault,
+        recipient: destination,
+        caller: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    const rentExempt = await provider.connection.getMinimumBalanceForRentExemption(41); // Vault::SIZE
+    const free = vaultBalance - rentExempt - 400_000;
+    try {
+      await program.methods
+        .withdraw(new anchor.BN(free + 1))
+        .accounts(withdrawAccounts)
+        .signers([newOwner])
+        .rpc();
+      assert.fail("Should have thrown WithdrawBreachesReserve");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("WithdrawBreachesReserve"),
+        `Expected WithdrawBreachesReserve error, got: ${err}`
+      );
+    }
+    await program.methods
+      .withdraw(new anchor.BN(free))
+      .accounts(withdrawAccounts)
+      .signers([newOwner])
+      .rpc();
+    assert.strictEqual(
+      await provider.connection.getBalance(newVault),
+      rentExempt + 400_000
+    );
+  });
+
+  it("C.50) block/unblock_recipient — blocked payee denied first on every path", async () => {
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const [blockedRecipient] = PublicKey.findProgramAddressSync(
+      [Buffer.from("blocked"), policyPda.toBuffer(), payee.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .blockRecipient()
+      .accounts({
+        blockedRecipient,
+        policy: policyPda,
+        vault: vaultPda,
+        recipient: payee,
+        authority: owner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const denied = await spendV2(new anchor.BN(1_000_000), payee, {
+      blockedRecipient,
+    });
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 30); // REASON_RECIPIENT_BLOCKED
+
+    // The block holds on the other spend paths too.
+    const accounts = () => ({
+      auditEvent: auditPda(nextSeq)[0],
+      policy: policyPda,
+      vault: vaultPda,
+      recipient: payee,
+      blockedRecipient,
+      caller: owner.publicKey,
+      systemProgram: SystemProgram.programId,
+    });
+    const [v1Audit] = auditPda(nextSeq);
+    await program.methods
+      .spendIntent(new anchor.BN(1_000_000))
+      .accounts(accounts())
+      .rpc();
+    nextSeq++;
+    const v1 = await program.account.auditEvent.fetch(v1Audit);
+    assert.strictEqual(v1.allowed, false);
+    assert.strictEqual(v1.reasonCode, 30); // REASON_RECIPIENT_BLOCKED
+
+    const [meterKey] = auditPda(nextSeq - 1);
+    await program.methods
+      .setMeter(meterKey, 88, new anchor.BN(1_000))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    const [meteredAudit] = auditPda(nextSeq);
+    await program.methods
+      .spendIntentMetered(new anchor.BN(1_000_000))
+      .accounts({ ...accounts(), meter: meterKey })
+      .rpc();
+    nextSeq++;
+    const metered = await program.account.auditEvent.fetch(meteredAudit);
+    assert.strictEqual(metered.allowed, false);
+    assert.strictEqual(metered.reasonCode, 30); // REASON_RECIPIENT_BLOCKED
+    await program.methods
+      .setMeter(null, 0, new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    await program.methods
+      .unblockRecipient()
+      .accounts({
+        blockedRecipient,
+        policy: policyPda,
+        recipient: payee,
+        authority: owner.publicKey,
+      })
+      .rpc();
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(blockedRecipient),
+      null
+    );
+  });
+
+  it("C.52) set_max_spends_per_day — count limit denies extra spends", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    await program.methods
+      .setPolicy(DAILY_BUDGET, 0, null)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .setMaxSpendsPerDay(1)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    const spend = async () => {
+      const { nextSequence } = await program.account.policy.fetch(newPolicy);
+      const [auditEvent] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          nextSequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      await program.methods
+        .depositAndSpend(new anchor.BN(1_000_000), new anchor.BN(1_000_000))
+        .accounts({
+          spend: {
+            auditEvent,
+            policy: newPolicy,
+            vault: newVault,
+            recipient: anchor.web3.Keypair.generate().publicKey,
+            caller: newOwner.publicKey,
+            systemProgram: SystemProgram.programId,
+          },
+          funder: newOwner.publicKey,
+        })
+        .signers([newOwner])
+        .rpc();
+      return program.account.auditEvent.fetch(auditEvent);
+    };
+
+    assert.strictEqual((await spend()).allowed, true);
+    const denied = await spend();
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 31); // REASON_COUNT_LIMIT
+  });
+
+  it("D.12) approve_spend — agent spend above the threshold waits for approval", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const agent = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      agent.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    await program.methods
+      .setPolicy(DAILY_BUDGET, 0, agent.publicKey)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .setApprovalThreshold(new anchor.BN(500_000), 3600)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(2_000_000))
+      .accounts({
+        vault: newVault,
+        funder: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+
+    const auditAt = (sequence) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          sequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+    const [pendingSpend] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("pending"),
+        newPolicy.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const amount = new anchor.BN(1_000_000);
+
+    let { nextSequence } = await program.account.policy.fetch(newPolicy);
+    await program.methods
+      .spendIntent(amount)
+      .accounts({
+        auditEvent: auditAt(nextSequence),
+        policy: newPolicy,
+        vault: newVault,
+        recipient: payee,
+        caller: agent.publicKey,
+        pendingSpend,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([agent])
+      .rpc();
+    const held = await program.account.auditEvent.fetch(auditAt(nextSequence));
+    assert.strictEqual(held.allowed, false);
+    assert.strictEqual(held.reasonCode, 32); // REASON_APPROVAL_REQUIRED
+    assert.ok(held.caller.equals(agent.publicKey));
+    assert.strictEqual(held.callerIsAgent, true);
+    const pending = await program.account.pendingSpend.fetch(pendingSpend);
+    assert.ok(pending.amount.eq(amount));
+    assert.ok(pending.expiresTs.sub(pending.requestedTs).eqn(3600));
+    assert.strictEqual(await provider.connection.getBalance(payee), 0);
+
+    let policy = await program.account.policy.fetch(newPolicy);
+    // The held lamports are kept out of owner withdrawals until settled.
+    assert.ok(policy.pendingLamports.eq(amount));
+    ({ nextSequence } = policy);
+    await program.methods
+      .approveSpend()
+      .accounts({
+        auditEvent: auditAt(nextSequence),
+        pendingSpend,
+        policy: newPolicy,
+        vault: newVault,
+        recipient: payee,
+        payer: agent.publicKey,
+        authority: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    const approved = await program.account.auditEvent.fetch(
+      auditAt(nextSequence)
+    );
+    assert.strictEqual(approved.allowed, true);
+    assert.strictEqual(approved.callerIsAgent, true);
+    assert.strictEqual(await provider.connection.getBalance(payee), 1_000_000);
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(pendingSpend),
+      null
+    );
+    policy = await program.account.policy.fetch(newPolicy);
+    assert.ok(policy.pendingLamports.eqn(0));
+  });
+
+  it("D.12b) approve_spend — spend_intent_v2 holds too, and approval re-checks the budgets", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const agent = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      agent.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    await program.methods
+      .setPolicy(DAILY_BUDGET, 0, agent.publicKey)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .setApprovalThreshold(new anchor.BN(500_000), 3600)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(2_000_000))
+      .accounts({
+        vault: newVault,
+        funder: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+
+    const auditAt = (sequence) =>
+      PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          sequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+    const [pendingSpend] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("pending"),
+        newPolicy.toBuffer(),
+        new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const amount = new anchor.BN(1_000_000);
+
+    let { nextSequence } = await program.account.policy.fetch(newPolicy);
+    await program.methods
+      .spendIntentV2(amount)
+      .accounts({
+        auditEvent: auditAt(nextSequence),
+        recipientSpend: null,
+        policy: newPolicy,
+        vault: newVault,
+        recipient: payee,
+        caller: agent.publicKey,
+        pendingSpend,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([agent])
+      .rpc();
+    const held = await program.account.auditEvent.fetch(auditAt(nextSequence));
+    assert.strictEqual(held.reasonCode, 32); // REASON_APPROVAL_REQUIRED
+    const pending = await program.account.pendingSpend.fetch(pendingSpend);
+    assert.ok(pending.amount.eq(amount));
+    assert.ok(pending.payer.equals(agent.publicKey));
+
+    // The budget shrinks below the held amount before the authority approves.
+    await program.methods
+      .setPolicy(new anchor.BN(500_000), 0, agent.publicKey)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    const approveAccounts = (sequence) => ({
+      auditEvent: auditAt(sequence),
+      pendingSpend,
+      policy: newPolicy,
+      vault: newVault,
+      recipient: payee,
+      payer: agent.publicKey,
+      authority: newOwner.publicKey,
+      systemProgram: SystemProgram.programId,
+    });
+    ({ nextSequence } = await program.account.policy.fetch(newPolicy));
+    await program.methods
+      .approveSpend()
+      .accounts(approveAccounts(nextSequence))
+      .signers([newOwner])
+      .rpc();
+    const denied = await program.account.auditEvent.fetch(
+      auditAt(nextSequence)
+    );
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 2); // REASON_BUDGET_EXCEEDED
+    assert.strictEqual(await provider.connection.getBalance(payee), 0);
+    assert.ok(await provider.connection.getAccountInfo(pendingSpend));
+
+    await program.methods
+      .setPolicy(DAILY_BUDGET, 0, agent.publicKey)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    ({ nextSequence } = await program.account.policy.fetch(newPolicy));
+    await program.methods
+      .approveSpend()
+      .accounts(approveAccounts(nextSequence))
+      .signers([newOwner])
+      .rpc();
+    const approved = await program.account.auditEvent.fetch(
+      auditAt(nextSequence)
+    );
+    assert.strictEqual(approved.allowed, true);
+    assert.strictEqual(await provider.connection.getBalance(payee), 1_000_000);
+  });
+
+  it("C.54) set_spend_window — spends outside the daily window are denied", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    try {
+      await program.methods
+        .setSpendWindow(3600, 3600)
+        .accounts(setterFresh)
+        .signers([newOwner])
+        .rpc();
+      assert.fail("Should have thrown InvalidSpendWindow");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidSpendWindow"),
+        `Expected InvalidSpendWindow error, got: ${err}`
+      );
+    }
+    // A one-hour window opening two hours from now (UTC seconds of day).
+    const nowOfDay = Math.floor(Date.now() / 1000) % 86_400;
+    const start = (nowOfDay + 7_200) % 86_400;
+    await program.methods
+      .setSpendWindow(start, (start + 3_600) % 86_400)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+
+    const { nextSequence } = await program.account.policy.fetch(newPolicy);
+    const [auditEvent] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("audit"),
+        newPolicy.toBuffer(),
+        nextSequence.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    await program.methods
+      .depositAndSpend(new anchor.BN(1_000_000), new anchor.BN(1_000_000))
+      .accounts({
+        spend: {
+          auditEvent,
+          policy: newPolicy,
+          vault: newVault,
+          recipient: anchor.web3.Keypair.generate().publicKey,
+          caller: newOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        },
+        funder: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    const audit = await program.account.auditEvent.fetch(auditEvent);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 33); // REASON_OUTSIDE_WINDOW
+  });
+
+  it("E.7) close_policy / close_vault — tear down a decommissioned vault", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const closeVaultAccounts = {
+      vault: newVault,
+      policy: newPolicy,
+      owner: newOwner.publicKey,
+    };
+    try {
+      await program.methods
+        .closeVault()
+        .accounts(closeVaultAccounts)
+        .signers([newOwner])
+        .rpc();
+      assert.fail("Should have thrown PolicyStillOpen");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("PolicyStillOpen"),
+        `Expected PolicyStillOpen error, got: ${err}`
+      );
+    }
+
+    await program.methods
+      .closePolicy()
+      .accounts({ policy: newPolicy, authority: newOwner.publicKey })
+      .signers([newOwner])
+      .rpc();
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(newPolicy),
+      null
+    );
+
+    // Drain the vault down to its rent-exempt minimum, then close it.
+    await program.methods
+      .deposit(new anchor.BN(1_000_000))
+      .accounts({
+        vault: newVault,
+        funder: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    const { data } = await provider.connection.getAccountInfo(newVault);
+    const rentExempt =
+      await provider.connection.getMinimumBalanceForRentExemption(data.length);
+    const balance = await provider.connection.getBalance(newVault);
+    await program.methods
+      .withdraw(new anchor.BN(balance - rentExempt))
+      .accounts({
+        vault: newVault,
+        policy: newPolicy,
+        owner: newOwner.publicKey,
+        destination: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .closeVault()
+      .accounts(closeVaultAccounts)
+      .signers([newOwner])
+      .rpc();
+    assert.strictEqual(
+      await provider.connection.getAccountInfo(newVault),
+      null
+    );
+  });
+
+  it("C.55) set_price_feed — spends without a fresh price are denied", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const priceFeed = anchor.web3.Keypair.generate().publicKey;
+    await program.methods
+      .setPriceFeed(priceFeed, new anchor.BN(50_000), 60)
+      .accounts({
+        policy: newPolicy,
+        vault: newVault,
+        authority: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.ok(policy.priceFeed.equals(priceFeed));
+    assert.ok(policy.dailyBudgetUsdCents.eqn(50_000));
+
+    const [auditEvent] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("audit"),
+        newPolicy.toBuffer(),
+        policy.nextSequence.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    await program.methods
+      .depositAndSpend(new anchor.BN(1_000_000), new anchor.BN(1_000_000))
+      .accounts({
+        spend: {
+          auditEvent,
+          policy: newPolicy,
+          vault: newVault,
+          recipient: anchor.web3.Keypair.generate().publicKey,
+          caller: newOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        },
+        funder: newOwner.publicKey,
+      })
+      .remainingAccounts([
+        { pubkey: priceFeed, isSigner: false, isWritable: false },
+      ])
+      .signers([newOwner])
+      .rpc();
+    const audit = await program.account.auditEvent.fetch(auditEvent);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 34); // REASON_STALE_PRICE
+  });
+
+  it("C.57) freeze — guardian pauses but cannot do anything else", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const guardian = anchor.web3.Keypair.generate();
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({
+        policy: newPolicy,
+        vault: newVault,
+        authority: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+
+    await program.methods
+      .freeze()
+      .accounts({ policy: newPolicy, guardian: guardian.publicKey })
+      .signers([guardian])
+      .rpc();
+    const frozen = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(frozen.paused, true);
+
+    // The freeze stops the basic spend path too, not only spend_intent_v2.
+    const [auditEvent] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("audit"),
+        newPolicy.toBuffer(),
+        frozen.nextSequence.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    await program.methods
+      .spendIntent(new anchor.BN(1_000))
+      .accounts({
+        auditEvent,
+        policy: newPolicy,
+        vault: newVault,
+        recipient: anchor.web3.Keypair.generate().publicKey,
+        caller: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    const audit = await program.account.auditEvent.fetch(auditEvent);
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 5); // REASON_PAUSED
+
+    // The guardian is not the authority: every setter rejects it.
+    try {
+      await program.methods
+        .setPolicyAdvanced(
+          DAILY_BUDGET,
+          COOLDOWN_SECS,
+          null,
+          false,
+          false,
+          null,
+          new anchor.BN(0),
+          0
+        )
+        .accounts({
+          policy: newPolicy,
+          vault: newVault,
+          authority: guardian.publicKey,
+        })
+        .signers([guardian])
+        .rpc();
+      assert.fail("Should have thrown Unauthorized");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("Unauthorized"),
+        `Expected Unauthorized error, got: ${err}`
+      );
+    }
+  });
+
+  it("C.62) unfreeze — authority lifts a guardian freeze; agents stay revocable when locked", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const guardian = anchor.web3.Keypair.generate();
+    const setterFresh = {
+      policy: newPolicy,
+      vault: newVault,
+      authority: newOwner.publicKey,
+    };
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    const agent = anchor.web3.Keypair.generate().publicKey;
+    const extraAgent = anchor.web3.Keypair.generate().publicKey;
+    await program.methods
+      .rotateAgentSecure(agent)
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    const [extraAgentEntry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("agent"), newPolicy.toBuffer(), extraAgent.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .addAgent(new anchor.BN(1_000_000))
+      .accounts({
+        agentEntry: extraAgentEntry,
+        policy: newPolicy,
+        vault: newVault,
+        agent: extraAgent,
+        authority: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .lockPolicy()
+      .accounts(setterFresh)
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .freeze()
+      .accounts({ policy: newPolicy, guardian: guardian.publicKey })
+      .signers([guardian])
+      .rpc();
+
+    // The guardian cannot undo its own freeze.
+    try {
+      await program.methods
+        .unfreeze()
+        .accounts({ policy: newPolicy, authority: guardian.publicKey })
+        .signers([guardian])
+        .rpc();
+      assert.fail("Should have thrown Unauthorized");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("Unauthorized"),
+        `Expected Unauthorized error, got: ${err}`
+      );
+    }
+
+    await program.methods
+      .unfreeze()
+      .accounts({ policy: newPolicy, authority: newOwner.publicKey })
+      .signers([newOwner])
+      .rpc();
+    let policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.paused, false);
+    assert.strictEqual(policy.locked, true);
+
+    // A locked policy cannot gain a new spender through rotation...
+    const [agentEntry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("agent"), newPolicy.toBuffer(), agent.toBuffer()],
+      program.programId
+    );
+    try {
+      await program.methods
+        .rotateAgentSecure(anchor.web3.Keypair.generate().publicKey)
+        .accounts({ ...setterFresh, oldAgentEntry: agentEntry })
+        .signers([newOwner])
+        .rpc();
+      assert.fail("Should have thrown PolicyLocked");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("PolicyLocked"),
+        `Expected PolicyLocked error, got: ${err}`
+      );
+    }
+
+    // ...but a compromised agent can still be revoked, either way it was added.
+    await program.methods
+      .rotateAgentSecure(null)
+      .accounts({ ...setterFresh, oldAgentEntry: agentEntry })
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .removeAgent()
+      .accounts({
+        agentEntry: extraAgentEntry,
+        policy: newPolicy,
+        agent: extraAgent,
+        authority: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.agent, null);
+    assert.strictEqual(await program.account.agent.fetchNullable(extraAgentEntry), null);
+  });
+
+  it("D.14) spend_batch — one instruction pays several recipients, all or nothing", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    await program.methods
+      .setPolicy(DAILY_BUDGET, 0, null)
+      .accounts({
+        policy: newPolicy,
+        vault: newVault,
+        authority: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(8_000_000))
+      .accounts({
+        vault: newVault,
+        funder: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+
+    const payees = [0, 1].map(() => anchor.web3.Keypair.generate().publicKey);
+    const remainingAccounts = payees.flatMap((payee) => [
+      { pubkey: payee, isWritable: true, isSigner: false },
+      {
+        pubkey: PublicKey.findProgramAddressSync(
+          [Buffer.from("blocked"), newPolicy.toBuffer(), payee.toBuffer()],
+          program.programId
+        )[0],
+        isWritable: false,
+        isSigner: false,
+      },
+    ]);
+    const batch = async (amounts) => {
+      const { nextBatchId } = await program.account.policy.fetch(newPolicy);
+      const [batchAudit] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("batch"),
+          newPolicy.toBuffer(),
+          nextBatchId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      await program.methods
+        .spendBatch(amounts.map((amount) => new anchor.BN(amount)))
+        .accounts({
+          batchAudit,
+          policy: newPolicy,
+          vault: newVault,
+          caller: newOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(remainingAccounts)
+        .signers([newOwner])
+        .rpc();
+      return program.account.batchAuditEvent.fetch(batchAudit);
+    };
+
+    const record = await batch([1_000_000, 2_000_000]);
+    assert.strictEqual(record.total.toNumber(), 3_000_000);
+    assert.strictEqual(record.entries.length, 2);
+    assert.ok(record.entries[1].recipient.equals(payees[1]));
+    assert.strictEqual(
+      await provider.connection.getBalance(payees[1]),
+      2_000_000
+    );
+
+    // 3 + 1 + 1.5 > 5 daily: the whole batch fails, the first payee gets nothing more.
+    try {
+      await batch([1_000_000, 1_500_000]);
+      assert.fail("Should have thrown BatchDenied");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("BatchDenied"),
+        `Expected BatchDenied error, got: ${err}`
+      );
+    }
+    assert.strictEqual(
+      await provider.connection.getBalance(payees[0]),
+      1_000_000
+    );
+  });
+
+  it("C.58) set_cooldown_scope — per-recipient cooldown lets other payees through", async () => {
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.spentTodayLamports.add(new anchor.BN(10_000_000)), 3_600, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setCooldownScope(1)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const [first, second] = [0, 1].map(() => anchor.web3.Keypair.generate().publicKey);
+    assert.strictEqual((await spendV2(new anchor.BN(1_000_000), first)).allowed, true);
+    // The policy-wide cooldown no longer blocks a different recipient...
+    assert.strictEqual((await spendV2(new anchor.BN(1_000_000), second)).allowed, true);
+    // ...but the same recipient waits out its own.
+    const denied = await spendV2(new anchor.BN(1_000_000), first);
+    assert.strictEqual(denied.allowed, false);
+    assert.strictEqual(denied.reasonCode, 3); // REASON_COOLDOWN
+
+    await program.methods
+      .setCooldownScope(0)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("D.2) spend_intent_v2 — another policy's RecipientSpend is rejected", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const payee = anchor.web3.Keypair.generate().publicKey;
+    const [foreignTracker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("recipient"), newPolicy.toBuffer(), payee.toBuffer()],
+      program.programId
+    );
+    const [foreignAudit] = PublicKey.findProgramAddressSync(
+      [Buffer.from("audit"), newPolicy.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    // A zero-amount attempt is enough to create the other policy's tracker.
+    await program.methods
+      .spendIntentV2(new anchor.BN(0))
+      .accounts({
+        auditEvent: foreignAudit,
+        recipientSpend: foreignTracker,
+        policy: newPolicy,
+        vault: newVault,
+        recipient: payee,
+        caller: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+    const tracker = await program.account.recipientSpend.fetch(foreignTracker);
+    assert.ok(tracker.policy.equals(newPolicy));
+
+    try {
+      await spendV2(new anchor.BN(1_000_000), payee, {
+        recipientSpend: foreignTracker,
+      });
+      assert.fail("Should have rejected the foreign tracker");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("ConstraintSeeds") ||
+          err.toString().includes("RecipientSpendMismatch"),
+        `Expected a tracker mismatch error, got: ${err}`
+      );
+    }
+  });
+
+  it("C.59) reset_spend_counters — authority clears today's spend", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    await program.methods
+      .setPolicy(new anchor.BN(1_000_000), 0, null)
+      .accounts({
+        policy: newPolicy,
+        vault: newVault,
+        authority: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    const spend = async () => {
+      const { nextSequence } = await program.account.policy.fetch(newPolicy);
+      const [auditEvent] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("audit"),
+          newPolicy.toBuffer(),
+          nextSequence.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+      await program.methods
+        .depositAndSpend(new anchor.BN(1_000_000), new anchor.BN(1_000_000))
+        .accounts({
+          spend: {
+            auditEvent,
+            policy: newPolicy,
+            vault: newVault,
+            recipient: anchor.web3.Keypair.generate().publicKey,
+            caller: newOwner.publicKey,
+            systemProgram: SystemProgram.programId,
+          },
+          funder: newOwner.publicKey,
+        })
+        .signers([newOwner])
+        .rpc();
+      return program.account.auditEvent.fetch(auditEvent);
+    };
+
+    assert.strictEqual((await spend()).allowed, true);
+    assert.strictEqual((await spend()).reasonCode, 2); // REASON_BUDGET_EXCEEDED
+
+    await program.methods
+      .resetSpendCounters()
+      .accounts({
+        policy: newPolicy,
+        recipientSpend: null,
+        authority: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.spentTodayLamports.toNumber(), 0);
+    assert.strictEqual(policy.allowedCountToday, 0);
+    assert.strictEqual((await spend()).allowed, true);
+  });
+
+  it("B.3) clone_policy — copies limits under the same authority, fresh counters", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    await program.methods
+      .setMaxSpendsPerDay(7)
+      .accounts({
+        policy: newPolicy,
+        vault: newVault,
+        authority: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+
+    const agentOwner = anchor.web3.Keypair.generate();
+    const [agentVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), agentOwner.publicKey.toBuffer()],
+      program.programId
+    );
+    const [agentPolicy] = PublicKey.findProgramAddressSync(
+      [Buffer.from("policy"), agentVault.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeVault()
+      .accounts({
+        vault: agentVault,
+        owner: agentOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([
+        SystemProgram.transfer({
+          fromPubkey: newOwner.publicKey,
+          toPubkey: agentOwner.publicKey,
+          lamports: LAMPORTS_PER_SOL / 100,
+        }),
+      ])
+      .signers([newOwner, agentOwner])
+      .rpc();
+    await program.methods
+      .clonePolicy()
+      .accounts({
+        policy: agentPolicy,
+        vault: agentVault,
+        sourcePolicy: newPolicy,
+        owner: agentOwner.publicKey,
+        authority: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([agentOwner, newOwner])
+      .rpc();
+
+    const source = await program.account.policy.fetch(newPolicy);
+    const clone = await program.account.policy.fetch(agentPolicy);
+    assert.ok(clone.vault.equals(agentVault));
+    assert.ok(clone.authority.equals(newOwner.publicKey));
+    assert.ok(clone.dailyBudgetLamports.eq(source.dailyBudgetLamports));
+    assert.strictEqual(clone.cooldownSeconds, source.cooldownSeconds);
+    assert.strictEqual(clone.maxSpendsPerDay, 7);
+    assert.strictEqual(clone.nextSequence.toNumber(), 0);
+    assert.strictEqual(clone.policyVersion, 1);
+  });
+
+  it("D.2) AuditEvent — records who submitted the spend", async () => {
+    const audit = await spendV2(
+      new anchor.BN(0),
+      anchor.web3.Keypair.generate().publicKey
+    );
+    assert.ok(audit.caller.equals(owner.publicKey));
+    assert.strictEqual(audit.callerIsAgent, false);
+  });
+
+  it("C.60) set_registry_program — a registry that cannot answer denies the payee", async () => {
+    // A random key is not an executable program, so it never says yes.
+    const registry = anchor.web3.Keypair.generate().publicKey;
+    await program.methods
+      .setRegistryProgram(registry)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const audit = await spendV2(new anchor.BN(1_000_000), payee.publicKey, {
+      registryProgram: registry,
+    });
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 6); // REASON_RECIPIENT_NOT_ALLOWED
+
+    // The program cannot be its own registry.
+    try {
+      await program.methods
+        .setRegistryProgram(program.programId)
+        .accounts(setterAccounts())
+        .rpc();
+      assert.fail("Should have thrown InvalidRegistryProgram");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidRegistryProgram"),
+        `Expected InvalidRegistryProgram error, got: ${err}`
+      );
+    }
+
+    await program.methods.setRegistryProgram(null).accounts(setterAccounts()).rpc();
+    policyVersion += 1;
+  });
+
+  it("D.15) spend_intent_idempotent — a retried key cannot pay twice", async () => {
+    const payee = anchor.web3.Keypair.generate();
+    const key = Array.from(anchor.web3.Keypair.generate().publicKey.toBytes().slice(0, 16));
+    const [spendReceipt] = PublicKey.findProgramAddressSync(
+      [Buffer.from("spend_receipt"), policyPda.toBuffer(), Buffer.from(key)],
+      program.programId
+    );
+    const send = (seq) =>
+      program.methods
+        .spendIntentIdempotent(new anchor.BN(1_000), key)
+        .accounts({
+          spend: {
+            auditEvent: auditPda(seq)[0],
+            policy: policyPda,
+            vault: vaultPda,
+            recipient: payee.publicKey,
+            caller: owner.publicKey,
+            systemProgram: SystemProgram.programId,
+          },
+          spendReceipt,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    const seq = nextSeq;
+    await send(seq);
+    nextSeq++;
+    const receipt = await program.account.spendReceipt.fetch(spendReceipt);
+    assert.ok(receipt.sequence.eqn(seq));
+    assert.strictEqual(receipt.outOfSequence, false);
+    const audit = await program.account.auditEvent.fetch(auditPda(seq)[0]);
+    assert.strictEqual(receipt.allowed, audit.allowed);
+    assert.strictEqual(receipt.reasonCode, audit.reasonCode);
+    assert.deepStrictEqual(Array.from(receipt.idempotencyKey), key);
+
+    // The retry gets a fresh audit sequence but the key is already spent.
+    try {
+      await send(nextSeq);
+      assert.fail("Should have failed: idempotency key already used");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("already in use"),
+        `Expected "already in use", got: ${err}`
+      );
+    }
+    const { nextSequence } = await program.account.policy.fetch(policyPda);
+    assert.ok(nextSequence.eqn(nextSeq));
+  });
+
+  it("C.61) set_recipient_lifetime_cap — caps the total ever paid to a recipient", async () => {
+    await program.methods
+      .setRecipientLifetimeCap(new anchor.BN(1_000))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const payee = anchor.web3.Keypair.generate();
+    const [recipientLifetime] = PublicKey.findProgramAddressSync(
+      [Buffer.from("recipient_lifetime"), policyPda.toBuffer(), payee.publicKey.toBuffer()],
+      program.programId
+    );
+    const audit = await spendV2(new anchor.BN(2_000), payee.publicKey, {
+      recipientLifetime,
+    });
+    assert.strictEqual(audit.allowed, false);
+    assert.strictEqual(audit.reasonCode, 35); // REASON_RECIPIENT_LIFETIME_CAP
+
+    // Without the carry-over PDA the cap cannot be checked.
+    try {
+      await spendV2(new anchor.BN(500), payee.publicKey);
+      assert.fail("Should have thrown RecipientLifetimeRequired");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("RecipientLifetimeRequired"),
+        `Expected RecipientLifetimeRequired, got: ${err}`
+      );
+    }
+
+    await program.methods
+      .setRecipientLifetimeCap(new anchor.BN(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+  });
+
+  it("C.2) set_policy_advanced — PolicyPaused/PolicyUnpaused only on a change", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const setPaused = (paused) =>
+      program.methods
+        .setPolicyAdvanced(
+          DAILY_BUDGET,
+          COOLDOWN_SECS,
+          null,
+          paused,
+          false,
+          null,
+          new anchor.BN(0),
+          0
+        )
+        .accounts({
+          policy: newPolicy,
+          vault: newVault,
+          authority: newOwner.publicKey,
+        })
+        .signers([newOwner])
+        .rpc();
+
+    const events = [];
+    const onPaused = program.addEventListener("policyPaused", (ev) =>
+      events.push(["paused", ev])
+    );
+    const onUnpaused = program.addEventListener("policyUnpaused", (ev) =>
+      events.push(["unpaused", ev])
+    );
+    await setPaused(true);
+    await setPaused(true); // already paused: no event
+    await setPaused(false);
+    await new Promise((r) => setTimeout(r, 1_000));
+    await program.removeEventListener(onPaused);
+    await program.removeEventListener(onUnpaused);
+
+    assert.deepStrictEqual(
+      events.map(([kind]) => kind),
+      ["paused", "unpaused"]
+    );
+    for (const [, ev] of events) {
+      assert.ok(ev.policy.equals(newPolicy));
+      assert.ok(ev.by.equals(newOwner.publicKey));
+    }
+  });
+
+  it("C.2b) set_policy_advanced — day_offset_seconds moves the day boundary", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const setOffset = (offset) =>
+      program.methods
+        .setPolicyAdvanced(
+          DAILY_BUDGET,
+          COOLDOWN_SECS,
+          null,
+          false,
+          false,
+          null,
+          new anchor.BN(0),
+          offset
+        )
+        .accounts({
+          policy: newPolicy,
+          vault: newVault,
+          authority: newOwner.publicKey,
+        })
+        .signers([newOwner])
+        .rpc();
+
+    try {
+      await setOffset(-86_400);
+      assert.fail("Should have thrown InvalidDayOffset");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidDayOffset"),
+        `Expected InvalidDayOffset error, got: ${err}`
+      );
+    }
+
+    // US Eastern (UTC-5); the index rounds down like Math.floor.
+    await setOffset(-18_000);
+    const { nextSequence } = await program.account.policy.fetch(newPolicy);
+    const [auditEvent] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("audit"),
+        newPolicy.toBuffer(),
+        nextSequence.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+    const before = Math.floor(Date.now() / 1000);
+    await program.methods
+      .depositAndSpend(new anchor.BN(2_000_000), new anchor.BN(1_000))
+      .accounts({
+        spend: {
+          auditEvent,
+          policy: newPolicy,
+          vault: newVault,
+          recipient: anchor.web3.Keypair.generate().publicKey,
+          caller: newOwner.publicKey,
+          systemProgram: SystemProgram.programId,
+        },
+        funder: newOwner.publicKey,
+      })
+      .signers([newOwner])
+      .rpc();
+    const after = Math.floor(Date.now() / 1000);
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.dayOffsetSeconds, -18_000);
+    const dayAt = (ts) => Math.floor((ts - 18_000) / 86_400);
+    assert.ok(
+      [dayAt(before - 60), dayAt(after + 60)].includes(policy.dayIndex.toNumber())
+    );
+  });
+
+  it("B.2) initialize_policy_with_recipient — allowlist on, payee registered", async () => {
+    const newOwner = anchor.web3.Keypair.generate();
+    const payee = anchor.web3.Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(
+      newOwner.publicKey,
+      LAMPORTS_PER_SOL / 10
+    );
+    await provider.connection.confirmTransaction(sig);
+
+    const [newVault] = PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), newOwner.publicKey.toBuffer()],
+      program.programId
+    );
+    const [newPolicy] = PublicKey.findProgramAddressSync(
+      [Buffer.from("policy"), newVault.toBuffer()],
+      program.programId
+    );
+    await program.methods
+      .initializeVault()
+      .accounts({
+        vault: newVault,
+        owner: newOwner.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([newOwner])
+      .rpc();
+
+    const accountsFor = (recipientPk) => ({
+      policy: newPolicy,
+      recipientAllow: PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("recipient_allow"),
+          newPolicy.toBuffer(),
+          recipientPk.toBuffer(),
+        ],
+        program.programId
+      )[0],
+      vault: newVault,
+      recipient: recipientPk,
+      owner: newOwner.publicKey,
+      systemProgram: SystemProgram.programId,
+    });
+
+    // The vault itself is not a valid payee.
+    try {
+      await program.methods
+        .initializePolicyWithRecipient(DAILY_BUDGET, COOLDOWN_SECS, null)
+        .accounts(accountsFor(newVault))
+        .signers([newOwner])
+        .rpc();
+      assert.fail("Should have thrown InvalidRecipient");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidRecipient"),
+        `Expected InvalidRecipient, got: ${err}`
+      );
+    }
+
+    const accounts = accountsFor(payee.publicKey);
+    await program.methods
+      .initializePolicyWithRecipient(DAILY_BUDGET, COOLDOWN_SECS, null)
+      .accounts(accounts)
+      .signers([newOwner])
+      .rpc();
+
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.allowlistEnabled, true);
+    assert.ok(policy.allowedRecipient.equals(payee.publicKey));
+    assert.ok(policy.dailyBudgetLamports.eq(DAILY_BUDGET));
+    assert.strictEqual(policy.policyVersion, 1);
+
+    const allow = await program.account.recipientAllow.fetch(accounts.recipientAllow);
+    assert.ok(allow.policy.equals(newPolicy));
+    assert.ok(allow.recipient.equals(payee.publicKey));
+    assert.ok(allow.registeredBy.equals(newOwner.publicKey));
+  });
+
+  it("D.8) SpendRecorded — each event carries the hash of the previous one", async () => {
+    const payee = anchor.web3.Keypair.generate();
+    const zero = new anchor.BN(0); // denied (INVALID_AMOUNT) but still recorded
+
+    await spendV2(zero, payee.publicKey);
+    const { lastEventHash } = await program.account.policy.fetch(policyPda);
+    assert.ok(lastEventHash.some((b) => b !== 0));
+
+    let listener;
+    const event = new Promise((resolve) => {
+      listener = program.addEventListener("spendRecorded", resolve);
+    });
+    await spendV2(zero, payee.publicKey);
+    const ev = await event;
+    await program.removeEventListener(listener);
+
+    assert.deepStrictEqual(ev.prevSequenceHash, lastEventHash);
+    const after = await program.account.policy.fetch(policyPda);
+    assert.notDeepStrictEqual(after.lastEventHash, lastEventHash);
+  });
+
+  it("G) get_policies_summary — returns packed state for passed policies", async () => {
+    const summaries = await program.methods
+      .getPoliciesSummary()
+      .remainingAccounts([
+        { pubkey: policyPda, isWritable: false, isSigner: false },
+      ])
+      .view();
+
+    const policy = await program.account.policy.fetch(policyPda);
+    assert.strictEqual(summaries.length, 1);
+    assert.ok(summaries[0].dailyBudgetLamports.eq(policy.dailyBudgetLamports));
+    assert.ok(
+      summaries[0].remainingTodayLamports.eq(
+        policy.dailyBudgetLamports.sub(policy.spentTodayLamports)
+      )
+    );
+    assert.strictEqual(summaries[0].paused, policy.paused);
+    assert.strictEqual(summaries[0].policyVersion, policyVersion);
+
+    // A non-policy account (the vault) is rejected rather than skipped.
+    try {
+      await program.methods
+        .getPoliciesSummary()
+        .remainingAccounts([
+          { pubkey: vaultPda, isWritable: false, isSigner: false },
+        ])
+        .view();
+      assert.fail("Should have thrown InvalidPolicyAccount");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidPolicyAccount"),
+        `Expected InvalidPolicyAccount error, got: ${err}`
+      );
+    }
+  });
+
+  it("H) project_eod_spend — projection never below today's spend", async () => {
+    const projected = await program.methods
+      .projectEodSpend()
+      .accounts({ policy: policyPda })
+      .view();
+    const policy = await program.account.policy.fetch(policyPda);
+    assert.ok(projected.gte(policy.spentTodayLamports));
+  });
+
+  it("I) verify_merkle_proof — proofs checked against the current root", async () => {
+    const { createHash } = require("crypto");
+    const sha = (...parts) => {
+      const h = createHash("sha256");
+      parts.forEach((p) => h.update(p));
+      return h.digest();
+    };
+    const a = anchor.web3.Keypair.generate().publicKey;
+    const b = anchor.web3.Keypair.generate().publicKey;
+    const [leafA, leafB] = [sha(a.toBuffer()), sha(b.toBuffer())];
+    const root =
+      Buffer.compare(leafA, leafB) <= 0 ? sha(leafA, leafB) : sha(leafB, leafA);
+
+    await program.methods
+      .setAllowlistMerkleRoot([...root])
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+
+    const verify = (recipient, proof) =>
+      program.methods
+        .verifyMerkleProof(recipient, proof.map((p) => [...p]))
+        .accounts({ policy: policyPda })
+        .view();
+    assert.strictEqual(await verify(a, [leafB]), true);
+    assert.strictEqual(await verify(b, [leafA]), true);
+    assert.strictEqual(await verify(owner.publicKey, [leafA]), false);
+
+    await program.methods
+      .setAllowlistMerkleRoot(Array(32).fill(0))
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    assert.strictEqual(await verify(a, [leafB]), false);
+  });
+
+  it("J) get_policy_flags — packs boolean settings into a bitmask", async () => {
+    const flags = () =>
+      program.methods.getPolicyFlags().accounts({ policy: policyPda }).view();
+    const base = await flags();
+
+    await program.methods
+      .setOneSpendPerSlot(true)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    assert.strictEqual(await flags(), base | (1 << 4)); // FLAG_ONE_SPEND_PER_SLOT
+
+    await program.methods
+      .setOneSpendPerSlot(false)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    assert.strictEqual(await flags(), base);
+  });
+
+  it("L) check_solvency — outstanding allowances count against the vault", async () => {
+    const payee = anchor.web3.Keypair.generate();
+    const [allowancePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("allowance"), policyPda.toBuffer(), payee.publicKey.toBuffer()],
+      program.programId
+    );
+    const CEILING = new anchor.BN(2_000_000);
+    await program.methods
+      .setAllowance(new anchor.BN(10_000_000), CEILING)
+      .accounts({
+        allowance: allowancePda,
+        ...setterAccounts(),
+        recipient: payee.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    // Past one second the allowance sits at its ceiling.
+    await new Promise((r) => setTimeout(r, 1_500));
+
+    const solvency = (accounts) =>
+      program.methods
+        .checkSolvency()
+        .accounts({ policy: policyPda, vault: vaultPda })
+        .remainingAccounts(
+          accounts.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }))
+        )
+        .view();
+
+    const free = await solvency([]);
+    const withAllowance = await solvency([allowancePda]);
+    assert.ok(free.sub(withAllowance).eq(CEILING));
+
+    // A non-commitment account (the policy) is rejected rather than skipped.
+    try {
+      await solvency([policyPda]);
+      assert.fail("Should have thrown InvalidCommitmentAccount");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidCommitmentAccount"),
+        `Expected InvalidCommitmentAccount, got: ${err}`
+      );
+    }
+  });
+
+  it("M) derive_addresses — matches client-side PDA derivation", async () => {
+    const derived = await program.methods
+      .deriveAddresses(owner.publicKey)
+      .accounts({ policy: policyPda })
+      .view();
+    assert.ok(derived.vault.equals(vaultPda));
+    assert.ok(derived.policy.equals(policyPda));
+    assert.ok(derived.nextAuditEvent.equals(auditPda(nextSeq)[0]));
+
+    const bare = await program.methods
+      .deriveAddresses(owner.publicKey)
+      .accounts({ policy: null })
+      .view();
+    assert.ok(bare.vault.equals(vaultPda));
+    assert.strictEqual(bare.nextAuditEvent, null);
+  });
+
+  it("N) export_audit_events — re-emits then closes audit PDAs", async () => {
+    const payee = anchor.web3.Keypair.generate();
+    const keys = [];
+    for (let i = 0; i < 2; i++) {
+      keys.push(auditPda(nextSeq)[0]);
+      await spendV2(new anchor.BN(0), payee.publicKey);
+    }
+
+    const exported = [];
+    const listener = program.addEventListener("auditExported", (ev) =>
+      exported.push(ev)
+    );
+    await program.methods
+      .exportAuditEvents(true)
+      .accounts({ policy: policyPda, authority: owner.publicKey })
+      .remainingAccounts(
+        keys.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }))
+      )
+      .rpc();
+    await new Promise((r) => setTimeout(r, 1_000));
+    await program.removeEventListener(listener);
+
+    assert.strictEqual(exported.length, 2);
+    exported.forEach((ev, i) => {
+      assert.ok(ev.auditEvent.equals(keys[i]));
+      assert.strictEqual(ev.reasonCode, 4); // REASON_INVALID_AMOUNT
+    });
+    for (const key of keys) {
+      assert.strictEqual(await provider.connection.getAccountInfo(key), null);
+    }
+  });
+
+  it("E.8) close_audit_events_batch — closes several audit PDAs at once", async () => {
+    const payee = anchor.web3.Keypair.generate();
+    const keys = [];
+    for (let i = 0; i < 3; i++) {
+      keys.push(auditPda(nextSeq)[0]);
+      await spendV2(new anchor.BN(0), payee.publicKey);
+    }
+    const authorityBefore = await provider.connection.getBalance(owner.publicKey);
+
+    await program.methods
+      .closeAuditEventsBatch()
+      .accounts({ policy: policyPda, authority: owner.publicKey })
+      .remainingAccounts(
+        keys.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }))
+      )
+      .rpc();
+
+    for (const key of keys) {
+      assert.strictEqual(await provider.connection.getAccountInfo(key), null);
+    }
+    assert.ok(
+      (await provider.connection.getBalance(owner.publicKey)) > authorityBefore
+    );
+
+    // An empty batch is rejected.
+    try {
+      await program.methods
+        .closeAuditEventsBatch()
+        .accounts({ policy: policyPda, authority: owner.publicKey })
+        .rpc();
+      assert.fail("Should have thrown InvalidAuditCloseBatch");
+    } catch (err) {
+      assert.ok(
+        err.toString().includes("InvalidAuditCloseBatch"),
+        `Expected InvalidAuditCloseBatch, got: ${err}`
+      );
+    }
+  });
+
+  it("O) record_policy_version — one digest entry per policy version", async () => {
+    const [historyPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("version_history"), policyPda.toBuffer()],
+      program.programId
+    );
+    const record = () =>
+      program.methods
+        .recordPolicyVersion()
+        .accounts({
+          versionHistory: historyPda,
+          policy: policyPda,
+          payer: owner.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+    await record();
+    await record(); // same version: no new entry
+    let history = await program.account.versionHistory.fetch(historyPda);
+    const len = history.entries.length;
+    assert.strictEqual(history.entries[len - 1].policyVersion, policyVersion);
+
+    const before = await program.account.policy.fetch(policyPda);
+    await program.methods
+      .setPolicy(before.dailyBudgetLamports, before.cooldownSeconds, before.agent)
+      .accounts(setterAccounts())
+      .rpc();
+    policyVersion += 1;
+    await record();
+
+    history = await program.account.versionHistory.fetch(historyPda);
+    assert.strictEqual(history.entries.length, len + 1);
+    assert.strictEqual(history.entries[len].policyVersion, policyVersion);
+  });
+
+  it("P) seconds_until_spendable — never longer than the cooldown", async () => {
+    const wait = await program.methods
+      .secondsUntilSpendable()
+      .accounts({ policy: policyPda })
+      .view();
+    const policy = await program.account.policy.fetch(policyPda);
+    assert.strictEqual(policy.paused, false);
+    assert.ok(wait.gte(new anchor.BN(0)));
+    assert.ok(wait.lte(new anchor.BN(policy.cooldownSeconds)));
+  });
+
+  it("Q) remaining_budget — today's headroom, cooldown and pause", async () => {
+    const status = await program.methods
+      .remainingBudget()
+      .accounts({ policy: policyPda })
+      .view();
+    const policy = await program.account.policy.fetch(policyPda);
+    assert.strictEqual(status.paused, policy.paused);
+    assert.ok(status.remainingToday.lte(policy.dailyBudgetLamports));
+    assert.ok(status.secondsUntilCooldownClears.gte(new anchor.BN(0)));
+    const cooldown = new anchor.BN(policy.cooldownSeconds);
+    assert.ok(status.secondsUntilCooldownClears.lte(cooldown));
+  });
+
+  it("R) spendable_balance — balance above rent, escrow and the reserve floor", async () => {
+    const spendable = await program.methods
+      .spendableBalance()
+      .accounts({ policy: policyPda, vault: vaultPda })
+      .view();
+    const policy = await program.account.policy.fetch(policyPda);
+    const balance = await provider.connection.getBalance(vaultPda);
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(41); // Vault::SIZE
+    const floor = Math.max(rent, policy.minReserveLamports.toNumber());
+    const held = policy.timelockedLamports.toNumber();
+    assert.strictEqual(spendable.toNumber(), Math.max(balance - held - floor, 0));
+  });
+
+  it("S) simulate_spend — reports the decision without recording it", async () => {
+    const { newOwner, newVault, newPolicy } = await freshPolicy();
+    const simulate = (amount) =>
+      program.methods
+        .simulateSpend(new anchor.BN(amount))
+        .accounts({
+          policy: newPolicy,
+          vault: newVault,
+          recipient: anchor.web3.Keypair.generate().publicKey,
+          caller: newOwner.publicKey,
+        })
+        .view();
+
+    const ok = await simulate(1_000_000);
+    assert.strictEqual(ok.allowed, true);
+    assert.strictEqual(ok.reasonCode, 1); // REASON_OK
+    const over = await simulate(DAILY_BUDGET.toNumber() + 1);
+    assert.strictEqual(over.allowed, false);
+    assert.strictEqual(over.reasonCode, 2); // REASON_BUDGET_EXCEEDED
+
+    const policy = await program.account.policy.fetch(newPolicy);
+    assert.strictEqual(policy.nextSequence.toNumber(), 0);
+    assert.strictEqual(policy.spentTodayLamports.toNumber(), 0);
+  });
 });